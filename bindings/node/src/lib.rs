@@ -0,0 +1,128 @@
+use docktor::diff::DockDiff;
+use docktor::dock::{Dock, DockItem, Section};
+use docktor::merge::Strategy;
+use docktor::profile::{ApplyOptions, Profile};
+use napi::{Error, Result};
+use napi_derive::napi;
+
+/// A Dock tile as seen from JavaScript.
+#[napi(object)]
+pub struct DockTile {
+    /// The label shown under the tile.
+    pub label: String,
+
+    /// The plist tile type, e.g. `file-tile` or `spacer-tile`.
+    pub kind: String,
+
+    /// The app's bundle identifier, if the tile is an app.
+    pub bundle_id: Option<String>,
+
+    /// The local path the tile points to, if any.
+    pub path: Option<String>,
+}
+
+/// Both sections of the Dock.
+#[napi(object)]
+pub struct DockContents {
+    pub apps: Vec<DockTile>,
+    pub others: Vec<DockTile>,
+}
+
+/// One difference between the live Dock and a profile.
+#[napi(object)]
+pub struct DockChange {
+    /// One of `added`, `removed`, `moved`, `relabeled`, `relocated`, `restyled`, or
+    /// `setting`.
+    pub change: String,
+
+    /// `apps` or `others`; unset for setting changes.
+    pub section: Option<String>,
+
+    /// The tile label, or the setting key.
+    pub label: String,
+
+    /// The tile's previous index, for removed and moved tiles.
+    pub from: Option<u32>,
+
+    /// The tile's new index, for added, moved, relabeled, relocated, and restyled tiles.
+    pub to: Option<u32>,
+}
+
+/// Options for [`apply`].
+#[napi(object)]
+pub struct ApplyProfileOptions {
+    /// Compute the changes without writing anything.
+    pub dry_run: Option<bool>,
+
+    /// Save the changes without restarting the Dock.
+    pub no_restart: Option<bool>,
+
+    /// Keep changes made to the Dock since the last apply.
+    pub merge: Option<bool>,
+}
+
+/// Loads the current user's Dock.
+#[napi]
+pub fn load() -> Result<DockContents> {
+    let dock = Dock::load().map_err(to_js_error)?;
+    Ok(DockContents {
+        apps: dock.section(Section::Apps).iter().map(tile).collect(),
+        others: dock.section(Section::Others).iter().map(tile).collect(),
+    })
+}
+
+/// Lists what applying the profile at `profilePath` would change.
+#[napi]
+pub fn diff(profile_path: String) -> Result<Vec<DockChange>> {
+    let profile = Profile::load(&profile_path).map_err(to_js_error)?;
+    let live = Dock::load().map_err(to_js_error)?;
+    let desired = profile.apply_to(&live).map_err(to_js_error)?;
+    Ok(changes(&DockDiff::between(&live, &desired)))
+}
+
+/// Applies the profile at `profilePath` to the live Dock and lists what changed.
+#[napi]
+pub fn apply(
+    profile_path: String,
+    options: Option<ApplyProfileOptions>,
+) -> Result<Vec<DockChange>> {
+    let options = options.map_or_else(ApplyOptions::default, |options| ApplyOptions {
+        dry_run: options.dry_run.unwrap_or_default(),
+        no_restart: options.no_restart.unwrap_or_default(),
+        strategy: if options.merge.unwrap_or_default() {
+            Strategy::Merge
+        } else {
+            Strategy::Replace
+        },
+        ..ApplyOptions::default()
+    });
+    let profile = Profile::load(&profile_path).map_err(to_js_error)?;
+    let diff = profile.apply(options).map_err(to_js_error)?;
+    Ok(changes(&diff))
+}
+
+fn tile(item: &DockItem) -> DockTile {
+    DockTile {
+        label: item.label().to_string(),
+        kind: item.kind.name().to_string(),
+        bundle_id: item.metadata.bundle_id.clone(),
+        path: item.path().map(|path| path.display().to_string()),
+    }
+}
+
+fn changes(diff: &DockDiff) -> Vec<DockChange> {
+    diff.changes
+        .iter()
+        .map(|change| DockChange {
+            change: change.kind().to_string(),
+            section: change.section().map(|section| section.id().to_string()),
+            label: change.label().to_string(),
+            from: change.from().map(|index| index as u32),
+            to: change.to().map(|index| index as u32),
+        })
+        .collect()
+}
+
+fn to_js_error(err: anyhow::Error) -> Error {
+    Error::from_reason(format!("{err:#}"))
+}
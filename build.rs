@@ -0,0 +1,42 @@
+fn main() {
+    record_build_info();
+    #[cfg(feature = "ffi")]
+    generate_header();
+}
+
+/// Passes the git commit and target triple to the crate for `docktor version`.
+fn record_build_info() {
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs/heads");
+    let commit = std::process::Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_default();
+    println!("cargo:rustc-env=DOCKTOR_GIT_COMMIT={commit}");
+    let target = std::env::var("TARGET").expect("TARGET is set by cargo");
+    println!("cargo:rustc-env=DOCKTOR_TARGET={target}");
+}
+
+/// Writes the C header for the `ffi` module to `include/docktor.h`.
+///
+/// Only `src/ffi.rs` is parsed, so the header holds the C API and nothing else.
+#[cfg(feature = "ffi")]
+fn generate_header() {
+    let crate_dir =
+        std::env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR is set by cargo");
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+
+    cbindgen::Builder::new()
+        .with_src(format!("{crate_dir}/src/ffi.rs"))
+        .with_config(
+            cbindgen::Config::from_file(format!("{crate_dir}/cbindgen.toml"))
+                .expect("cbindgen.toml is valid"),
+        )
+        .generate()
+        .expect("Failed to generate C bindings")
+        .write_to_file(format!("{crate_dir}/include/docktor.h"));
+}
@@ -0,0 +1,71 @@
+#ifndef DOCKTOR_H
+#define DOCKTOR_H
+
+/* Generated by cbindgen from src/ffi.rs; do not edit. */
+
+#include <stddef.h>
+
+// An opaque handle to a Dock configuration.
+typedef struct DocktorDock DocktorDock;
+
+// Returns the message for the most recent error on this thread, or `NULL`.
+//
+// The string is owned by docktor and valid until the next failing call on this thread.
+const char *docktor_last_error(void);
+
+// Creates an empty Dock. Free it with [`docktor_dock_free`].
+struct DocktorDock *docktor_dock_new(void);
+
+// Loads the current user's Dock, or returns `NULL` on error.
+struct DocktorDock *docktor_dock_load(void);
+
+// Loads a Dock from the plist at `path`, or returns `NULL` on error.
+//
+// # Safety
+//
+// `path` must be a valid NUL-terminated string.
+struct DocktorDock *docktor_dock_load_from(const char *path);
+
+// Appends the `.app` bundle at `app_path` to the Dock unless it is already there.
+// Returns 0 on success, -1 on error.
+//
+// # Safety
+//
+// `dock` must come from one of the `docktor_dock_*` constructors and not be freed;
+// `app_path` must be a valid NUL-terminated string.
+int docktor_dock_add_app(struct DocktorDock *dock, const char *app_path);
+
+// Removes items whose label or bundle id is `query`. Returns the number removed, or -1 on error.
+//
+// # Safety
+//
+// `dock` must come from one of the `docktor_dock_*` constructors and not be freed;
+// `query` must be a valid NUL-terminated string.
+int docktor_dock_remove(struct DocktorDock *dock, const char *query);
+
+// Saves the Dock to the current user's preferences. Returns 0 on success, -1 on error.
+//
+// # Safety
+//
+// `dock` must come from one of the `docktor_dock_*` constructors and not be freed.
+int docktor_dock_save(const struct DocktorDock *dock);
+
+// Saves the Dock to the plist at `path`. Returns 0 on success, -1 on error.
+//
+// # Safety
+//
+// `dock` must come from one of the `docktor_dock_*` constructors and not be freed;
+// `path` must be a valid NUL-terminated string.
+int docktor_dock_save_to(const struct DocktorDock *dock, const char *path);
+
+// Restarts the Dock so saved changes take effect. Returns 0 on success, -1 on error.
+int docktor_restart(void);
+
+// Frees a Dock handle. Passing `NULL` is a no-op.
+//
+// # Safety
+//
+// `dock` must come from one of the `docktor_dock_*` constructors and not already be freed.
+void docktor_dock_free(struct DocktorDock *dock);
+
+#endif  /* DOCKTOR_H */
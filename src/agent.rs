@@ -0,0 +1,174 @@
+//! A launch agent that snapshots the Dock once a day, building up a history of it
+//! without anyone having to remember to save.
+//!
+//! The agent runs `docktor snapshot save --if-changed --tag daily`, so days the Dock
+//! didn't change don't add a snapshot, and the retention limits in the config file
+//! keep the history from growing forever.
+
+use crate::config;
+use anyhow::{Context, Result, bail};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The launchd label the agent is installed under.
+pub const LABEL: &str = "com.github.targendaz2.docktor.snapshot";
+
+/// The tag daily snapshots are saved with.
+pub const TAG: &str = "daily";
+
+/// A time of day, parsed from `HH:MM`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeOfDay {
+    /// The hour, from 0 to 23.
+    pub hour: u8,
+
+    /// The minute, from 0 to 59.
+    pub minute: u8,
+}
+
+impl Default for TimeOfDay {
+    fn default() -> Self {
+        TimeOfDay {
+            hour: 12,
+            minute: 0,
+        }
+    }
+}
+
+impl std::str::FromStr for TimeOfDay {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (hour, minute) = s.split_once(':').unwrap_or((s, "0"));
+        match (hour.parse(), minute.parse()) {
+            (Ok(hour @ 0..=23), Ok(minute @ 0..=59)) => Ok(TimeOfDay { hour, minute }),
+            _ => bail!("{s:?} isn't a time of day; use HH:MM, e.g. 09:30"),
+        }
+    }
+}
+
+impl std::fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:02}:{:02}", self.hour, self.minute)
+    }
+}
+
+/// Where the agent's launchd definition lives, e.g.
+/// `~/Library/LaunchAgents/com.github.targendaz2.docktor.snapshot.plist`.
+pub fn plist_path() -> Result<PathBuf> {
+    Ok(crate::paths::home_dir()
+        .context("Could not determine home directory")?
+        .join("Library/LaunchAgents")
+        .join(format!("{LABEL}.plist")))
+}
+
+/// The launchd definition that runs `program` to take a snapshot every day at `at`.
+pub fn definition(program: &Path, at: TimeOfDay) -> Result<plist::Dictionary> {
+    let mut interval = plist::Dictionary::new();
+    interval.insert("Hour".into(), u64::from(at.hour).into());
+    interval.insert("Minute".into(), u64::from(at.minute).into());
+
+    let mut agent = plist::Dictionary::new();
+    agent.insert("Label".into(), LABEL.into());
+    agent.insert(
+        "ProgramArguments".into(),
+        plist::Value::Array(
+            [
+                &program.display().to_string(),
+                "--quiet",
+                "snapshot",
+                "save",
+                "--if-changed",
+                "--tag",
+                TAG,
+            ]
+            .into_iter()
+            .map(plist::Value::from)
+            .collect(),
+        ),
+    );
+    agent.insert("StartCalendarInterval".into(), interval.into());
+    agent.insert(
+        "StandardErrorPath".into(),
+        config::state_dir()?
+            .join("agent.log")
+            .display()
+            .to_string()
+            .into(),
+    );
+    Ok(agent)
+}
+
+/// Installs and loads the agent, replacing any earlier installation, and returns
+/// where its definition was written.
+///
+/// It runs the docktor binary doing the installing, so reinstall after moving it.
+pub fn install(at: TimeOfDay) -> Result<PathBuf> {
+    crate::macos::ensure("Installing a launch agent")?;
+    let path = plist_path()?;
+    let program = std::env::current_exe().context("Failed to find the docktor binary")?;
+    let definition = definition(&program, at)?;
+
+    if path.exists() {
+        unload(&path);
+    }
+    // launchd won't create the folder the log goes in.
+    for dir in [
+        path.parent().map(Path::to_path_buf),
+        Some(config::state_dir()?),
+    ]
+    .into_iter()
+    .flatten()
+    {
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    plist::to_file_xml(&path, &plist::Value::Dictionary(definition))
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+
+    let output = Command::new("launchctl")
+        .args(["bootstrap", &domain()?])
+        .arg(&path)
+        .output()
+        .context("Failed to run launchctl")?;
+    if !output.status.success() {
+        bail!(
+            "Loading the agent failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(path)
+}
+
+/// Unloads and removes the agent. Returns `false` if it wasn't installed.
+pub fn uninstall() -> Result<bool> {
+    let path = plist_path()?;
+    if !path.exists() {
+        return Ok(false);
+    }
+    unload(&path);
+    std::fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+    Ok(true)
+}
+
+/// Stops launchd running the agent at `path`. Failure only means it wasn't loaded.
+fn unload(path: &Path) {
+    if let Ok(domain) = domain() {
+        let _ = Command::new("launchctl")
+            .args(["bootout", &domain])
+            .arg(path)
+            .output();
+    }
+}
+
+/// The launchd domain of the current user's session, e.g. `gui/501`.
+fn domain() -> Result<String> {
+    let output = Command::new("id")
+        .arg("-u")
+        .output()
+        .context("Failed to run id")?;
+    Ok(format!(
+        "gui/{}",
+        String::from_utf8_lossy(&output.stdout).trim()
+    ))
+}
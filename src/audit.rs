@@ -0,0 +1,149 @@
+//! A log of every write docktor makes to the Dock, for tracing a tile back to the
+//! command that put it there.
+//!
+//! Each write appends one JSON object to `audit.log` in the state directory, holding
+//! when it happened, the command line, the profile if one was applied, and which
+//! tiles were added and removed.
+
+use crate::config;
+use crate::diff::{Change, DockDiff};
+use crate::dock::{DockItem, Section};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::PathBuf;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+
+/// One write to the Dock.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    /// When the write happened, in RFC 3339.
+    pub at: String,
+
+    /// The command that made it, e.g. `docktor add Firefox`.
+    pub operation: String,
+
+    /// The profile applied, if the write came from one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+
+    /// The diff's summary, e.g. `+1, −2`.
+    pub summary: String,
+
+    /// The tiles the write added.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub added: Vec<Tile>,
+
+    /// The tiles the write removed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed: Vec<Tile>,
+}
+
+/// A tile as the audit log remembers it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tile {
+    /// `apps` or `others`.
+    pub section: String,
+
+    /// The tile's [identity](DockItem::identity).
+    pub identity: String,
+
+    /// The tile's label when it was written.
+    pub label: String,
+}
+
+impl Tile {
+    fn new(section: Section, item: &DockItem) -> Self {
+        Tile {
+            section: section.id().to_string(),
+            identity: item.identity().into_owned(),
+            label: item.label().to_string(),
+        }
+    }
+
+    /// Whether this is `item` in `section`.
+    pub fn is(&self, section: Section, item: &DockItem) -> bool {
+        self.section == section.id() && self.identity == item.identity()
+    }
+}
+
+/// Where the log is kept.
+pub fn path() -> Result<PathBuf> {
+    Ok(config::state_dir()?.join("audit.log"))
+}
+
+/// Appends an entry for a write that made `diff`, while applying `profile` if given.
+pub(crate) fn record(diff: &DockDiff, profile: Option<&str>) -> Result<()> {
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    for change in &diff.changes {
+        match change {
+            Change::Added { section, item, .. } => added.push(Tile::new(*section, item)),
+            Change::Removed { section, item, .. } => removed.push(Tile::new(*section, item)),
+            _ => {}
+        }
+    }
+    let entry = Entry {
+        at: OffsetDateTime::now_utc()
+            .replace_nanosecond(0)
+            .unwrap_or_else(|_| OffsetDateTime::now_utc())
+            .format(&Rfc3339)
+            .context("Failed to format the time")?,
+        operation: operation(),
+        profile: profile.map(str::to_string),
+        summary: diff.summary(),
+        added,
+        removed,
+    };
+
+    let path = path()?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    writeln!(file, "{}", serde_json::to_string(&entry)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Every entry in the log, oldest first. Lines that can't be read are skipped.
+pub fn entries() -> Result<Vec<Entry>> {
+    let path = path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let text = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(text
+        .lines()
+        .filter_map(|line| match serde_json::from_str(line) {
+            Ok(entry) => Some(entry),
+            Err(err) => {
+                tracing::warn!("skipping unreadable audit log line: {err}");
+                None
+            }
+        })
+        .collect())
+}
+
+/// The command line of this process, with the program shortened to its file name.
+fn operation() -> String {
+    let mut args = std::env::args();
+    let program = args
+        .next()
+        .map(|program| {
+            PathBuf::from(&program)
+                .file_name()
+                .map_or(program.clone(), |name| name.to_string_lossy().into_owned())
+        })
+        .unwrap_or_default();
+    std::iter::once(program)
+        .chain(args)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,163 @@
+//! Tracing each tile in the Dock back to when it appeared and what put it there.
+//!
+//! The [audit log](crate::audit) answers this for tiles docktor added. Tiles added
+//! some other way, or before the log existed, are dated by the oldest snapshot in the
+//! unbroken run of snapshots that contain them.
+
+use crate::audit::{self, Entry};
+use crate::dock::{Dock, DockItem, Section};
+use crate::snapshot::Snapshot;
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// Where one tile came from.
+#[derive(Debug, Clone, Serialize)]
+pub struct Blame {
+    /// The section the tile is in.
+    #[serde(serialize_with = "section_id")]
+    pub section: Section,
+
+    /// The tile's position in its section.
+    pub index: usize,
+
+    /// The tile's label.
+    pub label: String,
+
+    /// How the tile got there.
+    pub origin: Origin,
+}
+
+/// How a tile got into the Dock.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "source", rename_all = "kebab-case")]
+pub enum Origin {
+    /// docktor added it; this is the latest write that did.
+    Logged {
+        /// When, in RFC 3339.
+        at: String,
+
+        /// The command that added it.
+        operation: String,
+
+        /// The profile being applied, if any.
+        profile: Option<String>,
+    },
+
+    /// It has been in every snapshot since `since`, but no logged write added it.
+    Snapshot {
+        /// The oldest snapshot in the run.
+        since: String,
+
+        /// Whether `since` is the oldest snapshot there is, so the tile may be older.
+        oldest: bool,
+    },
+
+    /// It isn't in the latest snapshot or the log, so it was added outside docktor since.
+    Unknown,
+}
+
+impl std::fmt::Display for Origin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Origin::Logged {
+                at,
+                operation,
+                profile,
+            } => {
+                write!(f, "{at}  {operation}")?;
+                if let Some(profile) = profile {
+                    write!(f, " (profile {profile})")?;
+                }
+                Ok(())
+            }
+            Origin::Snapshot {
+                since,
+                oldest: true,
+            } => write!(f, "before snapshot {since}, the oldest"),
+            Origin::Snapshot { since, .. } => write!(f, "since snapshot {since}, outside docktor"),
+            Origin::Unknown => write!(f, "added outside docktor since the latest snapshot"),
+        }
+    }
+}
+
+/// Works out where every tile in `dock` came from, from the audit log and snapshots.
+///
+/// A tile added, removed, and added again is blamed on the last addition. Snapshots
+/// that fail their checksum are skipped.
+pub fn blame(dock: &Dock) -> Result<Vec<Blame>> {
+    let entries = audit::entries()?;
+    let snapshots: Vec<(String, HashSet<(&'static str, String)>)> = Snapshot::list()?
+        .into_iter()
+        .filter_map(|snapshot| match snapshot.load() {
+            Ok(saved) => Some((snapshot.id, tiles(&saved))),
+            Err(err) => {
+                tracing::warn!("skipping snapshot {}: {err:#}", snapshot.id);
+                None
+            }
+        })
+        .collect();
+
+    let mut blames = Vec::new();
+    for section in Section::ALL {
+        for (index, item) in dock.section(section).iter().enumerate() {
+            let key = (section.id(), item.identity().into_owned());
+            let origin = match last_addition(&entries, section, item) {
+                Some(entry) => Origin::Logged {
+                    at: entry.at.clone(),
+                    operation: entry.operation.clone(),
+                    profile: entry.profile.clone(),
+                },
+                None => {
+                    let run: Vec<&String> = snapshots
+                        .iter()
+                        .rev()
+                        .take_while(|(_, tiles)| tiles.contains(&key))
+                        .map(|(id, _)| id)
+                        .collect();
+                    match run.last() {
+                        Some(since) => Origin::Snapshot {
+                            since: since.to_string(),
+                            oldest: run.len() == snapshots.len(),
+                        },
+                        None => Origin::Unknown,
+                    }
+                }
+            };
+            blames.push(Blame {
+                section,
+                index,
+                label: item.label().to_string(),
+                origin,
+            });
+        }
+    }
+    Ok(blames)
+}
+
+/// The latest logged write that added `item` to `section`, unless a later one removed
+/// it, in which case it came back some other way.
+fn last_addition<'a>(entries: &'a [Entry], section: Section, item: &DockItem) -> Option<&'a Entry> {
+    let touches = |tiles: &[audit::Tile]| tiles.iter().any(|tile| tile.is(section, item));
+    entries
+        .iter()
+        .rev()
+        .find(|entry| touches(&entry.added) || touches(&entry.removed))
+        .filter(|entry| touches(&entry.added))
+}
+
+/// Every tile in `dock`, keyed by section and identity.
+fn tiles(dock: &Dock) -> HashSet<(&'static str, String)> {
+    Section::ALL
+        .into_iter()
+        .flat_map(|section| {
+            dock.section(section)
+                .iter()
+                .map(move |item| (section.id(), item.identity().into_owned()))
+        })
+        .collect()
+}
+
+fn section_id<S: serde::Serializer>(section: &Section, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(section.id())
+}
@@ -0,0 +1,101 @@
+//! What exactly this copy of docktor is, for support requests and MDM inventory.
+
+use crate::macos::{self, Version};
+use serde::Serialize;
+use std::fmt;
+
+/// The version, commit, target, and features docktor was built with.
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildInfo {
+    /// The crate version, e.g. `0.1.0`.
+    pub version: &'static str,
+
+    /// The abbreviated git commit it was built from, if it was built from a checkout.
+    pub commit: Option<&'static str>,
+
+    /// The target triple, e.g. `aarch64-apple-darwin`.
+    pub target: &'static str,
+
+    /// The macOS versions it supports.
+    pub macos: MacosRange,
+
+    /// The cargo features it was built with.
+    pub features: Vec<&'static str>,
+}
+
+/// The macOS versions docktor supports, and the one it is running on.
+#[derive(Debug, Clone, Serialize)]
+pub struct MacosRange {
+    /// The oldest supported version.
+    pub oldest_supported: String,
+
+    /// The newest version tested.
+    pub newest_tested: String,
+
+    /// The version running now, if it could be determined.
+    pub running: Option<String>,
+}
+
+impl BuildInfo {
+    /// Describes the running binary.
+    pub fn current() -> Self {
+        let mut features = Vec::new();
+        if cfg!(feature = "cli") {
+            features.push("cli");
+        }
+        if cfg!(feature = "ffi") {
+            features.push("ffi");
+        }
+        if cfg!(feature = "async") {
+            features.push("async");
+        }
+        if cfg!(feature = "render") {
+            features.push("render");
+        }
+        if cfg!(feature = "dirs") {
+            features.push("dirs");
+        }
+        if cfg!(feature = "notify") {
+            features.push("notify");
+        }
+        if cfg!(feature = "network") {
+            features.push("network");
+        }
+
+        BuildInfo {
+            version: env!("CARGO_PKG_VERSION"),
+            commit: Some(env!("DOCKTOR_GIT_COMMIT")).filter(|commit| !commit.is_empty()),
+            target: env!("DOCKTOR_TARGET"),
+            macos: MacosRange {
+                oldest_supported: macos::OLDEST_SUPPORTED.to_string(),
+                newest_tested: macos::NEWEST_TESTED.to_string(),
+                running: macos::version().as_ref().map(Version::to_string),
+            },
+            features,
+        }
+    }
+}
+
+impl fmt::Display for BuildInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "docktor {}", self.version)?;
+        if let Some(commit) = self.commit {
+            write!(f, " ({commit})")?;
+        }
+        writeln!(f)?;
+        writeln!(f, "Target:   {}", self.target)?;
+        writeln!(
+            f,
+            "macOS:    {} to {}, running {}",
+            self.macos.oldest_supported,
+            self.macos.newest_tested,
+            self.macos.running.as_deref().unwrap_or("unknown")
+        )?;
+        let features = if self.features.is_empty() {
+            "none".to_string()
+        } else {
+            self.features.join(", ")
+        };
+        writeln!(f, "Features: {features}")
+    }
+}
@@ -0,0 +1,382 @@
+//! Tidying up a Dock that has collected duplicate tiles, tiles for things that are
+//! gone, oddly written URLs, broken GUIDs, and tiles for stray copies of an app.
+//!
+//! Each fix can be turned on separately with [`CleanOptions`]; [`Dock::clean`]
+//! applies the chosen ones and reports everything it did in a [`CleanReport`].
+
+use crate::dock::{Dock, DockItem, FileLocation, Section};
+use crate::mac_app;
+use crate::paths::VolumeState;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::path::PathBuf;
+
+/// The tile key the Dock keeps a tile's GUID under.
+const GUID_KEY: &str = "GUID";
+
+/// Which fixes [`Dock::clean`] applies.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CleanOptions {
+    /// Remove all but the first tile with the same identity in each section.
+    pub dedupe: bool,
+
+    /// Remove tiles whose file no longer exists, unless its volume is only unmounted.
+    pub prune_broken: bool,
+
+    /// Rewrite `file://` URLs the way the Dock writes them.
+    pub normalize_urls: bool,
+
+    /// Drop GUIDs that are malformed or shared with another tile, so the Dock assigns new ones.
+    pub repair_guids: bool,
+
+    /// Where tiles share a bundle identifier but point at different copies of the app,
+    /// keep only the one in the most preferred install location.
+    pub canonical_copies: bool,
+}
+
+impl CleanOptions {
+    /// Every fix turned on.
+    pub fn all() -> Self {
+        CleanOptions {
+            dedupe: true,
+            prune_broken: true,
+            normalize_urls: true,
+            repair_guids: true,
+            canonical_copies: true,
+        }
+    }
+}
+
+/// One thing [`Dock::clean`] fixed. `index` is the tile's position before cleaning.
+#[derive(Debug, Clone)]
+pub enum Fix {
+    /// A tile identical to an earlier one in the same section was removed.
+    Duplicate {
+        section: Section,
+        index: usize,
+        label: String,
+    },
+
+    /// A tile pointing at a file that no longer exists was removed.
+    Broken {
+        section: Section,
+        index: usize,
+        label: String,
+        path: PathBuf,
+    },
+
+    /// A tile's URL was rewritten in the Dock's own form.
+    NormalizedUrl {
+        section: Section,
+        index: usize,
+        label: String,
+        old: String,
+        new: String,
+    },
+
+    /// A tile's GUID was dropped because it was malformed or not unique.
+    RepairedGuid {
+        section: Section,
+        index: usize,
+        label: String,
+        reason: String,
+    },
+
+    /// A tile for one copy of an app was removed in favor of the tile for `kept`.
+    OtherCopy {
+        section: Section,
+        index: usize,
+        label: String,
+        path: PathBuf,
+        kept: PathBuf,
+    },
+}
+
+impl Fix {
+    /// The heading this fix is listed under in a report.
+    fn heading(&self) -> &'static str {
+        match self {
+            Fix::Duplicate { .. } => "Duplicates",
+            Fix::Broken { .. } => "Broken",
+            Fix::NormalizedUrl { .. } => "URLs",
+            Fix::RepairedGuid { .. } => "GUIDs",
+            Fix::OtherCopy { .. } => "Copies",
+        }
+    }
+}
+
+impl fmt::Display for Fix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Fix::Duplicate {
+                section,
+                index,
+                label,
+            } => write!(f, "- {label} ({} {index})", section.name()),
+            Fix::Broken {
+                section,
+                index,
+                label,
+                path,
+            } => write!(
+                f,
+                "- {label} ({} {index}): {} no longer exists",
+                section.name(),
+                path.display()
+            ),
+            Fix::NormalizedUrl {
+                section,
+                index,
+                label,
+                old,
+                new,
+            } => write!(f, "~ {label} ({} {index}): {old} → {new}", section.name()),
+            Fix::RepairedGuid {
+                section,
+                index,
+                label,
+                reason,
+            } => write!(f, "~ {label} ({} {index}): {reason}", section.name()),
+            Fix::OtherCopy {
+                section,
+                index,
+                label,
+                path,
+                kept,
+            } => write!(
+                f,
+                "- {label} ({} {index}): {} instead of {}",
+                section.name(),
+                path.display(),
+                kept.display()
+            ),
+        }
+    }
+}
+
+/// Everything one [`Dock::clean`] fixed, listed by kind when displayed.
+#[derive(Debug, Clone, Default)]
+pub struct CleanReport {
+    /// The fixes, in the order they were made.
+    pub fixes: Vec<Fix>,
+}
+
+impl CleanReport {
+    /// Returns `true` when there was nothing to fix.
+    pub fn is_empty(&self) -> bool {
+        self.fixes.is_empty()
+    }
+
+    /// Whether any fix only touched bookkeeping a [`DockDiff`](crate::diff::DockDiff)
+    /// doesn't show, so the Dock needs writing even if the diff is empty.
+    pub fn touches_bookkeeping(&self) -> bool {
+        self.fixes
+            .iter()
+            .any(|fix| matches!(fix, Fix::RepairedGuid { .. }))
+    }
+}
+
+impl fmt::Display for CleanReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for heading in ["Duplicates", "Broken", "URLs", "GUIDs", "Copies"] {
+            let mut fixes = self.fixes.iter().filter(|fix| fix.heading() == heading);
+            let Some(first) = fixes.next() else {
+                continue;
+            };
+            writeln!(f, "{heading}:")?;
+            writeln!(f, "  {first}")?;
+            for fix in fixes {
+                writeln!(f, "  {fix}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Tiles in one section that share a bundle identifier but point at different copies of
+/// the app, such as an old download left in `~/Downloads` next to `/Applications`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateCopies {
+    /// The section the tiles are in.
+    pub section: Section,
+
+    /// The bundle identifier they share.
+    pub bundle_id: String,
+
+    /// Each tile's index in the section and the copy it points at, in Dock order.
+    pub tiles: Vec<(usize, PathBuf)>,
+}
+
+impl DuplicateCopies {
+    /// The tile to keep: the one in the most preferred install location (see
+    /// [`mac_app::install_rank`]), or the first of those that rank the same.
+    pub fn canonical(&self) -> &(usize, PathBuf) {
+        self.tiles
+            .iter()
+            .min_by_key(|(_, path)| mac_app::install_rank(path))
+            .expect("duplicate copies always have tiles")
+    }
+}
+
+impl fmt::Display for DuplicateCopies {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let paths: Vec<String> = self
+            .tiles
+            .iter()
+            .map(|(_, path)| path.display().to_string())
+            .collect();
+        write!(
+            f,
+            "{} is pinned from {} copies: {}",
+            self.bundle_id,
+            paths.len(),
+            paths.join(", ")
+        )
+    }
+}
+
+impl Dock {
+    /// Finds tiles that share a bundle identifier but point at different copies of the
+    /// app. Tiles for the same copy are plain duplicates and aren't reported.
+    pub fn duplicate_copies(&self) -> Vec<DuplicateCopies> {
+        let mut found = Vec::new();
+        for section in Section::ALL {
+            let mut by_bundle_id: BTreeMap<&str, Vec<(usize, PathBuf)>> = BTreeMap::new();
+            for (index, item) in self.section(section).iter().enumerate() {
+                if let (Some(bundle_id), Some(path)) = (&item.metadata.bundle_id, item.path()) {
+                    by_bundle_id
+                        .entry(bundle_id)
+                        .or_default()
+                        .push((index, path));
+                }
+            }
+            for (bundle_id, tiles) in by_bundle_id {
+                let paths: HashSet<&PathBuf> = tiles.iter().map(|(_, path)| path).collect();
+                if paths.len() > 1 {
+                    found.push(DuplicateCopies {
+                        section,
+                        bundle_id: bundle_id.to_string(),
+                        tiles,
+                    });
+                }
+            }
+        }
+        found
+    }
+
+    /// Applies the fixes `options` turns on and reports what changed.
+    ///
+    /// Other copies of an app are removed first, so the duplicate that survives is the
+    /// canonical copy rather than the first. URLs are normalized next so tiles that only
+    /// differ in how their URL is spelled count as duplicates, and GUIDs are checked
+    /// last, across both sections.
+    pub fn clean(&mut self, options: &CleanOptions) -> CleanReport {
+        let mut fixes = Vec::new();
+        let mut guids = HashSet::new();
+
+        // Tiles for other copies, by section and index, with the copy kept instead.
+        let mut other_copies = HashMap::new();
+        if options.canonical_copies {
+            for copies in self.duplicate_copies() {
+                let (kept_index, kept) = copies.canonical();
+                for (index, path) in &copies.tiles {
+                    if index != kept_index && path != kept {
+                        other_copies.insert((copies.section, *index), (path.clone(), kept.clone()));
+                    }
+                }
+            }
+        }
+
+        for section in Section::ALL {
+            let items = match section {
+                Section::Apps => &mut self.applications,
+                Section::Others => &mut self.others,
+            };
+            let Some(items) = items else {
+                continue;
+            };
+
+            let mut kept: Vec<DockItem> = Vec::with_capacity(items.len());
+            let mut seen = HashSet::new();
+            for (index, mut item) in std::mem::take(items).into_iter().enumerate() {
+                let label = item.label().to_string();
+
+                if let Some((path, kept)) = other_copies.remove(&(section, index)) {
+                    fixes.push(Fix::OtherCopy {
+                        section,
+                        index,
+                        label,
+                        path,
+                        kept,
+                    });
+                    continue;
+                }
+
+                if options.normalize_urls
+                    && let Some(location) = &mut item.metadata.location
+                    && let Some(path) = location.path()
+                {
+                    let is_dir = location.url.ends_with('/') || path.is_dir();
+                    let normalized = FileLocation::from_path(&path, is_dir).url;
+                    if normalized != location.url {
+                        fixes.push(Fix::NormalizedUrl {
+                            section,
+                            index,
+                            label: label.clone(),
+                            old: std::mem::replace(&mut location.url, normalized.clone()),
+                            new: normalized,
+                        });
+                    }
+                }
+
+                if options.prune_broken
+                    && let Some(path) = item.path()
+                    && !path.exists()
+                    && !VolumeState::of(&path).is_unmounted()
+                {
+                    fixes.push(Fix::Broken {
+                        section,
+                        index,
+                        label,
+                        path,
+                    });
+                    continue;
+                }
+
+                if options.dedupe && !item.is_spacer() && !seen.insert(item.identity().into_owned())
+                {
+                    fixes.push(Fix::Duplicate {
+                        section,
+                        index,
+                        label,
+                    });
+                    continue;
+                }
+
+                if options.repair_guids
+                    && let Some(guid) = item.extra.get(GUID_KEY)
+                {
+                    let reason = match guid.as_signed_integer() {
+                        None => Some("malformed GUID".to_string()),
+                        Some(guid) if !guids.insert(guid) => Some(format!("duplicate GUID {guid}")),
+                        Some(_) => None,
+                    };
+                    if let Some(reason) = reason {
+                        item.extra.remove(GUID_KEY);
+                        fixes.push(Fix::RepairedGuid {
+                            section,
+                            index,
+                            label,
+                            reason,
+                        });
+                    }
+                }
+
+                kept.push(item);
+            }
+            *items = kept;
+        }
+
+        CleanReport { fixes }
+    }
+}
@@ -0,0 +1,63 @@
+use std::io::IsTerminal;
+
+/// Controls when terminal output is colored.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum ColorChoice {
+    /// Color when writing to a terminal and `NO_COLOR` is not set.
+    #[default]
+    Auto,
+
+    /// Always emit ANSI colors.
+    Always,
+
+    /// Never emit ANSI colors.
+    Never,
+}
+
+impl ColorChoice {
+    /// Resolves this choice against the environment and stdout.
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+                !no_color && std::io::stdout().is_terminal()
+            }
+        }
+    }
+}
+
+/// The ANSI styles used by docktor's renderers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    Green,
+    Red,
+    Yellow,
+    Blue,
+    Bold,
+    Dim,
+}
+
+impl Style {
+    fn code(self) -> &'static str {
+        match self {
+            Style::Green => "32",
+            Style::Red => "31",
+            Style::Yellow => "33",
+            Style::Blue => "34",
+            Style::Bold => "1",
+            Style::Dim => "2",
+        }
+    }
+}
+
+/// Wraps `text` in the ANSI escape codes for `style` when `enabled` is set.
+pub fn paint(text: &str, style: Style, enabled: bool) -> String {
+    if enabled {
+        format!("\x1b[{}m{}\x1b[0m", style.code(), text)
+    } else {
+        text.to_string()
+    }
+}
@@ -0,0 +1,102 @@
+//! Reporting whether a Mac's Dock matches a profile, in the shapes inventory tools expect.
+//!
+//! Inventory scripts such as Jamf extension attributes run as root, so when docktor is
+//! root and no plist path is configured, the logged-in user's Dock is the one checked.
+
+use crate::config::Config;
+use crate::diff::DockDiff;
+use crate::dock::Dock;
+use crate::profile::Profile;
+use crate::users;
+use anyhow::{Context, Result};
+use std::path::Path;
+
+/// How `docktor compliance` prints its result.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum ComplianceFormat {
+    /// A sentence, followed by the differences if there are any.
+    #[default]
+    Text,
+
+    /// `<result>…</result>` for a Jamf extension attribute; always exits 0.
+    JamfEa,
+
+    /// A JSON object with `status`, `summary`, and `reason`.
+    Json,
+}
+
+/// Whether the Dock matches a profile.
+#[derive(Debug, Clone)]
+pub enum Compliance {
+    /// The Dock matches the profile.
+    Compliant,
+
+    /// The Dock differs from the profile; the diff goes from the Dock to the profile.
+    Drifted(DockDiff),
+
+    /// The answer couldn't be worked out, e.g. nobody is logged in or the profile is broken.
+    Unknown(String),
+}
+
+impl Compliance {
+    /// Compares the Dock against the profile at `profile`, turning any failure into
+    /// [`Compliance::Unknown`] so inventory always gets an answer.
+    pub fn evaluate(profile: &Path) -> Self {
+        match compare(profile) {
+            Ok(diff) if diff.is_empty() => Compliance::Compliant,
+            Ok(diff) => Compliance::Drifted(diff),
+            Err(err) => Compliance::Unknown(format!("{err:#}")),
+        }
+    }
+
+    /// A short label for smart groups and reports: `Compliant`, `Drifted`, or `Unknown`.
+    pub fn status(&self) -> &'static str {
+        match self {
+            Compliance::Compliant => "Compliant",
+            Compliance::Drifted(_) => "Drifted",
+            Compliance::Unknown(_) => "Unknown",
+        }
+    }
+
+    /// Renders the result as JSON.
+    pub fn to_json(&self) -> serde_json::Value {
+        let (summary, reason) = match self {
+            Compliance::Compliant => (None, None),
+            Compliance::Drifted(diff) => (Some(diff.summary()), None),
+            Compliance::Unknown(reason) => (None, Some(reason.as_str())),
+        };
+        serde_json::json!({
+            "status": self.status().to_lowercase(),
+            "summary": summary,
+            "reason": reason,
+        })
+    }
+
+    /// Renders the result as a Jamf extension attribute, e.g. `<result>Drifted (+1, −2)</result>`.
+    ///
+    /// Smart groups can match on the leading word with "like".
+    pub fn to_jamf_ea(&self) -> String {
+        let value = match self {
+            Compliance::Compliant => self.status().to_string(),
+            Compliance::Drifted(diff) => format!("{} ({})", self.status(), diff.summary()),
+            Compliance::Unknown(reason) => format!("{}: {reason}", self.status()),
+        };
+        // Jamf reads the value up to the closing tag, so keep the message on one line.
+        format!("<result>{}</result>", value.replace(['\n', '<'], " "))
+    }
+}
+
+/// Diffs the Dock being checked against the profile.
+fn compare(profile: &Path) -> Result<DockDiff> {
+    let profile = Profile::load(profile)?;
+    if users::is_root() && Config::current().plist_path.is_none() {
+        let user = users::console_user()?.context("Nobody is logged in")?;
+        let dock = Dock::load_from(user.dock_path())?;
+        let desired = profile.with_home(&user.home).apply_to(&dock)?;
+        return Ok(DockDiff::between(&dock, &desired));
+    }
+    let dock = Dock::load()?;
+    let desired = profile.apply_to(&dock)?;
+    Ok(DockDiff::between(&dock, &desired))
+}
@@ -0,0 +1,302 @@
+//! Defaults for where docktor looks and how it writes, from the environment and
+//! `~/.config/docktor/config.toml`.
+//!
+//! Command-line flags win over environment variables, which win over the config
+//! file, which wins over the built-in defaults.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+static CURRENT: OnceLock<Config> = OnceLock::new();
+
+/// Whether the Dock is restarted after its plist is written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RestartMode {
+    /// Restart the Dock so changes show up straight away.
+    #[default]
+    Always,
+
+    /// Leave the Dock running; changes show up the next time it starts.
+    Never,
+}
+
+/// What to do when the plist changed between reading the Dock and writing it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConflictPolicy {
+    /// Refuse to write, leaving the newer plist alone.
+    #[default]
+    Abort,
+
+    /// Replay docktor's changes on the newer plist and write that.
+    Reapply,
+}
+
+/// How the Dock plist is encoded when docktor writes it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PlistFormat {
+    /// Binary, as the Dock writes it itself. The whole document is held in memory
+    /// while its object table is built.
+    #[default]
+    Binary,
+
+    /// XML, streamed to disk as it is serialized. The Dock reads it just as well and
+    /// rewrites it as binary the next time it saves.
+    Xml,
+}
+
+/// How the live Dock's preferences are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Backend {
+    /// Write the plist file and rename it into place.
+    #[default]
+    File,
+
+    /// Go through `/usr/bin/defaults`, for sandboxes and SSH sessions where writing the
+    /// file is blocked, or where `cfprefsd` keeps putting back its cached copy.
+    Defaults,
+}
+
+/// When snapshots are taken automatically.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BackupPolicy {
+    /// Snapshot the Dock before every write.
+    pub enabled: bool,
+
+    /// Keep at most this many snapshots, deleting the oldest after each write.
+    pub max_backups: Option<usize>,
+
+    /// Delete snapshots older than this many days after each write.
+    pub max_age_days: Option<u64>,
+}
+
+impl BackupPolicy {
+    /// Whether either retention limit is set.
+    pub fn has_retention(&self) -> bool {
+        self.max_backups.is_some() || self.max_age_days.is_some()
+    }
+}
+
+impl Default for BackupPolicy {
+    fn default() -> Self {
+        BackupPolicy {
+            enabled: true,
+            max_backups: None,
+            max_age_days: None,
+        }
+    }
+}
+
+/// How seriously `docktor profile lint` takes a rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Severity {
+    /// Don't check the rule.
+    Off,
+
+    /// Report it, without failing the lint.
+    Warn,
+
+    /// Report it and fail the lint.
+    Error,
+}
+
+/// Which policy checks `docktor profile lint` runs, and how seriously (`[lint]`).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LintConfig {
+    /// The most apps a profile may pin, for the `max-apps` rule.
+    pub max_apps: usize,
+
+    /// Each rule's severity, by name.
+    pub rules: LintRules,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        LintConfig {
+            max_apps: 20,
+            rules: LintRules::default(),
+        }
+    }
+}
+
+/// The severity of each lint rule (`[lint.rules]`).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct LintRules {
+    /// Paths inside one user's home folder, such as `/Users/alice/Documents`, rather
+    /// than `~/Documents`.
+    pub no_user_paths: Severity,
+
+    /// Apps named by path or name instead of bundle identifier.
+    pub apps_by_bundle_id: Severity,
+
+    /// More apps than `max_apps`.
+    pub max_apps: Severity,
+}
+
+impl Default for LintRules {
+    fn default() -> Self {
+        LintRules {
+            no_user_paths: Severity::Error,
+            apps_by_bundle_id: Severity::Warn,
+            max_apps: Severity::Warn,
+        }
+    }
+}
+
+/// docktor's settings after merging the config file and environment.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// The Dock plist to read and write instead of the current user's (`DOCKTOR_PLIST_PATH`).
+    pub plist_path: Option<PathBuf>,
+
+    /// Where snapshots, the sync checkout, and other state live (`DOCKTOR_STATE_DIR`).
+    pub state_dir: Option<PathBuf>,
+
+    /// Whether to restart the Dock after writing (`DOCKTOR_NO_RESTART`).
+    pub restart: RestartMode,
+
+    /// How to write the live Dock's preferences (`DOCKTOR_BACKEND`).
+    pub backend: Backend,
+
+    /// How to encode the plist when writing it.
+    pub plist_format: PlistFormat,
+
+    /// When to snapshot the Dock automatically.
+    pub backup: BackupPolicy,
+
+    /// What to do if the Dock plist changes while docktor is working on it.
+    pub on_conflict: ConflictPolicy,
+
+    /// Commands run around every write, before any a profile declares.
+    pub hooks: ApplyHooks,
+
+    /// The policy checks `docktor profile lint` runs.
+    pub lint: LintConfig,
+
+    /// The folders to take an app from when it's installed in several, most preferred
+    /// first (`DOCKTOR_INSTALL_PREFERENCE`, separated by `:`). `~/` is expanded. Without
+    /// it, `/Applications`, `/System/Applications`, then `~/Applications`.
+    pub install_preference: Option<Vec<PathBuf>>,
+}
+
+/// Shell commands run around every write to the Dock, whatever made it.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApplyHooks {
+    /// Run before changes are written; any failure aborts the write.
+    pub pre_apply: Vec<String>,
+
+    /// Run after changes are written.
+    pub post_apply: Vec<String>,
+}
+
+impl Config {
+    /// Returns the path of the config file, whether or not it exists.
+    pub fn path() -> Result<PathBuf> {
+        Ok(crate::paths::home_dir()
+            .ok_or_else(|| anyhow::anyhow!("Could not determine home directory"))?
+            .join(".config/docktor/config.toml"))
+    }
+
+    /// Reads the config file, if there is one, and applies environment overrides.
+    pub fn load() -> Result<Self> {
+        let mut config = match Self::path() {
+            Ok(path) if path.exists() => Self::load_from(&path)?,
+            _ => Config::default(),
+        };
+        config.apply_env()?;
+        Ok(config)
+    }
+
+    /// Reads a config file without looking at the environment.
+    pub fn load_from(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&text).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    fn apply_env(&mut self) -> Result<()> {
+        if let Some(path) = std::env::var_os("DOCKTOR_PLIST_PATH").filter(|v| !v.is_empty()) {
+            self.plist_path = Some(PathBuf::from(path));
+        }
+        if let Some(dir) = std::env::var_os("DOCKTOR_STATE_DIR").filter(|v| !v.is_empty()) {
+            self.state_dir = Some(PathBuf::from(dir));
+        }
+        if let Some(dirs) = std::env::var_os("DOCKTOR_INSTALL_PREFERENCE").filter(|v| !v.is_empty())
+        {
+            self.install_preference = Some(std::env::split_paths(&dirs).collect());
+        }
+        if let Ok(value) = std::env::var("DOCKTOR_BACKEND") {
+            self.backend = match value.to_ascii_lowercase().as_str() {
+                "" | "file" => Backend::File,
+                "defaults" => Backend::Defaults,
+                _ => anyhow::bail!("DOCKTOR_BACKEND must be file or defaults, not {value:?}"),
+            };
+        }
+        if let Ok(value) = std::env::var("DOCKTOR_NO_RESTART") {
+            self.restart = match value.to_ascii_lowercase().as_str() {
+                "" | "0" | "false" | "no" => RestartMode::Always,
+                "1" | "true" | "yes" => RestartMode::Never,
+                _ => anyhow::bail!("DOCKTOR_NO_RESTART must be 1 or 0, not {value:?}"),
+            };
+        }
+        Ok(())
+    }
+
+    /// Loads the settings for this process, reporting a bad config file or environment.
+    ///
+    /// Call this early; later calls, and [`Config::current`], return the same settings.
+    pub fn init() -> Result<&'static Config> {
+        Self::init_with(|_| {})
+    }
+
+    /// Like [`Config::init`], letting `overrides` adjust the settings first, for
+    /// command-line flags that win over the config file and environment.
+    pub fn init_with(overrides: impl FnOnce(&mut Config)) -> Result<&'static Config> {
+        if let Some(config) = CURRENT.get() {
+            return Ok(config);
+        }
+        let mut config = Self::load()?;
+        overrides(&mut config);
+        Ok(CURRENT.get_or_init(|| config))
+    }
+
+    /// The settings for this process, falling back to the defaults if they can't be loaded.
+    pub fn current() -> &'static Config {
+        CURRENT.get_or_init(|| {
+            Self::load().unwrap_or_else(|err| {
+                tracing::warn!("ignoring docktor config: {err:#}");
+                Config::default()
+            })
+        })
+    }
+
+    /// Whether a write should restart the Dock, given the `--no-restart` flag.
+    pub fn restarts(&self, no_restart: bool) -> bool {
+        !no_restart && self.restart == RestartMode::Always
+    }
+}
+
+/// Returns the directory docktor keeps its state in, e.g. `~/Library/Application Support/docktor`.
+pub fn state_dir() -> Result<PathBuf> {
+    if let Some(dir) = &Config::current().state_dir {
+        return Ok(dir.clone());
+    }
+    Ok(crate::paths::data_dir()
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Could not determine application support directory; set DOCKTOR_STATE_DIR"
+            )
+        })?
+        .join("docktor"))
+}
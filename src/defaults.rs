@@ -0,0 +1,178 @@
+//! Writing the live Dock through `/usr/bin/defaults` rather than to its plist file.
+//!
+//! This is the [`Backend::Defaults`](crate::config::Backend) backend. Each write compares
+//! the Dock against what `defaults export` reports: changed plain values become
+//! `defaults write`, removed keys become `defaults delete`, and anything else, such as a
+//! change to the sections, is imported whole with `defaults import`. Going through
+//! `defaults` keeps `cfprefsd` in the loop, so its cached copy never overwrites the change.
+
+use crate::config::Config;
+use crate::dock::Dock;
+use anyhow::{Context, Result, bail};
+use std::process::Command;
+
+/// The `defaults` binary, named in full so a different one on `PATH` isn't picked up.
+const DEFAULTS: &str = "/usr/bin/defaults";
+
+/// The domain of the current user's Dock preferences.
+const DOCK_DOMAIN: &str = "com.apple.dock";
+
+/// One `defaults` invocation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operation {
+    /// `defaults write <domain> <key> -<type> <value>`, for booleans, numbers, and strings.
+    Write { key: String, value: plist::Value },
+
+    /// `defaults delete <domain> <key>`.
+    Delete { key: String },
+
+    /// `defaults import <domain> <file>`, replacing every key at once.
+    Import,
+}
+
+/// The domain `defaults` knows the Dock by: `com.apple.dock`, or the configured
+/// `plist_path` without its extension, which `defaults` accepts as a domain.
+pub fn domain() -> String {
+    match &Config::current().plist_path {
+        Some(path) => path.with_extension("").display().to_string(),
+        None => DOCK_DOMAIN.to_string(),
+    }
+}
+
+/// Writes `dock` to the domain, with as few `defaults` calls as the change allows.
+#[tracing::instrument(skip_all)]
+pub fn save(dock: &Dock) -> Result<()> {
+    crate::macos::ensure("The defaults backend")?;
+    let domain = domain();
+    let desired = match plist::to_value(dock).context("Failed to serialize the Dock")? {
+        plist::Value::Dictionary(dict) => dict,
+        _ => bail!("The Dock didn't serialize to a dictionary"),
+    };
+    // A domain that can't be exported, e.g. because it doesn't exist yet, is imported whole.
+    let operations = match export(&domain) {
+        Ok(current) => plan(&current, &desired),
+        Err(err) => {
+            tracing::debug!("{err:#}");
+            vec![Operation::Import]
+        }
+    };
+    for operation in &operations {
+        run(&domain, operation, &desired)?;
+    }
+    tracing::info!(
+        domain,
+        operations = operations.len(),
+        "saved Dock through defaults"
+    );
+    Ok(())
+}
+
+/// The `defaults` calls that turn `current` into `desired`.
+pub fn plan(current: &plist::Dictionary, desired: &plist::Dictionary) -> Vec<Operation> {
+    let mut operations = Vec::new();
+    for (key, value) in desired {
+        if current.get(key) == Some(value) {
+            continue;
+        }
+        if !is_scalar(value) || current.get(key).is_some_and(|old| !is_scalar(old)) {
+            return vec![Operation::Import];
+        }
+        operations.push(Operation::Write {
+            key: key.clone(),
+            value: value.clone(),
+        });
+    }
+    for key in current.keys() {
+        if !desired.contains_key(key) {
+            operations.push(Operation::Delete { key: key.clone() });
+        }
+    }
+    operations
+}
+
+/// Whether `defaults write` can set `value` with a type flag.
+fn is_scalar(value: &plist::Value) -> bool {
+    matches!(
+        value,
+        plist::Value::Boolean(_)
+            | plist::Value::Integer(_)
+            | plist::Value::Real(_)
+            | plist::Value::String(_)
+    )
+}
+
+/// Reads the domain as `defaults` currently sees it.
+pub(crate) fn export(domain: &str) -> Result<plist::Dictionary> {
+    let output = Command::new(DEFAULTS)
+        .args(["export", domain, "-"])
+        .output()
+        .with_context(|| format!("Failed to run {DEFAULTS}"))?;
+    if !output.status.success() {
+        bail!(
+            "Exporting {domain} failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    plist::from_bytes(&output.stdout)
+        .with_context(|| format!("Failed to parse {domain} as exported by defaults"))
+}
+
+/// Runs one operation against the domain. `desired` is only read by an import.
+pub(crate) fn run(domain: &str, operation: &Operation, desired: &plist::Dictionary) -> Result<()> {
+    let mut command = Command::new(DEFAULTS);
+    // Kept until the import has read it.
+    let mut import_file = None;
+    match operation {
+        Operation::Write { key, value } => {
+            command.args(["write", domain, key]);
+            match value {
+                plist::Value::Boolean(value) => command.args(["-bool", &value.to_string()]),
+                plist::Value::Integer(value) => command.args(["-int", &value.to_string()]),
+                plist::Value::Real(value) => command.args(["-float", &value.to_string()]),
+                plist::Value::String(value) => command.args(["-string", value]),
+                _ => bail!("defaults write can't set {key} directly"),
+            };
+        }
+        Operation::Delete { key } => {
+            command.args(["delete", domain, key]);
+        }
+        Operation::Import => {
+            let path = std::env::temp_dir().join(format!("docktor-{}.plist", std::process::id()));
+            plist::to_file_xml(&path, &plist::Value::Dictionary(desired.clone()))
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            command.args(["import", domain]).arg(&path);
+            import_file = Some(TempFile(path));
+        }
+    }
+
+    let output = command
+        .output()
+        .with_context(|| format!("Failed to run {DEFAULTS}"))?;
+    drop(import_file);
+    if !output.status.success() {
+        bail!(
+            "{DEFAULTS} {} failed: {}",
+            describe(operation),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// The subcommand and key of an operation, for error messages.
+fn describe(operation: &Operation) -> String {
+    match operation {
+        Operation::Write { key, .. } => format!("write {key}"),
+        Operation::Delete { key } => format!("delete {key}"),
+        Operation::Import => "import".to_string(),
+    }
+}
+
+/// A file deleted when it goes out of scope.
+struct TempFile(std::path::PathBuf);
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
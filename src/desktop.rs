@@ -0,0 +1,207 @@
+//! The Desktop & Dock settings that live outside the Dock's own preferences, in
+//! `com.apple.WindowManager`: clicking the wallpaper to reveal the desktop, Stage
+//! Manager, desktop icons and widgets, and window tiling.
+//!
+//! This is opt in: only a profile with a `desktop` section touches these, and only the
+//! settings it names. They're written through `defaults`, which the window manager
+//! picks up without a restart.
+
+use crate::defaults::{self, Operation};
+use crate::macos;
+use crate::profile::Profile;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The preferences domain these settings are kept in.
+pub const DOMAIN: &str = "com.apple.WindowManager";
+
+/// Desktop and window settings, each left as it is when unset.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DesktopSettings {
+    /// Whether clicking the wallpaper moves windows aside to reveal the desktop; off
+    /// means only in Stage Manager (`EnableStandardClickToShowDesktop`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub click_wallpaper_to_reveal: Option<bool>,
+
+    /// Whether Stage Manager is on (`GloballyEnabled`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stage_manager: Option<bool>,
+
+    /// Whether files on the desktop are hidden (`StandardHideDesktopIcons`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hide_desktop_icons: Option<bool>,
+
+    /// Whether widgets on the desktop are hidden (`StandardHideWidgets`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hide_widgets: Option<bool>,
+
+    /// Whether dragging a window to a screen edge tiles it (`EnableTilingByEdgeDrag`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tile_by_edge_drag: Option<bool>,
+
+    /// Whether dragging a window to the menu bar fills the screen
+    /// (`EnableTopTilingByEdgeDrag`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tile_by_menu_bar_drag: Option<bool>,
+
+    /// Whether holding Option while dragging a window tiles it
+    /// (`EnableTilingOptionAccelerator`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tile_with_option_key: Option<bool>,
+
+    /// Whether tiled windows have gaps between them (`EnableTiledWindowMargins`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tiled_window_margins: Option<bool>,
+}
+
+impl DesktopSettings {
+    /// Every setting, as its profile name, its `com.apple.WindowManager` key, and its value.
+    fn fields(&self) -> [(&'static str, &'static str, Option<bool>); 8] {
+        [
+            (
+                "click_wallpaper_to_reveal",
+                "EnableStandardClickToShowDesktop",
+                self.click_wallpaper_to_reveal,
+            ),
+            ("stage_manager", "GloballyEnabled", self.stage_manager),
+            (
+                "hide_desktop_icons",
+                "StandardHideDesktopIcons",
+                self.hide_desktop_icons,
+            ),
+            ("hide_widgets", "StandardHideWidgets", self.hide_widgets),
+            (
+                "tile_by_edge_drag",
+                "EnableTilingByEdgeDrag",
+                self.tile_by_edge_drag,
+            ),
+            (
+                "tile_by_menu_bar_drag",
+                "EnableTopTilingByEdgeDrag",
+                self.tile_by_menu_bar_drag,
+            ),
+            (
+                "tile_with_option_key",
+                "EnableTilingOptionAccelerator",
+                self.tile_with_option_key,
+            ),
+            (
+                "tiled_window_margins",
+                "EnableTiledWindowMargins",
+                self.tiled_window_margins,
+            ),
+        ]
+    }
+
+    /// Layers `top` over these settings, keeping the ones it leaves unset.
+    pub fn overlay(&self, top: &DesktopSettings) -> DesktopSettings {
+        DesktopSettings {
+            click_wallpaper_to_reveal: top
+                .click_wallpaper_to_reveal
+                .or(self.click_wallpaper_to_reveal),
+            stage_manager: top.stage_manager.or(self.stage_manager),
+            hide_desktop_icons: top.hide_desktop_icons.or(self.hide_desktop_icons),
+            hide_widgets: top.hide_widgets.or(self.hide_widgets),
+            tile_by_edge_drag: top.tile_by_edge_drag.or(self.tile_by_edge_drag),
+            tile_by_menu_bar_drag: top.tile_by_menu_bar_drag.or(self.tile_by_menu_bar_drag),
+            tile_with_option_key: top.tile_with_option_key.or(self.tile_with_option_key),
+            tiled_window_margins: top.tiled_window_margins.or(self.tiled_window_margins),
+        }
+    }
+
+    /// The settings as they are now. Ones never changed from the macOS default are unset.
+    #[tracing::instrument]
+    pub fn read() -> Result<Self> {
+        macos::ensure("Reading desktop settings")?;
+        // A domain that doesn't exist yet just means every setting is at its default.
+        let current = match defaults::export(DOMAIN) {
+            Ok(current) => current,
+            Err(err) => {
+                tracing::debug!("{err:#}");
+                plist::Dictionary::new()
+            }
+        };
+        let flag = |key: &str| {
+            let value = current.get(key)?;
+            value
+                .as_boolean()
+                .or_else(|| value.as_signed_integer().map(|value| value != 0))
+        };
+        Ok(DesktopSettings {
+            click_wallpaper_to_reveal: flag("EnableStandardClickToShowDesktop"),
+            stage_manager: flag("GloballyEnabled"),
+            hide_desktop_icons: flag("StandardHideDesktopIcons"),
+            hide_widgets: flag("StandardHideWidgets"),
+            tile_by_edge_drag: flag("EnableTilingByEdgeDrag"),
+            tile_by_menu_bar_drag: flag("EnableTopTilingByEdgeDrag"),
+            tile_with_option_key: flag("EnableTilingOptionAccelerator"),
+            tiled_window_margins: flag("EnableTiledWindowMargins"),
+        })
+    }
+
+    /// The settings these set to something other than what `current` has.
+    pub fn changes(&self, current: &DesktopSettings) -> Vec<DesktopChange> {
+        self.fields()
+            .into_iter()
+            .zip(current.fields())
+            .filter_map(|((name, key, new), (_, _, old))| {
+                let new = new?;
+                (old != Some(new)).then_some(DesktopChange {
+                    name,
+                    key,
+                    old,
+                    new,
+                })
+            })
+            .collect()
+    }
+}
+
+/// One desktop setting a profile changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DesktopChange {
+    /// The setting's name in profiles, e.g. `click_wallpaper_to_reveal`.
+    pub name: &'static str,
+
+    /// Its `com.apple.WindowManager` key.
+    pub key: &'static str,
+
+    /// Its value before, if it was set.
+    pub old: Option<bool>,
+
+    /// Its value after.
+    pub new: bool,
+}
+
+impl fmt::Display for DesktopChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let on_off = |value: bool| if value { "on" } else { "off" };
+        match self.old {
+            Some(old) => write!(f, "~ {}: {} → {}", self.name, on_off(old), on_off(self.new)),
+            None => write!(f, "~ {}: (unset) → {}", self.name, on_off(self.new)),
+        }
+    }
+}
+
+impl Profile {
+    /// Sets the profile's `desktop` settings that differ from the current ones,
+    /// returning what changed. With `dry_run`, only reports what would change.
+    pub fn apply_desktop(&self, dry_run: bool) -> Result<Vec<DesktopChange>> {
+        let Some(desktop) = &self.desktop else {
+            return Ok(Vec::new());
+        };
+        let changes = desktop.changes(&DesktopSettings::read()?);
+        if !dry_run {
+            for change in &changes {
+                let operation = Operation::Write {
+                    key: change.key.to_string(),
+                    value: change.new.into(),
+                };
+                defaults::run(DOMAIN, &operation, &plist::Dictionary::new())?;
+            }
+        }
+        Ok(changes)
+    }
+}
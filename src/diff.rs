@@ -0,0 +1,708 @@
+use crate::color::{ColorChoice, Style, paint};
+use crate::dock::{
+    ARRANGEMENT_KEY, BOOKKEEPING_KEYS, DISPLAY_AS_KEY, Dock, DockItem, FILE_DATA_KEY,
+    FILE_LABEL_KEY, FolderOptions, SHOW_AS_KEY, Section, TILE_DATA_KEY,
+};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::path::PathBuf;
+
+/// A single difference between two Docks.
+#[derive(Debug, Clone)]
+pub enum Change {
+    /// An item present only in the new Dock.
+    Added {
+        section: Section,
+        index: usize,
+        item: DockItem,
+    },
+
+    /// An item present only in the old Dock.
+    Removed {
+        section: Section,
+        index: usize,
+        item: DockItem,
+    },
+
+    /// An item present in both Docks but at a different relative position.
+    Moved {
+        section: Section,
+        from: usize,
+        to: usize,
+        item: DockItem,
+    },
+
+    /// An item present in both Docks whose label changed; `index` is its new position.
+    Relabeled {
+        section: Section,
+        index: usize,
+        old: Option<String>,
+        item: DockItem,
+    },
+
+    /// An item present in both Docks that now points somewhere else, such as an app
+    /// whose bundle moved; `index` is its new position.
+    Relocated {
+        section: Section,
+        index: usize,
+        old: Option<PathBuf>,
+        item: DockItem,
+    },
+
+    /// A folder present in both Docks whose stack options changed; `index` is its
+    /// new position.
+    Restyled {
+        section: Section,
+        index: usize,
+        old: FolderOptions,
+        item: DockItem,
+    },
+
+    /// A top-level setting that was added, removed, or changed.
+    Setting {
+        key: String,
+        old: Option<plist::Value>,
+        new: Option<plist::Value>,
+    },
+}
+
+impl Change {
+    /// What kind of change this is: `added`, `removed`, `moved`, `relabeled`,
+    /// `relocated`, `restyled`, or `setting`, as hooks and bindings report it.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Change::Added { .. } => "added",
+            Change::Removed { .. } => "removed",
+            Change::Moved { .. } => "moved",
+            Change::Relabeled { .. } => "relabeled",
+            Change::Relocated { .. } => "relocated",
+            Change::Restyled { .. } => "restyled",
+            Change::Setting { .. } => "setting",
+        }
+    }
+
+    /// The tile changed, or `None` for a setting.
+    pub fn item(&self) -> Option<&DockItem> {
+        match self {
+            Change::Added { item, .. }
+            | Change::Removed { item, .. }
+            | Change::Moved { item, .. }
+            | Change::Relabeled { item, .. }
+            | Change::Relocated { item, .. }
+            | Change::Restyled { item, .. } => Some(item),
+            Change::Setting { .. } => None,
+        }
+    }
+
+    /// The section of the tile changed, or `None` for a setting.
+    pub fn section(&self) -> Option<Section> {
+        match self {
+            Change::Added { section, .. }
+            | Change::Removed { section, .. }
+            | Change::Moved { section, .. }
+            | Change::Relabeled { section, .. }
+            | Change::Relocated { section, .. }
+            | Change::Restyled { section, .. } => Some(*section),
+            Change::Setting { .. } => None,
+        }
+    }
+
+    /// The tile's label, or the setting's key.
+    pub fn label(&self) -> &str {
+        match self {
+            Change::Setting { key, .. } => key,
+            _ => self.item().map_or("", DockItem::label),
+        }
+    }
+
+    /// The tile's previous index, for removed and moved tiles.
+    pub fn from(&self) -> Option<usize> {
+        match self {
+            Change::Removed { index, .. } => Some(*index),
+            Change::Moved { from, .. } => Some(*from),
+            _ => None,
+        }
+    }
+
+    /// The tile's new index, for added, moved, relabeled, relocated, and restyled tiles.
+    pub fn to(&self) -> Option<usize> {
+        match self {
+            Change::Added { index, .. }
+            | Change::Relabeled { index, .. }
+            | Change::Relocated { index, .. }
+            | Change::Restyled { index, .. } => Some(*index),
+            Change::Moved { to, .. } => Some(*to),
+            Change::Removed { .. } | Change::Setting { .. } => None,
+        }
+    }
+}
+
+/// The set of changes that turn one Dock into another.
+#[derive(Debug, Clone, Default)]
+pub struct DockDiff {
+    /// Item changes grouped by section, followed by setting changes.
+    pub changes: Vec<Change>,
+}
+
+impl DockDiff {
+    /// Computes the changes needed to go from `old` to `new`.
+    pub fn between(old: &Dock, new: &Dock) -> Self {
+        let mut changes = Vec::new();
+        for section in Section::ALL {
+            diff_section(
+                section,
+                old.section(section),
+                new.section(section),
+                &mut changes,
+            );
+        }
+        diff_settings(&old.extra, &new.extra, &mut changes);
+        DockDiff { changes }
+    }
+
+    /// Returns `true` when both Docks are equivalent.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Expresses the diff as an RFC 6902 JSON Patch against the Dock plist.
+    ///
+    /// Paths address the plist's top-level keys, e.g. `/persistent-apps/2` or
+    /// `/autohide`. Moves are written as a remove followed by an add so the patch
+    /// can be applied in order: every removal runs from the highest old index down,
+    /// then every insertion from the lowest new index up.
+    pub fn to_json_patch(&self) -> serde_json::Value {
+        let mut ops = Vec::new();
+        for section in Section::ALL {
+            let mut removals = Vec::new();
+            let mut insertions = Vec::new();
+            for change in &self.changes {
+                match change {
+                    Change::Removed {
+                        section: s, index, ..
+                    } if *s == section => removals.push(*index),
+                    Change::Added {
+                        section: s,
+                        index,
+                        item,
+                    } if *s == section => insertions.push((*index, item)),
+                    Change::Moved {
+                        section: s,
+                        from,
+                        to,
+                        item,
+                    } if *s == section => {
+                        removals.push(*from);
+                        insertions.push((*to, item));
+                    }
+                    _ => {}
+                }
+            }
+
+            removals.sort_unstable_by(|a, b| b.cmp(a));
+            insertions.sort_unstable_by_key(|(index, _)| *index);
+            for index in removals {
+                ops.push(serde_json::json!({
+                    "op": "remove",
+                    "path": format!("/{}/{index}", section.key()),
+                }));
+            }
+            for (index, item) in insertions {
+                ops.push(serde_json::json!({
+                    "op": "add",
+                    "path": format!("/{}/{index}", section.key()),
+                    "value": serde_json::to_value(item).unwrap_or_default(),
+                }));
+            }
+        }
+
+        for change in &self.changes {
+            if let Change::Relabeled {
+                section,
+                index,
+                item,
+                ..
+            } = change
+            {
+                let path = format!(
+                    "/{}/{index}/{TILE_DATA_KEY}/{FILE_LABEL_KEY}",
+                    section.key()
+                );
+                ops.push(match &item.metadata.display_name {
+                    Some(label) => {
+                        serde_json::json!({ "op": "add", "path": path, "value": label })
+                    }
+                    None => serde_json::json!({ "op": "remove", "path": path }),
+                });
+            }
+            if let Change::Relocated {
+                section,
+                index,
+                item,
+                ..
+            } = change
+            {
+                let path = format!("/{}/{index}/{TILE_DATA_KEY}/{FILE_DATA_KEY}", section.key());
+                ops.push(match &item.metadata.location {
+                    Some(location) => serde_json::json!({
+                        "op": "add",
+                        "path": path,
+                        "value": serde_json::to_value(location).unwrap_or_default(),
+                    }),
+                    None => serde_json::json!({ "op": "remove", "path": path }),
+                });
+            }
+            if let Change::Restyled {
+                section,
+                index,
+                old,
+                item,
+            } = change
+            {
+                let new = item.metadata.folder_options();
+                let values = [
+                    (
+                        DISPLAY_AS_KEY,
+                        old.display.map(|v| v as i64),
+                        new.display.map(|v| v as i64),
+                    ),
+                    (
+                        SHOW_AS_KEY,
+                        old.view.map(|v| v as i64),
+                        new.view.map(|v| v as i64),
+                    ),
+                    (
+                        ARRANGEMENT_KEY,
+                        old.arrangement.map(|v| v as i64),
+                        new.arrangement.map(|v| v as i64),
+                    ),
+                ];
+                for (key, before, after) in values {
+                    let path = format!("/{}/{index}/{TILE_DATA_KEY}/{key}", section.key());
+                    match (before, after) {
+                        (Some(_), None) => {
+                            ops.push(serde_json::json!({ "op": "remove", "path": path }))
+                        }
+                        (before, Some(after)) if before != Some(after) => ops
+                            .push(serde_json::json!({ "op": "add", "path": path, "value": after })),
+                        _ => {}
+                    }
+                }
+            }
+        }
+        for change in &self.changes {
+            if let Change::Setting { key, old, new } = change {
+                let path = format!("/{}", key.replace('~', "~0").replace('/', "~1"));
+                let value = |value: &plist::Value| serde_json::to_value(value).unwrap_or_default();
+                ops.push(match (old, new) {
+                    (_, None) => serde_json::json!({ "op": "remove", "path": path }),
+                    (None, Some(new)) => {
+                        serde_json::json!({ "op": "add", "path": path, "value": value(new) })
+                    }
+                    (Some(_), Some(new)) => {
+                        serde_json::json!({ "op": "replace", "path": path, "value": value(new) })
+                    }
+                });
+            }
+        }
+        serde_json::Value::Array(ops)
+    }
+
+    /// A one-line count of the changes, such as `+2, −1, ↕1, ✎1, ⇢1, ≡1, ~1`.
+    pub fn summary(&self) -> String {
+        let (mut added, mut removed, mut moved, mut relabeled, mut relocated) = (0, 0, 0, 0, 0);
+        let (mut restyled, mut settings) = (0, 0);
+        for change in &self.changes {
+            match change {
+                Change::Added { .. } => added += 1,
+                Change::Removed { .. } => removed += 1,
+                Change::Moved { .. } => moved += 1,
+                Change::Relabeled { .. } => relabeled += 1,
+                Change::Relocated { .. } => relocated += 1,
+                Change::Restyled { .. } => restyled += 1,
+                Change::Setting { .. } => settings += 1,
+            }
+        }
+
+        let parts: Vec<String> = [
+            ("+", added),
+            ("−", removed),
+            ("↕", moved),
+            ("✎", relabeled),
+            ("⇢", relocated),
+            ("≡", restyled),
+            ("~", settings),
+        ]
+        .into_iter()
+        .filter(|(_, count)| *count > 0)
+        .map(|(sign, count)| format!("{sign}{count}"))
+        .collect();
+        if parts.is_empty() {
+            "no changes".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
+
+    /// Replays these changes on another Dock, matching items by [`DockItem::identity`].
+    ///
+    /// Items the other Dock already lacks stay removed, items it already has aren't
+    /// added twice, and indexes past the end of a section append. Everything the diff
+    /// doesn't touch is kept as the other Dock has it.
+    pub fn replay(&self, base: &Dock) -> Dock {
+        let mut dock = base.clone();
+        for section in Section::ALL {
+            let items = match section {
+                Section::Apps => &mut dock.applications,
+                Section::Others => &mut dock.others,
+            };
+            let present = items.is_some();
+            let mut working = items.take().unwrap_or_default();
+            let mut insertions = Vec::new();
+            for change in &self.changes {
+                match change {
+                    Change::Removed {
+                        section: s, item, ..
+                    } if *s == section => {
+                        take(&mut working, item);
+                    }
+                    Change::Moved {
+                        section: s,
+                        to,
+                        item,
+                        ..
+                    } if *s == section => {
+                        if let Some(existing) = take(&mut working, item) {
+                            insertions.push((*to, existing));
+                        }
+                    }
+                    Change::Added {
+                        section: s,
+                        index,
+                        item,
+                    } if *s == section
+                        && !working.iter().any(|w| w.identity() == item.identity()) =>
+                    {
+                        insertions.push((*index, item.clone()));
+                    }
+                    _ => {}
+                }
+            }
+            insertions.sort_by_key(|(index, _)| *index);
+            for (index, item) in insertions {
+                working.insert(index.min(working.len()), item);
+            }
+
+            for change in &self.changes {
+                match change {
+                    Change::Relabeled {
+                        section: s, item, ..
+                    } if *s == section => {
+                        if let Some(existing) = find(&mut working, item) {
+                            existing.metadata.display_name = item.metadata.display_name.clone();
+                        }
+                    }
+                    Change::Relocated {
+                        section: s, item, ..
+                    } if *s == section => {
+                        if let Some(existing) = find(&mut working, item) {
+                            existing.metadata.location = item.metadata.location.clone();
+                        }
+                    }
+                    Change::Restyled {
+                        section: s, item, ..
+                    } if *s == section => {
+                        if let Some(existing) = find(&mut working, item) {
+                            existing
+                                .metadata
+                                .set_folder_options(&item.metadata.folder_options());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            if present || !working.is_empty() {
+                *items = Some(working);
+            }
+        }
+
+        for change in &self.changes {
+            if let Change::Setting { key, new, .. } = change {
+                match new {
+                    Some(value) => dock.extra.insert(key.clone(), value.clone()),
+                    None => dock.extra.remove(key),
+                };
+            }
+        }
+        dock
+    }
+
+    /// Renders the diff for humans, one line per change.
+    pub fn render(&self, color: ColorChoice) -> String {
+        let color = color.enabled();
+        if self.is_empty() {
+            return paint("No changes.", Style::Dim, color) + "\n";
+        }
+
+        let mut out = String::new();
+        let mut heading = None;
+        for change in &self.changes {
+            let section = match change {
+                Change::Added { section, .. }
+                | Change::Removed { section, .. }
+                | Change::Moved { section, .. }
+                | Change::Relabeled { section, .. }
+                | Change::Relocated { section, .. }
+                | Change::Restyled { section, .. } => section.name(),
+                Change::Setting { .. } => "Settings",
+            };
+            if heading != Some(section) {
+                let _ = writeln!(out, "{}", paint(&format!("{section}:"), Style::Bold, color));
+                heading = Some(section);
+            }
+
+            let line = match change {
+                Change::Added { index, item, .. } => paint(
+                    &format!("+ {} (at {})", item.label(), index),
+                    Style::Green,
+                    color,
+                ),
+                Change::Removed { index, item, .. } => paint(
+                    &format!("- {} (was {})", item.label(), index),
+                    Style::Red,
+                    color,
+                ),
+                Change::Moved { from, to, item, .. } => paint(
+                    &format!("↕ {} ({} → {})", item.label(), from, to),
+                    Style::Yellow,
+                    color,
+                ),
+                Change::Relabeled { old, item, .. } => paint(
+                    &format!(
+                        "✎ {} → {}",
+                        old.as_deref().unwrap_or("(no label)"),
+                        item.label()
+                    ),
+                    Style::Blue,
+                    color,
+                ),
+                Change::Relocated { old, item, .. } => {
+                    let describe = |path: Option<PathBuf>| {
+                        path.map_or_else(|| "(nowhere)".to_string(), |p| p.display().to_string())
+                    };
+                    paint(
+                        &format!(
+                            "⇢ {}: {} → {}",
+                            item.label(),
+                            describe(old.clone()),
+                            describe(item.path())
+                        ),
+                        Style::Yellow,
+                        color,
+                    )
+                }
+                Change::Restyled { old, item, .. } => paint(
+                    &format!(
+                        "≡ {}: {} → {}",
+                        item.label(),
+                        old,
+                        item.metadata.folder_options()
+                    ),
+                    Style::Blue,
+                    color,
+                ),
+                Change::Setting { key, old, new } => paint(
+                    &format!(
+                        "~ {}: {} → {}",
+                        key,
+                        describe_value(old.as_ref()),
+                        describe_value(new.as_ref())
+                    ),
+                    Style::Blue,
+                    color,
+                ),
+            };
+            let _ = writeln!(out, "  {line}");
+        }
+        out
+    }
+}
+
+/// Pairs each item with its occurrence number so duplicate tiles (like spacers) match one-to-one.
+fn keyed(items: &[DockItem]) -> Vec<(Cow<'_, str>, usize)> {
+    let mut seen: HashMap<Cow<'_, str>, usize> = HashMap::new();
+    items
+        .iter()
+        .map(|item| {
+            let identity = item.identity();
+            let n = seen.entry(identity.clone()).or_default();
+            *n += 1;
+            (identity, *n)
+        })
+        .collect()
+}
+
+/// Removes and returns the first item with the same identity as `item`.
+fn take(items: &mut Vec<DockItem>, item: &DockItem) -> Option<DockItem> {
+    let index = items.iter().position(|i| i.identity() == item.identity())?;
+    Some(items.remove(index))
+}
+
+/// The first item with the same identity as `item`.
+fn find<'a>(items: &'a mut [DockItem], item: &DockItem) -> Option<&'a mut DockItem> {
+    items.iter_mut().find(|i| i.identity() == item.identity())
+}
+
+fn diff_section(section: Section, old: &[DockItem], new: &[DockItem], changes: &mut Vec<Change>) {
+    let old_keys = keyed(old);
+    let new_keys = keyed(new);
+    let old_set: HashSet<_> = old_keys.iter().cloned().collect();
+    let new_set: HashSet<_> = new_keys.iter().cloned().collect();
+
+    for (index, key) in old_keys.iter().enumerate() {
+        if !new_set.contains(key) {
+            changes.push(Change::Removed {
+                section,
+                index,
+                item: old[index].clone(),
+            });
+        }
+    }
+    for (index, key) in new_keys.iter().enumerate() {
+        if !old_set.contains(key) {
+            changes.push(Change::Added {
+                section,
+                index,
+                item: new[index].clone(),
+            });
+        }
+    }
+
+    // Items kept in both Docks, in old and new order. Anything outside their
+    // longest common subsequence has moved relative to its neighbours.
+    let old_common: Vec<_> = old_keys.iter().filter(|k| new_set.contains(k)).collect();
+    let new_common: Vec<_> = new_keys.iter().filter(|k| old_set.contains(k)).collect();
+    let stable = longest_common_subsequence(&old_common, &new_common);
+    let old_index: HashMap<_, _> = old_keys.iter().enumerate().map(|(i, k)| (k, i)).collect();
+
+    for (to, key) in new_keys.iter().enumerate() {
+        if old_set.contains(key) && !stable.contains(&key) {
+            changes.push(Change::Moved {
+                section,
+                from: old_index[key],
+                to,
+                item: new[to].clone(),
+            });
+        }
+    }
+
+    for (index, key) in new_keys.iter().enumerate() {
+        let Some(&from) = old_index.get(key) else {
+            continue;
+        };
+        let (before, after) = (
+            &old[from].metadata.display_name,
+            &new[index].metadata.display_name,
+        );
+        if before != after {
+            changes.push(Change::Relabeled {
+                section,
+                index,
+                old: before.clone(),
+                item: new[index].clone(),
+            });
+        }
+
+        let url = |item: &DockItem| {
+            item.metadata
+                .location
+                .as_ref()
+                .map(|location| location.canonical_url().into_owned())
+        };
+        if url(&old[from]) != url(&new[index]) {
+            changes.push(Change::Relocated {
+                section,
+                index,
+                old: old[from].path(),
+                item: new[index].clone(),
+            });
+        }
+
+        let before = old[from].metadata.folder_options();
+        if before != new[index].metadata.folder_options() {
+            changes.push(Change::Restyled {
+                section,
+                index,
+                old: before,
+                item: new[index].clone(),
+            });
+        }
+    }
+}
+
+fn longest_common_subsequence<'a, T: PartialEq + Eq + std::hash::Hash>(
+    a: &[&'a T],
+    b: &[&'a T],
+) -> HashSet<&'a T> {
+    let mut table = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            table[i][j] = if a[i] == b[j] {
+                table[i + 1][j + 1] + 1
+            } else {
+                table[i + 1][j].max(table[i][j + 1])
+            };
+        }
+    }
+
+    let mut stable = HashSet::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            stable.insert(a[i]);
+            i += 1;
+            j += 1;
+        } else if table[i + 1][j] >= table[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    stable
+}
+
+fn diff_settings(old: &plist::Dictionary, new: &plist::Dictionary, changes: &mut Vec<Change>) {
+    let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+    keys.sort();
+    keys.dedup();
+
+    for key in keys {
+        if BOOKKEEPING_KEYS.contains(&key.as_str()) {
+            continue;
+        }
+        let (old_value, new_value) = (old.get(key), new.get(key));
+        if old_value != new_value {
+            changes.push(Change::Setting {
+                key: key.clone(),
+                old: old_value.cloned(),
+                new: new_value.cloned(),
+            });
+        }
+    }
+}
+
+/// Formats a plist value compactly for a single diff line.
+pub(crate) fn describe_value(value: Option<&plist::Value>) -> String {
+    match value {
+        None => "(unset)".to_string(),
+        Some(plist::Value::Boolean(b)) => b.to_string(),
+        Some(plist::Value::Integer(i)) => i.to_string(),
+        Some(plist::Value::Real(r)) => r.to_string(),
+        Some(plist::Value::String(s)) => format!("{s:?}"),
+        Some(plist::Value::Array(a)) => format!("[{} items]", a.len()),
+        Some(plist::Value::Dictionary(d)) => format!("{{{} keys}}", d.len()),
+        Some(plist::Value::Data(d)) => format!("<{} bytes>", d.len()),
+        Some(other) => format!("{other:?}"),
+    }
+}
@@ -1,119 +1,1544 @@
+use crate::config::{Backend, Config, PlistFormat};
 use crate::mac_app::MacApp;
-use anyhow::{Context, Result};
+use crate::paths;
+use anyhow::{Context, Result, bail};
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
 
 /// Represents the top-level structure of the macOS Dock configuration plist.
-#[derive(Debug, Serialize, Deserialize)]
+///
+/// Deserialized through a [`plist::Dictionary`] so that keys docktor doesn't model
+/// survive a round trip untouched.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(try_from = "plist::Dictionary")]
 pub struct Dock {
     /// Applications pinned to the Dock (left side).
-    #[serde(rename = "persistent-apps")]
     pub applications: Option<Vec<DockItem>>,
 
     /// Non-application items like folders, documents, or spacers (right side).
-    #[serde(rename = "persistent-others")]
     pub others: Option<Vec<DockItem>>,
+
+    /// Every other top-level key (settings and Dock bookkeeping), kept as-is.
+    pub extra: plist::Dictionary,
+
+    /// The plist file this Dock was loaded from, as it was then.
+    #[serde(skip)]
+    pub(crate) loaded: Option<PlistStamp>,
+}
+
+/// A plist file as it was at one moment: where it is, when it was last modified, and
+/// a hash of its bytes.
+///
+/// Comparing two stamps of the same file tells whether anything, even a key docktor
+/// doesn't model, was written to it in between.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PlistStamp {
+    path: PathBuf,
+    modified: Option<std::time::SystemTime>,
+    hash: String,
+}
+
+impl PlistStamp {
+    /// Stamps `bytes`, just read from `path`.
+    fn new(path: &Path, bytes: &[u8]) -> Self {
+        PlistStamp {
+            path: path.to_path_buf(),
+            modified: std::fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .ok(),
+            hash: crate::snapshot::sha256(bytes),
+        }
+    }
+
+    /// Stamps the file at `path` as it is now.
+    pub(crate) fn of(path: &Path) -> Result<Self> {
+        let bytes = std::fs::read(path)
+            .with_context(|| format!("Failed to read Dock plist at {}", path.display()))?;
+        Ok(Self::new(path, &bytes))
+    }
+
+    /// The file stamped.
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+const APPS_KEY: &str = "persistent-apps";
+const OTHERS_KEY: &str = "persistent-others";
+
+/// Top-level keys the Dock maintains on its own rather than user settings.
+pub(crate) const BOOKKEEPING_KEYS: &[&str] = &[
+    "mod-count",
+    "version",
+    "recent-apps",
+    "trash-full",
+    "loc",
+    "region",
+    "last-messagetrace-stamp",
+    "lastShowIndicatorTime",
+];
+
+impl TryFrom<plist::Dictionary> for Dock {
+    type Error = plist::Error;
+
+    fn try_from(mut dict: plist::Dictionary) -> Result<Self, Self::Error> {
+        let applications = dict
+            .remove(APPS_KEY)
+            .map(|value| plist::from_value(&value))
+            .transpose()?;
+        let others = dict
+            .remove(OTHERS_KEY)
+            .map(|value| plist::from_value(&value))
+            .transpose()?;
+
+        Ok(Dock {
+            applications,
+            others,
+            extra: dict,
+            loaded: None,
+        })
+    }
+}
+
+impl Serialize for Dock {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+
+        let mut map = serializer.serialize_map(None)?;
+        if let Some(apps) = &self.applications {
+            map.serialize_entry(APPS_KEY, apps)?;
+        }
+        if let Some(others) = &self.others {
+            map.serialize_entry(OTHERS_KEY, others)?;
+        }
+        for (key, value) in &self.extra {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
 }
 
 impl Dock {
+    /// Creates a Dock with empty sections and no settings, without touching the user's plist.
+    pub fn new() -> Self {
+        Dock {
+            applications: Some(Vec::new()),
+            others: Some(Vec::new()),
+            extra: plist::Dictionary::new(),
+            loaded: None,
+        }
+    }
+
+    /// Starts composing a Dock in memory.
+    pub fn builder() -> DockBuilder {
+        DockBuilder { dock: Dock::new() }
+    }
+
+    /// Returns the path to the user's Dock preferences plist file.
+    ///
+    /// `DOCKTOR_PLIST_PATH` or `plist_path` in the config file points it elsewhere, and
+    /// must on other platforms, which have no Dock of their own.
+    pub fn default_path() -> Result<PathBuf> {
+        if let Some(path) = &Config::current().plist_path {
+            return Ok(path.clone());
+        }
+        crate::macos::ensure("Finding the Dock's preferences without DOCKTOR_PLIST_PATH")?;
+        Ok(crate::paths::home_dir()
+            .context("Could not determine home directory; set DOCKTOR_PLIST_PATH")?
+            .join("Library/Preferences/com.apple.dock.plist"))
+    }
+
     /// Loads the Dock configuration from the user's preferences plist file.
     pub fn load() -> Result<Self> {
-        let dock_path = dirs::home_dir()
-            .ok_or_else(|| anyhow::anyhow!("Could not determine home directory"))?
-            .join("Library/Preferences/com.apple.dock.plist");
+        Self::load_from(Self::default_path()?)
+    }
 
-        let file = std::fs::File::open(&dock_path)
+    /// Loads a Dock configuration from an arbitrary plist file, such as a snapshot.
+    ///
+    /// The file is stamped as it was read, so a later write can tell
+    /// whether anything else wrote it in between.
+    pub fn load_from<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let dock_path = path.as_ref();
+        let _span = tracing::info_span!("load", path = %dock_path.display()).entered();
+
+        let bytes = std::fs::read(dock_path)
             .with_context(|| format!("Failed to open Dock plist at {}", dock_path.display()))?;
 
-        let dock: Dock = plist::from_reader(file)
+        let mut dock: Dock = plist::from_bytes(&bytes)
             .with_context(|| format!("Failed to parse Dock plist at {}", dock_path.display()))?;
+        dock.loaded = Some(PlistStamp::new(dock_path, &bytes));
 
+        tracing::debug!(
+            apps = dock.section(Section::Apps).len(),
+            others = dock.section(Section::Others).len(),
+            "loaded Dock"
+        );
         Ok(dock)
     }
 
-    /// Adds a new application to the Dock's persistent applications section.
-    pub fn add_app(&mut self, app: &MacApp) {
-        if self.applications.is_none() {
-            self.applications = Some(Vec::new());
+    /// Loads only the given sections of the user's Dock, for read-only queries.
+    ///
+    /// Everything else in the plist, including settings and the recent apps list, is
+    /// skipped rather than deserialized, so this is much faster on large Docks. The
+    /// result is a [`DockSections`], which can't be saved.
+    pub fn load_sections(sections: &[Section]) -> Result<DockSections> {
+        Self::load_sections_from(Self::default_path()?, sections)
+    }
+
+    /// Like [`Dock::load_sections`], reading the plist at `path`.
+    pub fn load_sections_from<P: AsRef<Path>>(
+        path: P,
+        sections: &[Section],
+    ) -> Result<DockSections> {
+        use serde::de::IgnoredAny;
+
+        /// The two sections, each either deserialized or skipped.
+        #[derive(Deserialize)]
+        struct Only<A, O> {
+            #[serde(rename = "persistent-apps")]
+            apps: Option<A>,
+            #[serde(rename = "persistent-others")]
+            others: Option<O>,
+        }
+
+        let dock_path = path.as_ref();
+        let _span = tracing::info_span!("load_sections", path = %dock_path.display()).entered();
+        let file = std::fs::File::open(dock_path)
+            .with_context(|| format!("Failed to open Dock plist at {}", dock_path.display()))?;
+        let reader = std::io::BufReader::new(file);
+        let parse_error = || format!("Failed to parse Dock plist at {}", dock_path.display());
+
+        let (applications, others) = match (
+            sections.contains(&Section::Apps),
+            sections.contains(&Section::Others),
+        ) {
+            (true, true) => {
+                let only: Only<Vec<DockItem>, Vec<DockItem>> =
+                    plist::from_reader(reader).with_context(parse_error)?;
+                (only.apps, only.others)
+            }
+            (true, false) => {
+                let only: Only<Vec<DockItem>, IgnoredAny> =
+                    plist::from_reader(reader).with_context(parse_error)?;
+                (only.apps, None)
+            }
+            (false, true) => {
+                let only: Only<IgnoredAny, Vec<DockItem>> =
+                    plist::from_reader(reader).with_context(parse_error)?;
+                (None, only.others)
+            }
+            (false, false) => (None, None),
+        };
+        Ok(DockSections {
+            applications,
+            others,
+        })
+    }
+
+    /// Writes the Dock configuration back to the user's preferences plist file, or
+    /// through `defaults` when that is the configured [`Backend`](crate::config::Backend).
+    ///
+    /// What was written is [recorded](crate::state) so later runs can tell whether
+    /// the Dock was changed outside docktor.
+    pub fn save(&self) -> Result<()> {
+        match Config::current().backend {
+            Backend::File => self.save_to(Self::default_path()?)?,
+            Backend::Defaults => crate::defaults::save(self)?,
+        }
+        if let Err(err) = crate::state::record(self) {
+            tracing::warn!("Could not record the saved Dock: {err:#}");
+        }
+        Ok(())
+    }
+
+    /// Writes the Dock configuration to `path`, in the configured
+    /// [`PlistFormat`](crate::config::PlistFormat).
+    ///
+    /// The file is written next to its destination and renamed into place, so a
+    /// failed write never leaves a truncated plist behind.
+    pub fn save_to<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let dock_path = path.as_ref();
+        let tmp_path = dock_path.with_extension("plist.docktor-tmp");
+        let _span = tracing::info_span!("save", path = %dock_path.display()).entered();
+
+        let file = std::fs::File::create(&tmp_path)
+            .with_context(|| format!("Failed to create {}", tmp_path.display()))?;
+        self.write_to(
+            std::io::BufWriter::new(file),
+            Config::current().plist_format,
+        )
+        .and_then(|file| Ok(file.sync_all()?))
+        .with_context(|| format!("Failed to write Dock plist to {}", tmp_path.display()))?;
+        std::fs::rename(&tmp_path, dock_path)
+            .with_context(|| format!("Failed to replace Dock plist at {}", dock_path.display()))?;
+
+        tracing::info!("saved Dock");
+        Ok(())
+    }
+
+    /// Serializes the Dock straight into `writer`, without building a [`plist::Value`]
+    /// first, and hands back the underlying writer once everything is flushed.
+    ///
+    /// XML is streamed as it is produced; binary plists need their whole object
+    /// table before anything can be written, so that copy can't be avoided.
+    pub fn write_to<W: std::io::Write>(
+        &self,
+        mut writer: std::io::BufWriter<W>,
+        format: PlistFormat,
+    ) -> Result<W> {
+        match format {
+            PlistFormat::Binary => plist::to_writer_binary(&mut writer, self)?,
+            PlistFormat::Xml => plist::to_writer_xml(&mut writer, self)?,
+        }
+        writer
+            .into_inner()
+            .map_err(|err| anyhow::Error::new(err.into_error()))
+    }
+
+    /// Returns the items in the given section of the Dock.
+    pub fn section(&self, section: Section) -> &[DockItem] {
+        match section {
+            Section::Apps => self.applications.as_deref().unwrap_or_default(),
+            Section::Others => self.others.as_deref().unwrap_or_default(),
+        }
+    }
+
+    /// Iterates over the top-level keys that are user settings, skipping Dock bookkeeping.
+    pub fn settings(&self) -> impl Iterator<Item = (&String, &plist::Value)> {
+        self.extra
+            .iter()
+            .filter(|(key, _)| !BOOKKEEPING_KEYS.contains(&key.as_str()))
+    }
+
+    /// Adds an item to the end of a section, following `policy` if it is already there.
+    ///
+    /// Items are considered the same when their [`DockItem::identity`] matches.
+    /// Returns whether the Dock changed.
+    pub fn add(&mut self, section: Section, item: DockItem, policy: AddPolicy) -> bool {
+        let items = match section {
+            Section::Apps => &mut self.applications,
+            Section::Others => &mut self.others,
+        }
+        .get_or_insert_with(Vec::new);
+
+        let existing = items
+            .iter()
+            .position(|existing| existing.identity() == item.identity());
+        match (existing, policy) {
+            (Some(_), AddPolicy::Skip) => return false,
+            (Some(index), AddPolicy::Replace) => items[index] = item,
+            (_, _) => items.push(item),
+        }
+        true
+    }
+
+    /// Adds a tile for a file share on a server, such as `smb://server/share`, to the end
+    /// of the others section, unless it is there already.
+    ///
+    /// Returns whether the Dock changed. See [`DockItem::network_share`].
+    pub fn add_network_share(&mut self, url: &str, label: impl Into<String>) -> Result<bool> {
+        let item = DockItem::network_share(url, label)?;
+        Ok(self.add(Section::Others, item, AddPolicy::Skip))
+    }
+
+    /// Adds an application to the Dock's persistent applications section.
+    ///
+    /// Returns whether the Dock changed.
+    pub fn add_app(&mut self, app: &MacApp, policy: AddPolicy) -> bool {
+        self.add(Section::Apps, DockItem::new(app), policy)
+    }
+
+    /// Inserts an item at `position` in a section, following `policy` if it is already there.
+    ///
+    /// With [`AddPolicy::Replace`] the existing item is moved to `position`. Returns the
+    /// index the item ended up at, or `None` if it was skipped.
+    pub fn add_at(
+        &mut self,
+        section: Section,
+        item: DockItem,
+        position: &Position,
+        policy: AddPolicy,
+    ) -> Result<Option<usize>> {
+        let current = self.section(section);
+        let existing = current
+            .iter()
+            .position(|existing| existing.identity() == item.identity());
+        // The position is resolved before anything is removed, so a failure leaves the
+        // Dock as it was.
+        let (index, replaced) = match (existing, policy) {
+            (Some(_), AddPolicy::Skip) => return Ok(None),
+            (Some(existing), AddPolicy::Replace) => {
+                let mut remaining = current.to_vec();
+                remaining.remove(existing);
+                (position.resolve(&remaining)?, Some(existing))
+            }
+            _ => (position.resolve(current)?, None),
+        };
+
+        let items = match section {
+            Section::Apps => &mut self.applications,
+            Section::Others => &mut self.others,
+        }
+        .get_or_insert_with(Vec::new);
+        if let Some(existing) = replaced {
+            items.remove(existing);
+        }
+        items.insert(index, item);
+        Ok(Some(index))
+    }
+
+    /// Removes every item whose label or bundle identifier matches `query`.
+    ///
+    /// Returns how many items were removed.
+    pub fn remove(&mut self, query: &str) -> usize {
+        let mut removed = 0;
+        for items in [&mut self.applications, &mut self.others]
+            .into_iter()
+            .flatten()
+        {
+            let before = items.len();
+            items.retain(|item| !item.matches(query));
+            removed += before - items.len();
+        }
+        removed
+    }
+
+    /// Removes every item in a section, returning how many there were.
+    pub fn clear(&mut self, section: Section) -> usize {
+        let items = self.items_mut(section);
+        std::mem::take(items).len()
+    }
+
+    /// Changes the label of the first item whose label or bundle identifier matches
+    /// `query`, leaving the rest of its tile data as it is.
+    ///
+    /// Returns whether an item was renamed.
+    pub fn rename(&mut self, query: &str, label: &str) -> bool {
+        let existing = [&mut self.applications, &mut self.others]
+            .into_iter()
+            .flatten()
+            .flat_map(|items| items.iter_mut())
+            .find(|existing| existing.matches(query));
+        match existing {
+            Some(existing) => {
+                existing.metadata.display_name = Some(label.to_string());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Points every item under `old` at the same place under `new`, e.g. after a
+    /// username change moved the home folder.
+    ///
+    /// The stale bookmark is dropped from each rewritten tile so the Dock follows the
+    /// new URL. Returns how many items were rewritten.
+    pub fn rewrite_prefix(&mut self, old: &Path, new: &Path) -> usize {
+        let mut rewritten = 0;
+        for item in [&mut self.applications, &mut self.others]
+            .into_iter()
+            .flatten()
+            .flat_map(|items| items.iter_mut())
+        {
+            let Some(location) = &item.metadata.location else {
+                continue;
+            };
+            let Some(rest) = location
+                .path()
+                .and_then(|path| path.strip_prefix(old).ok().map(Path::to_path_buf))
+            else {
+                continue;
+            };
+
+            let path = if rest.as_os_str().is_empty() {
+                new.to_path_buf()
+            } else {
+                new.join(rest)
+            };
+            let url_type = location.url_type;
+            let mut location = FileLocation::from_path(&path, location.url.ends_with('/'));
+            location.url_type = url_type;
+            item.metadata.location = Some(location);
+            item.metadata.extra.remove(BOOKMARK_KEY);
+            rewritten += 1;
+        }
+        rewritten
+    }
+
+    /// Replaces the first item whose label or bundle identifier matches `query`, keeping its position.
+    ///
+    /// Returns whether an item was replaced.
+    pub fn replace(&mut self, query: &str, item: DockItem) -> bool {
+        let existing = [&mut self.applications, &mut self.others]
+            .into_iter()
+            .flatten()
+            .flat_map(|items| items.iter_mut())
+            .find(|existing| existing.matches(query));
+        match existing {
+            Some(existing) => {
+                *existing = item;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Finds where an item is, returning its section and index.
+    pub fn locate(&self, item: &ItemRef) -> Option<(Section, usize)> {
+        match item {
+            ItemRef::Index(section, index) => {
+                (*index < self.section(*section).len()).then_some((*section, *index))
+            }
+            ItemRef::Query(query) => Section::ALL.into_iter().find_map(|section| {
+                self.section(section)
+                    .iter()
+                    .position(|item| item.matches(query))
+                    .map(|index| (section, index))
+            }),
+        }
+    }
+
+    /// Swaps two items in the same section.
+    pub fn swap(&mut self, a: &ItemRef, b: &ItemRef) -> Result<()> {
+        let Some((section_a, index_a)) = self.locate(a) else {
+            bail!("No Dock item matches {a}");
+        };
+        let Some((section_b, index_b)) = self.locate(b) else {
+            bail!("No Dock item matches {b}");
+        };
+        if section_a != section_b {
+            bail!(
+                "Cannot swap {a} and {b}: one is in {} and the other in {}",
+                section_a.name(),
+                section_b.name()
+            );
+        }
+
+        let items = match section_a {
+            Section::Apps => &mut self.applications,
+            Section::Others => &mut self.others,
+        };
+        if let Some(items) = items {
+            items.swap(index_a, index_b);
+        }
+        Ok(())
+    }
+
+    /// Moves an item to `position` within `section`, returning where it ended up.
+    ///
+    /// Without a `section` the item stays in its own, unless `position` is anchored to
+    /// an item in the other one. Fails if the item or anchor isn't found, or if the
+    /// target section can't hold the item; see [`Section::accepts`].
+    pub fn move_to(
+        &mut self,
+        item: &ItemRef,
+        position: &Position,
+        section: Option<Section>,
+    ) -> Result<(Section, usize)> {
+        let Some((from, index)) = self.locate(item) else {
+            bail!("No Dock item matches {item}");
+        };
+        let anchored = match position {
+            Position::After(query) | Position::Before(query) => Section::ALL
+                .into_iter()
+                .find(|section| self.section(*section).iter().any(|i| i.matches(query))),
+            _ => None,
+        };
+        let to = section.or(anchored).unwrap_or(from);
+
+        let moving = &self.section(from)[index];
+        if !to.accepts(moving) {
+            bail!(
+                "{} can't go in {}: it only holds {}",
+                moving.label(),
+                to.name(),
+                to.holds()
+            );
         }
-        if let Some(apps) = &mut self.applications {
-            apps.push(DockItem::new(app));
+
+        let moving = self.items_mut(from).remove(index);
+        let items = self.items_mut(to);
+        match position.resolve(items) {
+            Ok(at) => {
+                items.insert(at, moving);
+                Ok((to, at))
+            }
+            Err(err) => {
+                self.items_mut(from).insert(index, moving);
+                Err(err)
+            }
         }
     }
 
-    /// Restart the Dock process to apply changes.
+    /// The items in a section, creating it if the plist didn't have one.
+    fn items_mut(&mut self, section: Section) -> &mut Vec<DockItem> {
+        match section {
+            Section::Apps => &mut self.applications,
+            Section::Others => &mut self.others,
+        }
+        .get_or_insert_with(Vec::new)
+    }
+
+    /// Restart the Dock process to apply changes. Does nothing outside macOS.
+    #[tracing::instrument]
     pub fn restart() -> Result<()> {
-        std::process::Command::new("killall")
+        if !cfg!(target_os = "macos") {
+            tracing::debug!("not on macOS, so there's no Dock to restart");
+            return Ok(());
+        }
+        let status = std::process::Command::new("killall")
             .arg("Dock")
             .status()
             .with_context(|| "Failed to restart the Dock")?;
+        if status.success() {
+            tracing::info!("restarted the Dock");
+        } else {
+            tracing::warn!(%status, "killall Dock did not succeed");
+        }
         Ok(())
     }
 }
 
+/// Composes a [`Dock`] from scratch, for templates and tests.
+#[derive(Debug, Clone)]
+pub struct DockBuilder {
+    dock: Dock,
+}
+
+impl DockBuilder {
+    /// Appends an item to the given section.
+    pub fn item(mut self, section: Section, item: DockItem) -> Self {
+        let items = match section {
+            Section::Apps => &mut self.dock.applications,
+            Section::Others => &mut self.dock.others,
+        };
+        items.get_or_insert_with(Vec::new).push(item);
+        self
+    }
+
+    /// Appends a tile for an application bundle to the apps section.
+    pub fn app(self, app: &MacApp) -> Self {
+        self.item(Section::Apps, DockItem::new(app))
+    }
+
+    /// Appends an item to the others section.
+    pub fn other(self, item: DockItem) -> Self {
+        self.item(Section::Others, item)
+    }
+
+    /// Sets a top-level setting such as `autohide` or `tilesize`.
+    pub fn setting(mut self, key: &str, value: impl Into<plist::Value>) -> Self {
+        self.dock.extra.insert(key.to_string(), value.into());
+        self
+    }
+
+    /// Finishes the Dock.
+    pub fn build(self) -> Dock {
+        self.dock
+    }
+}
+
+/// Some of a Dock's tiles, without its settings or bookkeeping, as
+/// [`Dock::load_sections`] reads them.
+///
+/// There's no way to save one: writing it back would wipe everything it skipped.
+#[derive(Debug, Clone, Default)]
+pub struct DockSections {
+    applications: Option<Vec<DockItem>>,
+    others: Option<Vec<DockItem>>,
+}
+
+impl DockSections {
+    /// Returns the items in the given section, or none if it wasn't loaded.
+    pub fn section(&self, section: Section) -> &[DockItem] {
+        match section {
+            Section::Apps => self.applications.as_deref().unwrap_or_default(),
+            Section::Others => self.others.as_deref().unwrap_or_default(),
+        }
+    }
+}
+
+impl From<Dock> for DockSections {
+    fn from(dock: Dock) -> Self {
+        DockSections {
+            applications: dock.applications,
+            others: dock.others,
+        }
+    }
+}
+
+/// Refers to one item in the Dock, by position or by label or bundle identifier.
+///
+/// Parses from `3` (the fourth app), `others:0` (the first other item), or anything
+/// else as a query for [`DockItem::matches`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ItemRef {
+    /// The item at a zero-based index in a section.
+    Index(Section, usize),
+
+    /// The first item whose label or bundle identifier matches.
+    Query(String),
+}
+
+impl std::str::FromStr for ItemRef {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (section, index) = match s.split_once(':') {
+            Some(("apps", index)) => (Section::Apps, index),
+            Some(("others", index)) => (Section::Others, index),
+            _ => (Section::Apps, s),
+        };
+        Ok(match index.parse() {
+            Ok(index) => ItemRef::Index(section, index),
+            Err(_) => ItemRef::Query(s.to_string()),
+        })
+    }
+}
+
+impl std::fmt::Display for ItemRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ItemRef::Index(Section::Apps, index) => write!(f, "apps:{index}"),
+            ItemRef::Index(Section::Others, index) => write!(f, "others:{index}"),
+            ItemRef::Query(query) => write!(f, "{query:?}"),
+        }
+    }
+}
+
+/// Where to insert an item within a section.
+///
+/// Parses from `start`, `end`, a zero-based index such as `3`, `after:<query>`, or
+/// `before:<query>`, where the query is a label or bundle identifier.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Position {
+    /// Before every other item.
+    Start,
+
+    /// After every other item.
+    #[default]
+    End,
+
+    /// At a zero-based index, or the end if the section is shorter.
+    Index(usize),
+
+    /// Right after the first item matching the query.
+    After(String),
+
+    /// Right before the first item matching the query.
+    Before(String),
+}
+
+impl Position {
+    /// Resolves the position to an index into `items`.
+    pub fn resolve(&self, items: &[DockItem]) -> Result<usize> {
+        let find = |query: &str| {
+            items
+                .iter()
+                .position(|item| item.matches(query))
+                .with_context(|| format!("No Dock item matches {query:?}"))
+        };
+        Ok(match self {
+            Position::Start => 0,
+            Position::End => items.len(),
+            Position::Index(index) => (*index).min(items.len()),
+            Position::After(query) => find(query)? + 1,
+            Position::Before(query) => find(query)?,
+        })
+    }
+}
+
+impl std::str::FromStr for Position {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let anchor = |query: &str| {
+            if query.is_empty() {
+                bail!("Position {s:?} is missing an item to anchor to");
+            }
+            Ok(query.to_string())
+        };
+        match s {
+            "start" => Ok(Position::Start),
+            "end" => Ok(Position::End),
+            _ => {
+                if let Some(query) = s.strip_prefix("after:") {
+                    Ok(Position::After(anchor(query)?))
+                } else if let Some(query) = s.strip_prefix("before:") {
+                    Ok(Position::Before(anchor(query)?))
+                } else if let Ok(index) = s.parse() {
+                    Ok(Position::Index(index))
+                } else {
+                    bail!(
+                        "Invalid position {s:?}; use start, end, an index, after:<item>, or before:<item>"
+                    )
+                }
+            }
+        }
+    }
+}
+
+impl TryFrom<String> for Position {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl From<Position> for String {
+    fn from(position: Position) -> Self {
+        position.to_string()
+    }
+}
+
+impl std::fmt::Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Position::Start => write!(f, "start"),
+            Position::End => write!(f, "end"),
+            Position::Index(index) => write!(f, "{index}"),
+            Position::After(query) => write!(f, "after:{query}"),
+            Position::Before(query) => write!(f, "before:{query}"),
+        }
+    }
+}
+
+/// What to do when an item being added is already in the Dock.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum AddPolicy {
+    /// Leave the existing item alone, so repeated adds are idempotent.
+    #[default]
+    Skip,
+
+    /// Overwrite the existing item in place with the new one.
+    Replace,
+
+    /// Append the item anyway.
+    AllowDuplicate,
+}
+
+/// The two sections of the Dock, on either side of the divider.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Section {
+    /// Pinned applications (`persistent-apps`).
+    Apps,
+
+    /// Folders, documents, and other items (`persistent-others`).
+    Others,
+}
+
+impl Section {
+    /// Both sections, in the order they appear in the Dock.
+    pub const ALL: [Section; 2] = [Section::Apps, Section::Others];
+
+    /// A human-readable name for the section.
+    pub fn name(self) -> &'static str {
+        match self {
+            Section::Apps => "Apps",
+            Section::Others => "Others",
+        }
+    }
+
+    /// The section's lowercase name, as profiles, positions, and reports write it:
+    /// `apps` or `others`.
+    pub fn id(self) -> &'static str {
+        match self {
+            Section::Apps => "apps",
+            Section::Others => "others",
+        }
+    }
+
+    /// Whether the Dock shows `item` in this section: apps and spacers on the left,
+    /// everything but apps on the right.
+    pub fn accepts(self, item: &DockItem) -> bool {
+        match self {
+            Section::Apps => item.metadata.bundle_id.is_some() || item.is_spacer(),
+            Section::Others => item.metadata.bundle_id.is_none(),
+        }
+    }
+
+    /// What [`Section::accepts`], for messages.
+    fn holds(self) -> &'static str {
+        match self {
+            Section::Apps => "apps and spacers",
+            Section::Others => "folders, files, and spacers",
+        }
+    }
+
+    /// The plist key holding this section's items.
+    pub fn key(self) -> &'static str {
+        match self {
+            Section::Apps => APPS_KEY,
+            Section::Others => OTHERS_KEY,
+        }
+    }
+}
+
 /// Represents an individual item in the Dock.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(try_from = "plist::Dictionary")]
 pub struct DockItem {
     /// Metadata associated with this Dock item.
-    #[serde(rename = "tile-data")]
     pub metadata: TileMetadata,
 
     /// The kind of item: application, folder, document, or spacer.
-    #[serde(rename = "tile-type")]
     pub kind: DockItemKind,
+
+    /// Any other keys on the tile (such as `GUID`), kept as-is.
+    ///
+    /// For [`DockItemKind::Unknown`] tiles this also holds the original `tile-type`.
+    pub extra: plist::Dictionary,
+}
+
+pub(crate) const TILE_DATA_KEY: &str = "tile-data";
+pub(crate) const TILE_TYPE_KEY: &str = "tile-type";
+
+impl TryFrom<plist::Dictionary> for DockItem {
+    type Error = plist::Error;
+
+    fn try_from(mut dict: plist::Dictionary) -> Result<Self, Self::Error> {
+        let metadata = match dict.remove(TILE_DATA_KEY) {
+            Some(value) => plist::from_value(&value)?,
+            None => TileMetadata::default(),
+        };
+        let kind = match dict.get(TILE_TYPE_KEY) {
+            Some(value) => plist::from_value(value)?,
+            None => DockItemKind::Unknown,
+        };
+        if kind != DockItemKind::Unknown {
+            dict.remove(TILE_TYPE_KEY);
+        }
+
+        Ok(DockItem {
+            metadata,
+            kind,
+            extra: dict,
+        })
+    }
+}
+
+impl Serialize for DockItem {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry(TILE_DATA_KEY, &self.metadata)?;
+        if self.kind != DockItemKind::Unknown {
+            map.serialize_entry(TILE_TYPE_KEY, &self.kind)?;
+        }
+        for (key, value) in &self.extra {
+            if key != TILE_TYPE_KEY || self.kind == DockItemKind::Unknown {
+                map.serialize_entry(key, value)?;
+            }
+        }
+        map.end()
+    }
 }
 
 impl DockItem {
+    /// Creates a tile for an application bundle.
     pub fn new(app: &MacApp) -> Self {
+        Self::builder().app(app).build()
+    }
+
+    /// Starts building a tile with full control over its tile data.
+    pub fn builder() -> DockItemBuilder {
+        DockItemBuilder::default()
+    }
+
+    /// Creates a tile for a folder or file, choosing the tile type from what's on disk.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        if is_saved_search(path) {
+            return Self::smart_folder(path);
+        }
+        let is_dir = std::fs::metadata(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?
+            .is_dir();
+
+        let mut builder = Self::builder().path(path, is_dir).kind(if is_dir {
+            DockItemKind::DirectoryTile
+        } else {
+            DockItemKind::FileTile
+        });
+        if let Some(name) = path.file_name() {
+            builder = builder.label(name.to_string_lossy());
+        }
+        Ok(builder.build())
+    }
+
+    /// Creates a tile for a smart folder, a saved Spotlight search such as
+    /// `~/Library/Saved Searches/Recent Screenshots.savedSearch`.
+    ///
+    /// The search is a single file, but the Dock lists its results like a folder's
+    /// contents, so the tile is a directory tile with the folder file type and a URL
+    /// without the trailing slash folders get. [`DockItem::from_path`] calls this for
+    /// `.savedSearch` files.
+    pub fn smart_folder<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        if !is_saved_search(path) {
+            bail!("{} is not a saved search", path.display());
+        }
+        if !path.is_file() {
+            bail!("{} does not exist", path.display());
+        }
+        let mut builder = Self::builder()
+            .kind(DockItemKind::DirectoryTile)
+            .path(path, false)
+            .file_type(FOLDER_FILE_TYPE);
+        if let Some(name) = path.file_stem() {
+            builder = builder.label(name.to_string_lossy());
+        }
+        Ok(builder.build())
+    }
+
+    /// Whether this is a smart folder tile, pointing at a saved search.
+    pub fn is_smart_folder(&self) -> bool {
+        self.kind == DockItemKind::DirectoryTile
+            && self.path().is_some_and(|path| is_saved_search(&path))
+    }
+
+    /// Creates a tile for a file share on a server, such as `smb://server/share` or
+    /// `afp://server/share`.
+    ///
+    /// This is a URL tile, as the Dock makes for a server address dragged from Finder's
+    /// Connect to Server: clicking it mounts the share, so unlike a folder tile for its
+    /// mount point under `/Volumes`, it keeps working while the share isn't mounted.
+    pub fn network_share(url: &str, label: impl Into<String>) -> Result<Self> {
+        if !is_network_share(url) {
+            bail!(
+                "{url} is not a network share; use an {} URL with a server",
+                NETWORK_SHARE_SCHEMES.join("://, ") + "://"
+            );
+        }
+        let mut location = plist::Dictionary::new();
+        location.insert(CF_URL_STRING_KEY.to_string(), url.into());
+        location.insert(CF_URL_STRING_TYPE_KEY.to_string(), 15.into());
+        Ok(Self::builder()
+            .kind(DockItemKind::UrlTile)
+            .tile_data(URL_KEY, location)
+            .tile_data(URL_LABEL_KEY, label.into())
+            .build())
+    }
+
+    /// The URL a URL tile opens, such as a network share or a web link.
+    pub fn url(&self) -> Option<&str> {
+        if self.kind != DockItemKind::UrlTile {
+            return None;
+        }
+        self.metadata
+            .extra
+            .get(URL_KEY)?
+            .as_dictionary()?
+            .get(CF_URL_STRING_KEY)?
+            .as_string()
+    }
+
+    /// Creates a tile for an app bundle, folder, or file, with the section it belongs in.
+    pub fn for_path<P: AsRef<Path>>(path: P) -> Result<(Section, Self)> {
+        let path = path.as_ref();
+        if path.extension().is_some_and(|ext| ext == "app") {
+            Ok((Section::Apps, Self::new(&MacApp::from_path(path)?)))
+        } else {
+            Ok((Section::Others, Self::from_path(path)?))
+        }
+    }
+
+    /// Replaces the label shown under the tile, keeping everything else.
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.metadata.display_name = Some(label.into());
+        self
+    }
+
+    /// Creates a spacer tile of the given kind.
+    pub fn spacer(kind: DockItemKind) -> Self {
+        DockItem {
+            kind,
+            ..Default::default()
+        }
+    }
+
+    /// Returns `true` for spacer tiles of any size.
+    pub fn is_spacer(&self) -> bool {
+        matches!(
+            self.kind,
+            DockItemKind::SpacerTile | DockItemKind::SmallSpacerTile | DockItemKind::FlexSpacerTile
+        )
+    }
+
+    /// The local path this item points to, if it is backed by a `file://` URL.
+    pub fn path(&self) -> Option<PathBuf> {
+        self.metadata.location.as_ref()?.path()
+    }
+
+    /// The label shown for this item, falling back to its kind for unlabeled tiles.
+    ///
+    /// URL tiles keep their label under `label` rather than `file-label`.
+    pub fn label(&self) -> &str {
+        self.metadata
+            .display_name
+            .as_deref()
+            .or_else(|| {
+                (self.kind == DockItemKind::UrlTile)
+                    .then(|| self.metadata.extra.get(URL_LABEL_KEY)?.as_string())
+                    .flatten()
+            })
+            .unwrap_or_else(|| self.kind.name())
+    }
+
+    /// Whether `query` names this item, by exact bundle identifier or case-insensitive label.
+    pub fn matches(&self, query: &str) -> bool {
+        self.metadata.bundle_id.as_deref() == Some(query)
+            || self
+                .metadata
+                .display_name
+                .as_deref()
+                .is_some_and(|label| label.eq_ignore_ascii_case(query))
+    }
+
+    /// A key identifying this item when comparing two Docks.
+    ///
+    /// Prefers the bundle identifier, then the file URL in its
+    /// [canonical](crate::paths::canonical) form, then a URL tile's URL, then the label.
+    pub fn identity(&self) -> Cow<'_, str> {
+        if let Some(bundle_id) = &self.metadata.bundle_id {
+            return Cow::Borrowed(bundle_id);
+        }
+        match (&self.metadata.location, self.url()) {
+            (Some(location), _) => location.canonical_url(),
+            (None, Some(url)) => Cow::Borrowed(url),
+            (None, None) => Cow::Borrowed(self.label()),
+        }
+    }
+}
+
+/// The `tile-data` keys a URL tile keeps its URL and label under.
+const URL_KEY: &str = "url";
+const URL_LABEL_KEY: &str = "label";
+const CF_URL_STRING_KEY: &str = "_CFURLString";
+const CF_URL_STRING_TYPE_KEY: &str = "_CFURLStringType";
+
+/// The URL schemes of file shares [`DockItem::network_share`] accepts.
+const NETWORK_SHARE_SCHEMES: &[&str] = &["smb", "afp", "nfs", "cifs"];
+
+/// Whether `url` names a file share on a server, e.g. `smb://server/share`.
+pub(crate) fn is_network_share(url: &str) -> bool {
+    url.split_once("://").is_some_and(|(scheme, rest)| {
+        NETWORK_SHARE_SCHEMES.contains(&scheme.to_ascii_lowercase().as_str())
+            && rest
+                .split('/')
+                .next()
+                .is_some_and(|server| !server.is_empty())
+    })
+}
+
+/// The name a share is shown under by default: its last path component, or the server.
+pub(crate) fn network_share_name(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    rest.trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .unwrap_or(rest)
+}
+
+/// The `file-type` of folder tiles, smart folders included.
+const FOLDER_FILE_TYPE: i64 = 2;
+
+/// Whether `path` names a saved Spotlight search, going by its extension.
+pub(crate) fn is_saved_search(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "savedSearch")
+}
+
+/// Builds a [`DockItem`], for tiles [`DockItem::new`] and [`DockItem::from_path`] can't express.
+#[derive(Debug, Clone, Default)]
+pub struct DockItemBuilder {
+    kind: DockItemKind,
+    url: Option<String>,
+    url_type: Option<i32>,
+    label: Option<String>,
+    bundle_id: Option<String>,
+    tile_data: plist::Dictionary,
+    extra: plist::Dictionary,
+}
+
+impl DockItemBuilder {
+    /// Fills in the location, label, and bundle identifier from an application bundle.
+    pub fn app(self, app: &MacApp) -> Self {
+        self.kind(DockItemKind::FileTile)
+            .path(&app.path, true)
+            .label(app.display_name.clone())
+            .bundle_id(app.bundle_id.clone())
+    }
+
+    /// Sets the tile type. Defaults to [`DockItemKind::FileTile`].
+    pub fn kind(mut self, kind: DockItemKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Points the tile at a local path; `is_dir` adds the trailing slash the Dock expects.
+    pub fn path<P: AsRef<Path>>(mut self, path: P, is_dir: bool) -> Self {
+        self.url = Some(FileLocation::from_path(path.as_ref(), is_dir).url);
+        self
+    }
+
+    /// Points the tile at a URL, used verbatim.
+    pub fn url(mut self, url: impl Into<String>) -> Self {
+        self.url = Some(url.into());
+        self
+    }
+
+    /// Overrides the `_CFURLStringType` of the location. Defaults to 15, a file URL.
+    pub fn url_type(mut self, url_type: i32) -> Self {
+        self.url_type = Some(url_type);
+        self
+    }
+
+    /// Sets the label shown under the tile.
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Sets the bundle identifier.
+    pub fn bundle_id(mut self, bundle_id: impl Into<String>) -> Self {
+        self.bundle_id = Some(bundle_id.into());
+        self
+    }
+
+    /// Sets the tile's `file-type` (41 for apps, 2 for folders, 32 for documents).
+    pub fn file_type(self, file_type: i64) -> Self {
+        self.tile_data("file-type", file_type)
+    }
+
+    /// Sets how a folder's contents are sorted.
+    pub fn arrangement(self, arrangement: Arrangement) -> Self {
+        self.tile_data(ARRANGEMENT_KEY, arrangement as i64)
+    }
+
+    /// Sets whether a folder is shown as a stack or a folder icon.
+    pub fn display_as(self, display: FolderDisplay) -> Self {
+        self.tile_data(DISPLAY_AS_KEY, display as i64)
+    }
+
+    /// Sets how a folder's contents are shown when it is opened.
+    pub fn show_as(self, view: FolderView) -> Self {
+        self.tile_data(SHOW_AS_KEY, view as i64)
+    }
+
+    /// Sets the tile's `GUID`. The Dock assigns one itself when it is missing.
+    pub fn guid(mut self, guid: i64) -> Self {
+        self.extra.insert("GUID".to_string(), guid.into());
+        self
+    }
+
+    /// Sets an arbitrary `tile-data` key not covered by the other methods.
+    pub fn tile_data(mut self, key: &str, value: impl Into<plist::Value>) -> Self {
+        self.tile_data.insert(key.to_string(), value.into());
+        self
+    }
+
+    /// Finishes the tile.
+    pub fn build(self) -> DockItem {
+        let location = self.url.map(|url| FileLocation {
+            url,
+            url_type: self.url_type.unwrap_or(15),
+        });
         DockItem {
-            kind: DockItemKind::FileTile,
+            kind: self.kind,
             metadata: TileMetadata {
-                location: Some(FileLocation {
-                    url: format!("file://{}", app.path.display()),
-                    url_type: 15, // Standard file URL type
-                }),
-                display_name: Some(app.display_name.clone()),
-                bundle_id: Some(app.bundle_id.clone()),
+                location,
+                display_name: self.label,
+                bundle_id: self.bundle_id,
+                extra: self.tile_data,
             },
+            extra: self.extra,
+        }
+    }
+}
+
+/// How a folder tile sorts its contents (`arrangement`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Arrangement {
+    Name = 1,
+    #[cfg_attr(feature = "cli", value(alias = "dateadded"))]
+    DateAdded = 2,
+    #[cfg_attr(feature = "cli", value(alias = "datemodified"))]
+    DateModified = 3,
+    #[cfg_attr(feature = "cli", value(alias = "datecreated"))]
+    DateCreated = 4,
+    Kind = 5,
+}
+
+/// Whether a folder tile looks like a stack of its contents or a folder (`displayas`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum FolderDisplay {
+    Stack = 0,
+    Folder = 1,
+}
+
+/// How a folder tile presents its contents when opened (`showas`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum FolderView {
+    Automatic = 0,
+    Fan = 1,
+    Grid = 2,
+    List = 3,
+}
+
+impl Arrangement {
+    /// Every arrangement, in the order the Dock's menu lists them.
+    pub const ALL: [Arrangement; 5] = [
+        Arrangement::Name,
+        Arrangement::DateAdded,
+        Arrangement::DateModified,
+        Arrangement::DateCreated,
+        Arrangement::Kind,
+    ];
+
+    /// The name docktor uses for it, e.g. `date-added`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Arrangement::Name => "name",
+            Arrangement::DateAdded => "date-added",
+            Arrangement::DateModified => "date-modified",
+            Arrangement::DateCreated => "date-created",
+            Arrangement::Kind => "kind",
+        }
+    }
+}
+
+impl FolderDisplay {
+    /// Both displays.
+    pub const ALL: [FolderDisplay; 2] = [FolderDisplay::Stack, FolderDisplay::Folder];
+
+    /// The name docktor uses for it.
+    pub fn name(self) -> &'static str {
+        match self {
+            FolderDisplay::Stack => "stack",
+            FolderDisplay::Folder => "folder",
+        }
+    }
+}
+
+impl FolderView {
+    /// Every view, in the order the Dock's menu lists them.
+    pub const ALL: [FolderView; 4] = [
+        FolderView::Automatic,
+        FolderView::Fan,
+        FolderView::Grid,
+        FolderView::List,
+    ];
+
+    /// The name docktor uses for it.
+    pub fn name(self) -> &'static str {
+        match self {
+            FolderView::Automatic => "automatic",
+            FolderView::Fan => "fan",
+            FolderView::Grid => "grid",
+            FolderView::List => "list",
+        }
+    }
+}
+
+pub(crate) const ARRANGEMENT_KEY: &str = "arrangement";
+pub(crate) const DISPLAY_AS_KEY: &str = "displayas";
+pub(crate) const SHOW_AS_KEY: &str = "showas";
+
+/// A folder tile's stack options; `None` where the tile leaves it to the Dock's default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FolderOptions {
+    /// Stack or folder icon.
+    pub display: Option<FolderDisplay>,
+
+    /// Fan, grid, or list when opened.
+    pub view: Option<FolderView>,
+
+    /// How the contents are sorted.
+    pub arrangement: Option<Arrangement>,
+}
+
+impl std::fmt::Display for FolderOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let parts: Vec<String> = [
+            ("display", self.display.map(FolderDisplay::name)),
+            ("view", self.view.map(FolderView::name)),
+            ("sort", self.arrangement.map(Arrangement::name)),
+        ]
+        .into_iter()
+        .filter_map(|(option, value)| Some(format!("{option} {}", value?)))
+        .collect();
+        if parts.is_empty() {
+            write!(f, "defaults")
+        } else {
+            write!(f, "{}", parts.join(", "))
+        }
+    }
+}
+
+/// The variant of a folder option enum stored in the plist as `code`.
+fn from_code<T: Copy>(variants: &[T], code: i64, to_code: impl Fn(T) -> i64) -> Option<T> {
+    variants
+        .iter()
+        .copied()
+        .find(|variant| to_code(*variant) == code)
+}
+
+impl TileMetadata {
+    /// The folder options stored on this tile; unrecognized values count as unset.
+    pub fn folder_options(&self) -> FolderOptions {
+        let code = |key: &str| {
+            self.extra
+                .get(key)
+                .and_then(plist::Value::as_signed_integer)
+        };
+        FolderOptions {
+            display: code(DISPLAY_AS_KEY)
+                .and_then(|n| from_code(&FolderDisplay::ALL, n, |v| v as i64)),
+            view: code(SHOW_AS_KEY).and_then(|n| from_code(&FolderView::ALL, n, |v| v as i64)),
+            arrangement: code(ARRANGEMENT_KEY)
+                .and_then(|n| from_code(&Arrangement::ALL, n, |v| v as i64)),
+        }
+    }
+
+    /// Replaces the folder options on this tile; `None` removes an option.
+    pub fn set_folder_options(&mut self, options: &FolderOptions) {
+        let values = [
+            (DISPLAY_AS_KEY, options.display.map(|v| v as i64)),
+            (SHOW_AS_KEY, options.view.map(|v| v as i64)),
+            (ARRANGEMENT_KEY, options.arrangement.map(|v| v as i64)),
+        ];
+        for (key, value) in values {
+            match value {
+                Some(value) => self.extra.insert(key.to_string(), value.into()),
+                None => self.extra.remove(key),
+            };
         }
     }
 }
 
 /// Describes the type of Dock item.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum DockItemKind {
     /// A file-based app or document.
+    #[default]
     FileTile,
 
     /// A folder shortcut in the Dock.
     DirectoryTile,
 
+    /// A web link.
+    UrlTile,
+
     /// A visual spacer between items.
     SpacerTile,
 
+    /// A half-width spacer.
+    SmallSpacerTile,
+
+    /// A spacer that grows to fill the available space.
+    FlexSpacerTile,
+
     /// Unknown or future Dock tile types (fallback).
     #[serde(other)]
     Unknown,
 }
 
+impl DockItemKind {
+    /// The plist name of this tile type.
+    pub fn name(self) -> &'static str {
+        match self {
+            DockItemKind::FileTile => "file-tile",
+            DockItemKind::DirectoryTile => "directory-tile",
+            DockItemKind::UrlTile => "url-tile",
+            DockItemKind::SpacerTile => "spacer-tile",
+            DockItemKind::SmallSpacerTile => "small-spacer-tile",
+            DockItemKind::FlexSpacerTile => "flex-spacer-tile",
+            DockItemKind::Unknown => "unknown",
+        }
+    }
+}
+
 /// Contains metadata for a Dock item (path, label, etc.).
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(try_from = "plist::Dictionary")]
 pub struct TileMetadata {
     /// The location on disk for the Dock item.
-    #[serde(rename = "file-data")]
     pub location: Option<FileLocation>,
 
     /// The display name shown under the icon in the Dock.
-    #[serde(rename = "file-label")]
     pub display_name: Option<String>,
 
     /// The app's bundle identifier, if applicable.
-    #[serde(rename = "bundle-identifier")]
     pub bundle_id: Option<String>,
+
+    /// Any other tile-data keys (bookmarks, GUIDs, folder options), kept as-is.
+    pub extra: plist::Dictionary,
+}
+
+pub(crate) const FILE_DATA_KEY: &str = "file-data";
+pub(crate) const FILE_LABEL_KEY: &str = "file-label";
+pub(crate) const BUNDLE_ID_KEY: &str = "bundle-identifier";
+const BOOKMARK_KEY: &str = "book";
+
+impl TryFrom<plist::Dictionary> for TileMetadata {
+    type Error = plist::Error;
+
+    fn try_from(mut dict: plist::Dictionary) -> Result<Self, Self::Error> {
+        let location = dict
+            .remove(FILE_DATA_KEY)
+            .map(|value| plist::from_value(&value))
+            .transpose()?;
+        let display_name = dict
+            .remove(FILE_LABEL_KEY)
+            .map(|value| plist::from_value(&value))
+            .transpose()?;
+        let bundle_id = dict
+            .remove(BUNDLE_ID_KEY)
+            .map(|value| plist::from_value(&value))
+            .transpose()?;
+
+        Ok(TileMetadata {
+            location,
+            display_name,
+            bundle_id,
+            extra: dict,
+        })
+    }
+}
+
+impl Serialize for TileMetadata {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+
+        let mut map = serializer.serialize_map(None)?;
+        if let Some(location) = &self.location {
+            map.serialize_entry(FILE_DATA_KEY, location)?;
+        }
+        if let Some(display_name) = &self.display_name {
+            map.serialize_entry(FILE_LABEL_KEY, display_name)?;
+        }
+        if let Some(bundle_id) = &self.bundle_id {
+            map.serialize_entry(BUNDLE_ID_KEY, bundle_id)?;
+        }
+        for (key, value) in &self.extra {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
 }
 
 /// Represents the file system URL and URL type.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileLocation {
     /// The item's path as a `file://` URL string.
     #[serde(rename = "_CFURLString")]
@@ -123,3 +1548,74 @@ pub struct FileLocation {
     #[serde(rename = "_CFURLStringType")]
     pub url_type: i32,
 }
+
+impl FileLocation {
+    /// Builds a percent-encoded `file://` URL for a path on disk.
+    ///
+    /// Directories (including `.app` bundles) get a trailing slash, as the Dock writes them.
+    ///
+    /// Cryptex and firmlink paths are written in their [canonical](crate::paths::canonical) form.
+    pub fn from_path(path: &Path, is_dir: bool) -> Self {
+        let path = paths::canonical(path);
+        let mut url = String::from("file://");
+        for byte in path.to_string_lossy().trim_end_matches('/').bytes() {
+            match byte {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'.' | b'_' | b'~' => {
+                    url.push(byte as char)
+                }
+                _ => url.push_str(&format!("%{byte:02X}")),
+            }
+        }
+        if is_dir {
+            url.push('/');
+        }
+
+        FileLocation {
+            url,
+            url_type: 15, // Standard file URL type
+        }
+    }
+
+    /// Decodes a `file://` URL back into a local path, in its canonical form.
+    pub fn path(&self) -> Option<PathBuf> {
+        self.raw_path().map(|path| paths::canonical(&path))
+    }
+
+    /// The URL with any cryptex or firmlink path rewritten, for comparing tiles.
+    pub fn canonical_url(&self) -> Cow<'_, str> {
+        match self.raw_path() {
+            Some(raw) if paths::canonical(&raw) != raw => {
+                Cow::Owned(Self::from_path(&raw, self.url.ends_with('/')).url)
+            }
+            _ => Cow::Borrowed(&self.url),
+        }
+    }
+
+    /// Decodes a `file://` URL exactly as written.
+    fn raw_path(&self) -> Option<PathBuf> {
+        let encoded = self.url.strip_prefix("file://")?;
+        let (bytes, mut decoded) = (encoded.as_bytes(), Vec::with_capacity(encoded.len()));
+
+        let mut i = 0;
+        while i < bytes.len() {
+            let hex = (bytes[i] == b'%')
+                .then(|| encoded.get(i + 1..i + 3))
+                .flatten()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+            match hex {
+                Some(byte) => {
+                    decoded.push(byte);
+                    i += 3;
+                }
+                None => {
+                    decoded.push(bytes[i]);
+                    i += 1;
+                }
+            }
+        }
+
+        let path = String::from_utf8_lossy(&decoded);
+        let path = path.trim_end_matches('/');
+        Some(PathBuf::from(if path.is_empty() { "/" } else { path }))
+    }
+}
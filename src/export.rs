@@ -0,0 +1,384 @@
+use crate::dock::{Arrangement, Dock, DockItem, DockItemKind, FolderDisplay, FolderView, Section};
+use crate::profile::{Profile, spacer_keyword};
+use anyhow::{Context, Result};
+use std::fmt::Write;
+
+/// Tile-data keys that describe how an item looks rather than where it lives on
+/// this particular machine; everything else (bookmarks, GUIDs) is left out of scripts.
+const PORTABLE_TILE_KEYS: &[&str] = &[
+    "arrangement",
+    "displayas",
+    "showas",
+    "preferreditemsize",
+    "file-type",
+];
+
+/// The tool a generated rebuild script drives.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum ScriptFlavor {
+    /// Embed a profile and run `docktor apply`.
+    #[default]
+    Docktor,
+
+    /// Use `dockutil` commands.
+    Dockutil,
+
+    /// Use `defaults write` directly.
+    Defaults,
+}
+
+/// A declarative format `docktor export` can write instead of a script.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum ExportFormat {
+    /// A docktor YAML profile.
+    #[default]
+    Profile,
+
+    /// A nix-darwin module setting `system.defaults.dock`.
+    NixDarwin,
+
+    /// A chezmoi `run_onchange_` script template that applies an embedded profile.
+    Chezmoi,
+}
+
+/// `system.defaults.dock` options nix-darwin defines; other settings go through
+/// `CustomUserPreferences`, since nix-darwin rejects unknown options.
+const NIX_DARWIN_OPTIONS: &[&str] = &[
+    "appswitcher-all-displays",
+    "autohide",
+    "autohide-delay",
+    "autohide-time-modifier",
+    "dashboard-in-overlay",
+    "enable-spring-load-actions-on-all-items",
+    "expose-animation-duration",
+    "expose-group-apps",
+    "largesize",
+    "launchanim",
+    "magnification",
+    "mineffect",
+    "minimize-to-application",
+    "mouse-over-hilite-stack",
+    "mru-spaces",
+    "orientation",
+    "scroll-to-open",
+    "show-process-indicators",
+    "show-recents",
+    "showhidden",
+    "slow-motion-allowed",
+    "static-only",
+    "tilesize",
+    "wvous-bl-corner",
+    "wvous-br-corner",
+    "wvous-tl-corner",
+    "wvous-tr-corner",
+];
+
+/// Renders `dock` in a declarative format.
+pub fn render(dock: &Dock, format: ExportFormat) -> Result<String> {
+    match format {
+        ExportFormat::Profile => Profile::from_dock(dock).to_yaml(),
+        ExportFormat::NixDarwin => Ok(nix_darwin(dock)),
+        ExportFormat::Chezmoi => chezmoi(dock),
+    }
+}
+
+/// Renders `dock` as a commented profile for a new user to start from.
+///
+/// The keys come in the order the Dock shows them, apps, then others, then
+/// settings, each introduced by a comment explaining it.
+pub fn scaffold(dock: &Dock) -> Result<String> {
+    let profile = Profile::from_dock(dock);
+    let mut out = String::from(
+        "# A docktor profile, captured from the Dock.\n\
+         # Apply it with `docktor apply <file>`; see what differs with `docktor check <file>`.\n\n",
+    );
+    out.push_str(
+        "# Apps pinned on the left, in order: paths to .app bundles, bundle identifiers,\n\
+         # or app names. Use spacer, small-spacer, or flex-spacer for gaps, and\n\
+         # `app: [First, Fallback]` with `optional: true` for apps not every Mac has.\n\
+         # Remove the key to leave apps alone.\n",
+    );
+    out.push_str(&yaml_key("apps", &profile.apps)?);
+    out.push_str(
+        "\n# Folders and files pinned on the right, next to the Trash.\n\
+         # Remove the key to leave them alone.\n",
+    );
+    out.push_str(&yaml_key("others", &profile.others)?);
+    out.push_str(
+        "\n# Dock preferences, by their com.apple.dock name. Settings not listed keep\n\
+         # whatever value they have.\n",
+    );
+    out.push_str(&yaml_key("settings", &profile.settings)?);
+    out.push_str(
+        "\n# Other keys: `add` to insert items at a position, `group_by_category`,\n\
+         # `max_items`, `separator_after` to end app groups with a spacer, `login_items`,\n\
+         # `desktop` for window manager settings, `hooks`, and `extends`/`include` to\n\
+         # layer profiles.\n",
+    );
+    Ok(out)
+}
+
+/// Serializes a single top-level profile key.
+fn yaml_key(key: &str, value: &impl serde::Serialize) -> Result<String> {
+    let mut map = serde_yaml::Mapping::new();
+    map.insert(
+        key.into(),
+        serde_yaml::to_value(value).context("Failed to serialize profile")?,
+    );
+    serde_yaml::to_string(&map).context("Failed to serialize profile")
+}
+
+/// Renders a nix-darwin module that recreates `dock`.
+///
+/// Paths are written out in full, since nix-darwin has no notion of `~`. Flexible
+/// spacers have no nix-darwin equivalent and are left as comments.
+fn nix_darwin(dock: &Dock) -> String {
+    let mut out = String::from("# Generated by `docktor export --format nix-darwin`.\n{\n");
+    out.push_str("  system.defaults.dock = {\n");
+    let mut custom = Vec::new();
+    for (key, value) in dock.settings() {
+        let Some(value) = nix_value(value) else {
+            continue;
+        };
+        if NIX_DARWIN_OPTIONS.contains(&key.as_str()) {
+            let _ = writeln!(out, "    {} = {value};", nix_attr(key));
+        } else {
+            custom.push((key, value));
+        }
+    }
+
+    for (section, attr) in [
+        (Section::Apps, "persistent-apps"),
+        (Section::Others, "persistent-others"),
+    ] {
+        let items = dock.section(section);
+        if items.is_empty() {
+            continue;
+        }
+        let _ = writeln!(out, "    {attr} = [");
+        for item in items {
+            let line = match item.kind {
+                DockItemKind::SpacerTile => "{ spacer = { small = false; }; }".to_string(),
+                DockItemKind::SmallSpacerTile => "{ spacer = { small = true; }; }".to_string(),
+                DockItemKind::FlexSpacerTile => "# flexible spacer".to_string(),
+                _ => match item.path() {
+                    Some(path) => nix_string(&path.to_string_lossy()),
+                    None => format!("# {} (not a local file)", item.label()),
+                },
+            };
+            let _ = writeln!(out, "      {line}");
+        }
+        out.push_str("    ];\n");
+    }
+    out.push_str("  };\n");
+
+    if !custom.is_empty() {
+        out.push_str("  system.defaults.CustomUserPreferences.\"com.apple.dock\" = {\n");
+        for (key, value) in custom {
+            let _ = writeln!(out, "    {} = {value};", nix_attr(key));
+        }
+        out.push_str("  };\n");
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Renders a chezmoi script template that applies `dock` as a profile on macOS.
+///
+/// Saved as e.g. `run_onchange_after_dock.sh.tmpl`, chezmoi reruns it whenever the
+/// embedded profile changes.
+fn chezmoi(dock: &Dock) -> Result<String> {
+    let script = script(dock, ScriptFlavor::Docktor)?;
+    let body = script
+        .strip_prefix("#!/bin/sh\n")
+        .unwrap_or(&script)
+        .replace(
+            "`docktor export --script`",
+            "`docktor export --format chezmoi`",
+        );
+    Ok(format!(
+        "{{{{- if eq .chezmoi.os \"darwin\" -}}}}\n#!/bin/sh\n{body}{{{{ end -}}}}\n"
+    ))
+}
+
+/// Formats a scalar setting as a Nix value.
+fn nix_value(value: &plist::Value) -> Option<String> {
+    match value {
+        plist::Value::Boolean(b) => Some(b.to_string()),
+        plist::Value::Integer(i) => Some(i.to_string()),
+        plist::Value::Real(r) => Some(format!("{r:?}")),
+        plist::Value::String(s) => Some(nix_string(s)),
+        _ => None,
+    }
+}
+
+/// Quotes a string for Nix, escaping interpolation.
+fn nix_string(s: &str) -> String {
+    let escaped = s
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace("${", "\\${");
+    format!("\"{escaped}\"")
+}
+
+/// Writes a setting name as a Nix attribute, quoting it if needed.
+fn nix_attr(key: &str) -> String {
+    let bare = key
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        && key.starts_with(|c: char| c.is_ascii_alphabetic());
+    if bare {
+        key.to_string()
+    } else {
+        nix_string(key)
+    }
+}
+
+/// Renders a shell script that rebuilds `dock` using the given tool.
+pub fn script(dock: &Dock, flavor: ScriptFlavor) -> Result<String> {
+    let mut out = String::from(
+        "#!/bin/sh\n# Rebuilds the Dock. Generated by `docktor export --script`.\nset -e\n\n",
+    );
+
+    match flavor {
+        ScriptFlavor::Docktor => {
+            let yaml = Profile::from_dock(dock).to_yaml()?;
+            out.push_str("profile=\"$(mktemp -t docktor)\"\n");
+            out.push_str("trap 'rm -f \"$profile\"' EXIT\n");
+            out.push_str("cat > \"$profile\" <<'DOCKTOR_PROFILE'\n");
+            out.push_str(&yaml);
+            out.push_str("DOCKTOR_PROFILE\n");
+            out.push_str("docktor apply \"$profile\"\n");
+            return Ok(out);
+        }
+        ScriptFlavor::Dockutil => {
+            out.push_str("dockutil --remove all --no-restart\n");
+            for section in Section::ALL {
+                for item in dock.section(section) {
+                    if let Some(line) = dockutil_add(section, item) {
+                        let _ = writeln!(out, "{line}");
+                    }
+                }
+            }
+        }
+        ScriptFlavor::Defaults => {
+            for section in Section::ALL {
+                let _ = writeln!(
+                    out,
+                    "defaults write com.apple.dock {} -array",
+                    section.key()
+                );
+                for item in dock.section(section) {
+                    let _ = writeln!(
+                        out,
+                        "defaults write com.apple.dock {} -array-add {}",
+                        section.key(),
+                        shell_quote(&tile_fragment(item)?)
+                    );
+                }
+            }
+        }
+    }
+
+    for (key, value) in dock.settings() {
+        if let Some(arg) = defaults_arg(value) {
+            let _ = writeln!(
+                out,
+                "defaults write com.apple.dock {} {}",
+                shell_quote(key),
+                arg
+            );
+        }
+    }
+    out.push_str("killall Dock\n");
+    Ok(out)
+}
+
+/// Builds the `dockutil --add` invocation for a single tile.
+fn dockutil_add(section: Section, item: &DockItem) -> Option<String> {
+    let section_arg = section.id();
+    if let Some(spacer_type) = spacer_keyword(item.kind) {
+        return Some(format!(
+            "dockutil --add '' --type {spacer_type} --section {section_arg} --no-restart"
+        ));
+    }
+
+    if let Some(url) = item.url() {
+        return Some(format!(
+            "dockutil --add {} --label {} --section {section_arg} --no-restart",
+            shell_quote(url),
+            shell_quote(item.label())
+        ));
+    }
+
+    let path = item.path()?;
+    let mut line = format!(
+        "dockutil --add {} --section {section_arg}",
+        shell_quote(&path.to_string_lossy())
+    );
+    if item.kind == DockItemKind::DirectoryTile {
+        let options = item.metadata.folder_options();
+        let view = options.view.map(|view| match view {
+            FolderView::Automatic => "auto",
+            FolderView::Fan => "fan",
+            FolderView::Grid => "grid",
+            FolderView::List => "list",
+        });
+        let display = options.display.map(FolderDisplay::name);
+        let sort = options.arrangement.map(|arrangement| match arrangement {
+            Arrangement::Name => "name",
+            Arrangement::DateAdded => "dateadded",
+            Arrangement::DateModified => "datemodified",
+            Arrangement::DateCreated => "datecreated",
+            Arrangement::Kind => "kind",
+        });
+        for (flag, value) in [("--view", view), ("--display", display), ("--sort", sort)] {
+            if let Some(value) = value {
+                let _ = write!(line, " {flag} {value}");
+            }
+        }
+    }
+    line.push_str(" --no-restart");
+    Some(line)
+}
+
+/// Serializes a tile as the XML fragment `defaults -array-add` accepts, keeping only portable keys.
+fn tile_fragment(item: &DockItem) -> Result<String> {
+    let mut item = item.clone();
+    item.extra.clear();
+    item.metadata
+        .extra
+        .retain(|key, _| PORTABLE_TILE_KEYS.contains(&key.as_str()));
+
+    let mut xml = Vec::new();
+    plist::to_writer_xml_with_options(
+        &mut xml,
+        &item,
+        &plist::XmlWriteOptions::default()
+            .indent(b' ', 0)
+            .root_element(false),
+    )
+    .context("Failed to serialize Dock item")?;
+
+    Ok(String::from_utf8(xml)
+        .context("Serialized Dock item was not UTF-8")?
+        .replace('\n', ""))
+}
+
+/// Formats a setting as `defaults write` type flag and value, if it is a scalar.
+fn defaults_arg(value: &plist::Value) -> Option<String> {
+    match value {
+        plist::Value::Boolean(b) => Some(format!("-bool {b}")),
+        plist::Value::Integer(i) => Some(format!("-int {i}")),
+        plist::Value::Real(r) => Some(format!("-float {r}")),
+        plist::Value::String(s) => Some(format!("-string {}", shell_quote(s))),
+        _ => None,
+    }
+}
+
+/// Quotes a string for POSIX shells.
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
@@ -0,0 +1,212 @@
+//! A C ABI over the Dock model, for Swift and Objective-C callers.
+//!
+//! Build with `--features ffi` to also generate `include/docktor.h`. Functions that
+//! can fail return `NULL` or a negative number and record a message retrievable
+//! with [`docktor_last_error`].
+
+use crate::dock::{AddPolicy, Dock};
+use crate::mac_app::MacApp;
+use std::cell::RefCell;
+use std::ffi::{CStr, CString, c_char, c_int};
+use std::ptr;
+
+/// An opaque handle to a Dock configuration.
+pub struct DocktorDock(Dock);
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(err: anyhow::Error) {
+    let message = CString::new(format!("{err:#}").replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// Converts a C string argument, recording an error for `NULL` or invalid UTF-8.
+unsafe fn str_arg<'a>(s: *const c_char, name: &str) -> Option<&'a str> {
+    if s.is_null() {
+        set_last_error(anyhow::anyhow!("{name} must not be NULL"));
+        return None;
+    }
+    // SAFETY: the caller guarantees `s` is a valid NUL-terminated string.
+    match unsafe { CStr::from_ptr(s) }.to_str() {
+        Ok(s) => Some(s),
+        Err(err) => {
+            set_last_error(anyhow::anyhow!("{name} is not valid UTF-8: {err}"));
+            None
+        }
+    }
+}
+
+/// Returns the message for the most recent error on this thread, or `NULL`.
+///
+/// The string is owned by docktor and valid until the next failing call on this thread.
+#[unsafe(no_mangle)]
+pub extern "C" fn docktor_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |s| s.as_ptr()))
+}
+
+/// Creates an empty Dock. Free it with [`docktor_dock_free`].
+#[unsafe(no_mangle)]
+pub extern "C" fn docktor_dock_new() -> *mut DocktorDock {
+    Box::into_raw(Box::new(DocktorDock(Dock::new())))
+}
+
+/// Loads the current user's Dock, or returns `NULL` on error.
+#[unsafe(no_mangle)]
+pub extern "C" fn docktor_dock_load() -> *mut DocktorDock {
+    match Dock::load() {
+        Ok(dock) => Box::into_raw(Box::new(DocktorDock(dock))),
+        Err(err) => {
+            set_last_error(err);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Loads a Dock from the plist at `path`, or returns `NULL` on error.
+///
+/// # Safety
+///
+/// `path` must be a valid NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn docktor_dock_load_from(path: *const c_char) -> *mut DocktorDock {
+    let Some(path) = (unsafe { str_arg(path, "path") }) else {
+        return ptr::null_mut();
+    };
+    match Dock::load_from(path) {
+        Ok(dock) => Box::into_raw(Box::new(DocktorDock(dock))),
+        Err(err) => {
+            set_last_error(err);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Appends the `.app` bundle at `app_path` to the Dock unless it is already there.
+/// Returns 0 on success, -1 on error.
+///
+/// # Safety
+///
+/// `dock` must come from one of the `docktor_dock_*` constructors and not be freed;
+/// `app_path` must be a valid NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn docktor_dock_add_app(
+    dock: *mut DocktorDock,
+    app_path: *const c_char,
+) -> c_int {
+    // SAFETY: the caller guarantees `dock` is a live handle.
+    let Some(dock) = (unsafe { dock.as_mut() }) else {
+        set_last_error(anyhow::anyhow!("dock must not be NULL"));
+        return -1;
+    };
+    let Some(app_path) = (unsafe { str_arg(app_path, "app_path") }) else {
+        return -1;
+    };
+    match MacApp::from_path(app_path) {
+        Ok(app) => {
+            dock.0.add_app(&app, AddPolicy::Skip);
+            0
+        }
+        Err(err) => {
+            set_last_error(err);
+            -1
+        }
+    }
+}
+
+/// Removes items whose label or bundle id is `query`. Returns the number removed, or -1 on error.
+///
+/// # Safety
+///
+/// `dock` must come from one of the `docktor_dock_*` constructors and not be freed;
+/// `query` must be a valid NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn docktor_dock_remove(
+    dock: *mut DocktorDock,
+    query: *const c_char,
+) -> c_int {
+    // SAFETY: the caller guarantees `dock` is a live handle.
+    let Some(dock) = (unsafe { dock.as_mut() }) else {
+        set_last_error(anyhow::anyhow!("dock must not be NULL"));
+        return -1;
+    };
+    let Some(query) = (unsafe { str_arg(query, "query") }) else {
+        return -1;
+    };
+    c_int::try_from(dock.0.remove(query)).unwrap_or(c_int::MAX)
+}
+
+/// Saves the Dock to the current user's preferences. Returns 0 on success, -1 on error.
+///
+/// # Safety
+///
+/// `dock` must come from one of the `docktor_dock_*` constructors and not be freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn docktor_dock_save(dock: *const DocktorDock) -> c_int {
+    // SAFETY: the caller guarantees `dock` is a live handle.
+    let Some(dock) = (unsafe { dock.as_ref() }) else {
+        set_last_error(anyhow::anyhow!("dock must not be NULL"));
+        return -1;
+    };
+    match dock.0.save() {
+        Ok(()) => 0,
+        Err(err) => {
+            set_last_error(err);
+            -1
+        }
+    }
+}
+
+/// Saves the Dock to the plist at `path`. Returns 0 on success, -1 on error.
+///
+/// # Safety
+///
+/// `dock` must come from one of the `docktor_dock_*` constructors and not be freed;
+/// `path` must be a valid NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn docktor_dock_save_to(
+    dock: *const DocktorDock,
+    path: *const c_char,
+) -> c_int {
+    // SAFETY: the caller guarantees `dock` is a live handle.
+    let Some(dock) = (unsafe { dock.as_ref() }) else {
+        set_last_error(anyhow::anyhow!("dock must not be NULL"));
+        return -1;
+    };
+    let Some(path) = (unsafe { str_arg(path, "path") }) else {
+        return -1;
+    };
+    match dock.0.save_to(path) {
+        Ok(()) => 0,
+        Err(err) => {
+            set_last_error(err);
+            -1
+        }
+    }
+}
+
+/// Restarts the Dock so saved changes take effect. Returns 0 on success, -1 on error.
+#[unsafe(no_mangle)]
+pub extern "C" fn docktor_restart() -> c_int {
+    match Dock::restart() {
+        Ok(()) => 0,
+        Err(err) => {
+            set_last_error(err);
+            -1
+        }
+    }
+}
+
+/// Frees a Dock handle. Passing `NULL` is a no-op.
+///
+/// # Safety
+///
+/// `dock` must come from one of the `docktor_dock_*` constructors and not already be freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn docktor_dock_free(dock: *mut DocktorDock) {
+    if !dock.is_null() {
+        // SAFETY: the caller guarantees `dock` was created by `Box::into_raw` and is live.
+        drop(unsafe { Box::from_raw(dock) });
+    }
+}
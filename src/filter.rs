@@ -0,0 +1,66 @@
+use crate::dock::{Dock, DockItem};
+use anyhow::{Context, Result};
+
+/// A pattern matched against an item's bundle identifier, label, and path.
+#[derive(Debug, Clone)]
+pub enum Matcher {
+    /// A shell-style glob such as `com.adobe.*`, ignoring case.
+    Glob(glob::Pattern),
+
+    /// A regular expression, matched anywhere in the field unless anchored.
+    Regex(regex::Regex),
+}
+
+impl Matcher {
+    /// Compiles a glob pattern.
+    pub fn glob(pattern: &str) -> Result<Self> {
+        glob::Pattern::new(pattern)
+            .map(Matcher::Glob)
+            .with_context(|| format!("Invalid glob {pattern:?}"))
+    }
+
+    /// Compiles a regular expression.
+    pub fn regex(pattern: &str) -> Result<Self> {
+        regex::Regex::new(pattern)
+            .map(Matcher::Regex)
+            .with_context(|| format!("Invalid regex {pattern:?}"))
+    }
+
+    /// Whether the pattern matches the item's bundle identifier, label, or path.
+    pub fn matches(&self, item: &DockItem) -> bool {
+        let path = item.path().map(|path| path.to_string_lossy().into_owned());
+        [
+            item.metadata.bundle_id.as_deref(),
+            item.metadata.display_name.as_deref(),
+            path.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        .any(|field| match self {
+            Matcher::Glob(pattern) => pattern.matches_with(
+                field,
+                glob::MatchOptions {
+                    case_sensitive: false,
+                    ..Default::default()
+                },
+            ),
+            Matcher::Regex(regex) => regex.is_match(field),
+        })
+    }
+}
+
+impl Dock {
+    /// Removes every item any of `matchers` match, returning how many were removed.
+    pub fn remove_matching(&mut self, matchers: &[Matcher]) -> usize {
+        let mut removed = 0;
+        for items in [&mut self.applications, &mut self.others]
+            .into_iter()
+            .flatten()
+        {
+            let before = items.len();
+            items.retain(|item| !matchers.iter().any(|matcher| matcher.matches(item)));
+            removed += before - items.len();
+        }
+        removed
+    }
+}
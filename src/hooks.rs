@@ -0,0 +1,213 @@
+use crate::diff::{Change, DockDiff};
+use crate::dock::Section;
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Hooks a profile runs when something happens to the Dock.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Hooks {
+    /// Shell commands run before the profile's changes are written; any failure aborts the apply.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_apply: Vec<String>,
+
+    /// Shell commands run after the profile's changes are written.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_apply: Vec<String>,
+
+    /// Run after the profile changes the Dock.
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        with = "serde_yaml::with::singleton_map_recursive"
+    )]
+    pub on_apply: Vec<Hook>,
+
+    /// Run when `docktor check` finds the live Dock has drifted from the profile.
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        with = "serde_yaml::with::singleton_map_recursive"
+    )]
+    pub on_drift: Vec<Hook>,
+}
+
+/// Somewhere to send an event's JSON payload, written in YAML as `webhook: <url>`
+/// or `exec: <command>`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum Hook {
+    /// POST the payload to this URL.
+    Webhook(String),
+
+    /// Run this shell command with the payload on stdin and `DOCKTOR_EVENT` and
+    /// `DOCKTOR_SUMMARY` set.
+    Exec(String),
+}
+
+/// What happened, as reported to hooks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Event {
+    /// Changes are about to be written.
+    PreApply,
+
+    /// Changes were just written.
+    PostApply,
+
+    /// A profile was applied and changed the Dock.
+    Applied,
+
+    /// The live Dock no longer matches a profile.
+    Drift,
+}
+
+impl Event {
+    fn name(self) -> &'static str {
+        match self {
+            Event::PreApply => "pre_apply",
+            Event::PostApply => "post_apply",
+            Event::Applied => "applied",
+            Event::Drift => "drift",
+        }
+    }
+}
+
+/// The JSON body sent to hooks.
+#[derive(Debug, Serialize)]
+struct Payload<'a> {
+    event: Event,
+    host: String,
+    profile: Option<&'a str>,
+    summary: String,
+    changes: Vec<ChangeSummary>,
+}
+
+/// One change in a hook payload.
+#[derive(Debug, Serialize)]
+struct ChangeSummary {
+    change: &'static str,
+    section: Option<&'static str>,
+    label: String,
+    from: Option<usize>,
+    to: Option<usize>,
+}
+
+impl From<&Change> for ChangeSummary {
+    fn from(change: &Change) -> Self {
+        ChangeSummary {
+            change: change.kind(),
+            section: change.section().map(Section::id),
+            label: change.label().to_string(),
+            from: change.from(),
+            to: change.to(),
+        }
+    }
+}
+
+/// Sends `event` to every hook, logging failures rather than returning them.
+///
+/// A broken webhook shouldn't undo a successful apply.
+#[tracing::instrument(skip_all, fields(event = event.name()))]
+pub fn fire(hooks: &[Hook], event: Event, profile: Option<&str>, diff: &DockDiff) {
+    if hooks.is_empty() {
+        return;
+    }
+
+    let body = match payload(event, profile, diff) {
+        Ok(body) => body,
+        Err(err) => {
+            tracing::warn!("{err:#}");
+            return;
+        }
+    };
+    for hook in hooks {
+        if let Err(err) = run(hook, event, diff, &body) {
+            tracing::warn!("{err:#}");
+        }
+    }
+}
+
+/// Runs each shell command in turn with the payload on stdin, stopping at the first failure.
+#[tracing::instrument(skip_all, fields(event = event.name()))]
+pub fn run_commands<'a>(
+    commands: impl IntoIterator<Item = &'a String>,
+    event: Event,
+    profile: Option<&str>,
+    diff: &DockDiff,
+) -> Result<()> {
+    let mut commands = commands.into_iter().peekable();
+    if commands.peek().is_none() {
+        return Ok(());
+    }
+
+    let body = payload(event, profile, diff)?;
+    for command in commands {
+        run(&Hook::Exec(command.clone()), event, diff, &body)?;
+    }
+    Ok(())
+}
+
+fn payload(event: Event, profile: Option<&str>, diff: &DockDiff) -> Result<Vec<u8>> {
+    let payload = Payload {
+        event,
+        host: crate::macos::hostname(),
+        profile,
+        summary: diff.summary(),
+        changes: diff.changes.iter().map(ChangeSummary::from).collect(),
+    };
+    serde_json::to_vec(&payload).context("Failed to serialize hook payload")
+}
+
+fn run(hook: &Hook, event: Event, diff: &DockDiff, body: &[u8]) -> Result<()> {
+    let mut command = match hook {
+        #[cfg(not(feature = "network"))]
+        Hook::Webhook(url) => {
+            bail!("Can't post to {url}: docktor was built without the network feature")
+        }
+        #[cfg(feature = "network")]
+        Hook::Webhook(url) => {
+            let mut command = Command::new("curl");
+            command
+                .args(["--fail", "--silent", "--show-error", "--max-time", "30"])
+                .args(["--header", "Content-Type: application/json"])
+                .args(["--data-binary", "@-"])
+                .arg(url);
+            command
+        }
+        Hook::Exec(script) => {
+            let mut command = Command::new("sh");
+            command
+                .arg("-c")
+                .arg(script)
+                .env("DOCKTOR_EVENT", event.name())
+                .env("DOCKTOR_SUMMARY", diff.summary());
+            command
+        }
+    };
+    tracing::debug!(?hook, "running hook");
+
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run hook {hook:?}"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A hook that ignores its input may close stdin early; that's fine.
+        let _ = stdin.write_all(body);
+    }
+    let output = child
+        .wait_with_output()
+        .with_context(|| format!("Failed to run hook {hook:?}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if stderr.trim().is_empty() {
+            bail!("Hook {hook:?} failed with {}", output.status);
+        }
+        bail!("Hook {hook:?} failed: {}", stderr.trim());
+    }
+    Ok(())
+}
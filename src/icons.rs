@@ -0,0 +1,206 @@
+//! A cache of app icons converted from `.icns` to PNG, so each icon is only decoded once.
+//!
+//! Entries are PNG files stored by bundle ID, version, and size, e.g.
+//! `icons/com.apple.Safari@17.4-64.png`, so an app update gets a fresh icon while
+//! unchanged apps are read straight from disk. GUI frontends can show the files as they
+//! are. Only built with the `render` feature.
+
+use crate::config;
+use crate::mac_app::MacApp;
+use crate::macos;
+use anyhow::{Context, Result};
+use icns::{IconFamily, Image, PixelFormat};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Icons converted to PNG, kept in a folder.
+#[derive(Debug, Clone)]
+pub struct IconCache {
+    dir: PathBuf,
+}
+
+impl IconCache {
+    /// The cache in docktor's state folder.
+    pub fn open() -> Result<Self> {
+        Ok(Self::at(config::state_dir()?.join("icons")))
+    }
+
+    /// A cache kept in `dir`, which is created when the first icon is stored.
+    pub fn at(dir: impl Into<PathBuf>) -> Self {
+        IconCache { dir: dir.into() }
+    }
+
+    /// The folder the icons are kept in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The PNG of `app`'s icon at `size` pixels square, converting it first if it isn't
+    /// cached, or `None` if the app has no icon.
+    ///
+    /// Storing an icon removes the ones cached for other versions of the same app.
+    pub fn app_icon(&self, app: &MacApp, size: u32) -> Result<Option<PathBuf>> {
+        let Some(icns) = &app.icon else {
+            return Ok(None);
+        };
+        let name = file_name(&app.bundle_id);
+        let version = file_name(app.version.as_deref().unwrap_or("unversioned"));
+        let entry = self.dir.join(format!("{name}@{version}-{size}.png"));
+        if !entry.exists() {
+            self.store(icns, &entry, size)?;
+            self.remove_other_versions(&name, &version);
+        }
+        Ok(Some(entry))
+    }
+
+    /// The PNG of the `.icns` file at `icns` at `size` pixels square, for icons that don't
+    /// belong to an app, such as the generic folder icon.
+    ///
+    /// These are stored by file name and macOS version, since they change with macOS.
+    pub fn file_icon(&self, icns: &Path, size: u32) -> Result<PathBuf> {
+        let name = file_name(&icns.file_stem().unwrap_or_default().to_string_lossy());
+        let version = macos::version().map_or_else(|| "unknown".to_string(), |v| v.to_string());
+        let entry = self.dir.join(format!("{name}@macos-{version}-{size}.png"));
+        if !entry.exists() {
+            self.store(icns, &entry, size)?;
+        }
+        Ok(entry)
+    }
+
+    /// Deletes every cached icon, returning how many there were.
+    pub fn clear(&self) -> Result<usize> {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Ok(0);
+        };
+        let mut removed = 0;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "png") {
+                fs::remove_file(&path)
+                    .with_context(|| format!("Failed to delete {}", path.display()))?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Converts `icns` and writes it to `entry`, through a temporary file so a reader never
+    /// sees half an icon.
+    fn store(&self, icns: &Path, entry: &Path, size: u32) -> Result<()> {
+        let image = read_icns(icns, size)?;
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        let partial = entry.with_extension("png.partial");
+        let file = fs::File::create(&partial)
+            .with_context(|| format!("Failed to create {}", partial.display()))?;
+        image
+            .write_png(std::io::BufWriter::new(file))
+            .with_context(|| format!("Failed to write {}", partial.display()))?;
+        fs::rename(&partial, entry)
+            .with_context(|| format!("Failed to save {}", entry.display()))?;
+        tracing::debug!("cached {} as {}", icns.display(), entry.display());
+        Ok(())
+    }
+
+    /// Removes icons cached for `name` at any version but `version`.
+    fn remove_other_versions(&self, name: &str, version: &str) {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let file = entry.file_name().to_string_lossy().into_owned();
+            let Some((entry_name, rest)) = file.split_once('@') else {
+                continue;
+            };
+            let stale = entry_name == name
+                && rest
+                    .rsplit_once('-')
+                    .is_some_and(|(entry_version, _)| entry_version != version);
+            if stale && let Err(err) = fs::remove_file(entry.path()) {
+                tracing::warn!("Could not delete {}: {err}", entry.path().display());
+            }
+        }
+    }
+}
+
+/// Reads a cached PNG as RGBA.
+pub fn load(png: &Path) -> Result<Image> {
+    let file = fs::File::open(png).with_context(|| format!("Failed to open {}", png.display()))?;
+    let image = Image::read_png(std::io::BufReader::new(file))
+        .with_context(|| format!("Failed to read {}", png.display()))?;
+    Ok(image.convert_to(PixelFormat::RGBA))
+}
+
+/// Reads the image in an `.icns` file closest to `size`, scaled to `size` square, as RGBA.
+pub fn read_icns(file: &Path, size: u32) -> Result<Image> {
+    let family = IconFamily::read(std::io::BufReader::new(
+        fs::File::open(file).with_context(|| format!("Failed to open {}", file.display()))?,
+    ))
+    .with_context(|| format!("Failed to read {}", file.display()))?;
+
+    // The smallest image at least as big as asked for, or else the biggest there is.
+    let mut types: Vec<_> = family
+        .available_icons()
+        .into_iter()
+        .filter(|icon_type| !icon_type.is_mask())
+        .collect();
+    types.sort_by_key(|icon_type| icon_type.pixel_width());
+    let best = types
+        .iter()
+        .find(|icon_type| icon_type.pixel_width() >= size)
+        .or(types.last())
+        .with_context(|| format!("{} has no images", file.display()))?;
+    let image = family
+        .get_icon_with_type(*best)
+        .with_context(|| format!("Failed to decode {}", file.display()))?;
+    Ok(scale(&image.convert_to(PixelFormat::RGBA), size))
+}
+
+/// Resizes an RGBA image to `size` square, averaging the pixels each one covers.
+pub fn scale(image: &Image, size: u32) -> Image {
+    if image.width() == size && image.height() == size {
+        return image.clone();
+    }
+    let mut scaled = Image::new(PixelFormat::RGBA, size, size);
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let source = image.data();
+    let data = scaled.data_mut();
+    for y in 0..size as usize {
+        let (y0, y1) = span(y, size as usize, height);
+        for x in 0..size as usize {
+            let (x0, x1) = span(x, size as usize, width);
+            let mut sum = [0u32; 4];
+            for sy in y0..y1 {
+                for sx in x0..x1 {
+                    let pixel = &source[(sy * width + sx) * 4..][..4];
+                    for (total, channel) in sum.iter_mut().zip(pixel) {
+                        *total += u32::from(*channel);
+                    }
+                }
+            }
+            let count = ((y1 - y0) * (x1 - x0)) as u32;
+            let pixel = &mut data[(y * size as usize + x) * 4..][..4];
+            for (channel, total) in pixel.iter_mut().zip(sum) {
+                *channel = (total / count) as u8;
+            }
+        }
+    }
+    scaled
+}
+
+/// The source pixels destination pixel `i` of `size` covers in a row of `len`.
+fn span(i: usize, size: usize, len: usize) -> (usize, usize) {
+    let start = i * len / size;
+    let end = ((i + 1) * len / size).max(start + 1).min(len);
+    (start.min(len - 1), end)
+}
+
+/// `text` with anything that doesn't belong in a cache file name replaced by `_`.
+fn file_name(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '_' => c,
+            _ => '_',
+        })
+        .collect()
+}
@@ -0,0 +1,118 @@
+//! Layering profiles with `extends:` and `include:`.
+//!
+//! A profile is built from its layers in a fixed order: the profile it extends, then
+//! each include in the order listed, then the profile's own fields. Later layers
+//! replace `apps`, `others`, `group_by_category`, `max_items`, `separator_after`,
+//! `separator`, and `login_items` when they set them, override individual `settings`,
+//! `desktop` settings, and `groups`, and append to `add` and every hook list.
+
+use crate::profile::{Profile, expand_home};
+use anyhow::{Context, Result, bail};
+use std::path::{Path, PathBuf};
+
+impl Profile {
+    /// Merges in the profiles this one extends and includes, recursively.
+    ///
+    /// Relative paths are resolved against the directory of the profile naming them.
+    /// Fails if a profile can't be loaded or a profile ends up including itself.
+    pub fn resolve(self) -> Result<Self> {
+        let mut stack = Vec::new();
+        if let Some(source) = &self.source {
+            stack.push(canonical(source));
+        }
+        self.resolve_with(&mut stack)
+    }
+
+    fn resolve_with(mut self, stack: &mut Vec<PathBuf>) -> Result<Self> {
+        let parents: Vec<String> = self
+            .extends
+            .take()
+            .into_iter()
+            .chain(std::mem::take(&mut self.include))
+            .collect();
+        if parents.is_empty() {
+            return Ok(self);
+        }
+
+        let dir = self
+            .source
+            .as_deref()
+            .and_then(Path::parent)
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        let mut merged: Option<Profile> = None;
+        for parent in parents {
+            let path = dir.join(expand_home(&parent));
+            let key = canonical(&path);
+            if let Some(start) = stack.iter().position(|seen| *seen == key) {
+                let cycle: Vec<String> = stack[start..]
+                    .iter()
+                    .chain([&key])
+                    .map(|path| path.display().to_string())
+                    .collect();
+                bail!("Profiles include each other: {}", cycle.join(" → "));
+            }
+
+            let profile = Profile::load_layer(&path)?;
+            stack.push(key);
+            let profile = profile
+                .resolve_with(stack)
+                .with_context(|| format!("Failed to resolve {}", path.display()))?;
+            stack.pop();
+            merged = Some(match merged {
+                Some(base) => base.overlay(profile),
+                None => profile,
+            });
+        }
+
+        let source = self.source.clone();
+        let mut profile = merged.unwrap_or_default().overlay(self);
+        profile.source = source;
+        Ok(profile)
+    }
+
+    /// Layers `top` over this profile.
+    fn overlay(self, top: Profile) -> Profile {
+        let mut profile = self;
+        if top.apps.is_some() {
+            profile.apps = top.apps;
+        }
+        if top.others.is_some() {
+            profile.others = top.others;
+        }
+        profile.add.extend(top.add);
+        profile.settings.extend(top.settings);
+        if top.group_by_category.is_some() {
+            profile.group_by_category = top.group_by_category;
+        }
+        if top.max_items.is_some() {
+            profile.max_items = top.max_items;
+        }
+        if !top.separator_after.is_empty() {
+            profile.separator_after = top.separator_after;
+        }
+        if top.separator.is_some() {
+            profile.separator = top.separator;
+        }
+        profile.groups.extend(top.groups);
+        if top.login_items.is_some() {
+            profile.login_items = top.login_items;
+        }
+        if let Some(top) = top.desktop {
+            profile.desktop = Some(match profile.desktop {
+                Some(base) => base.overlay(&top),
+                None => top,
+            });
+        }
+        profile.hooks.pre_apply.extend(top.hooks.pre_apply);
+        profile.hooks.post_apply.extend(top.hooks.post_apply);
+        profile.hooks.on_apply.extend(top.hooks.on_apply);
+        profile.hooks.on_drift.extend(top.hooks.on_drift);
+        profile
+    }
+}
+
+/// Compares paths by where they really are, so `./a.yaml` and `a.yaml` are one profile.
+fn canonical(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
@@ -0,0 +1,286 @@
+use crate::dock::{Dock, DockItem, DockItemKind};
+use crate::mac_app::MacApp;
+use crate::usage;
+use std::collections::BTreeMap;
+
+/// What [`Dock::sort_apps`] orders applications by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum SortKey {
+    /// The label, ignoring case.
+    #[default]
+    Name,
+
+    /// The app's category (`LSApplicationCategoryType`), then its label.
+    Category,
+
+    /// The path to the app bundle.
+    Path,
+}
+
+impl Dock {
+    /// Sorts the apps section.
+    ///
+    /// Items matching an entry in `pinned` move to the front, in the order given.
+    /// Spacers stay where they are and the apps between them are sorted as separate
+    /// groups. The sort is stable, so items that compare equal keep their order.
+    pub fn sort_apps(&mut self, key: SortKey, pinned: &[&str]) {
+        let Some(apps) = &mut self.applications else {
+            return;
+        };
+
+        let mut front = Vec::new();
+        for query in pinned {
+            if let Some(index) = apps.iter().position(|item| item.matches(query)) {
+                front.push(apps.remove(index));
+            }
+        }
+
+        for group in apps.split_mut(DockItem::is_spacer) {
+            match key {
+                SortKey::Name => group.sort_by_cached_key(|item| item.label().to_lowercase()),
+                SortKey::Path => group.sort_by_key(DockItem::path),
+                // Uncategorized apps go last.
+                SortKey::Category => group.sort_by_cached_key(|item| {
+                    let category = category(item);
+                    (category.is_none(), category, item.label().to_lowercase())
+                }),
+            }
+        }
+
+        front.append(apps);
+        *apps = front;
+    }
+}
+
+impl Dock {
+    /// Groups the apps section by category, separated by `spacer` tiles.
+    ///
+    /// Existing spacers are dropped first. Categories listed in `order` come first,
+    /// in that order, then any others alphabetically, then uncategorized apps. Apps keep
+    /// their relative order within a group. Categories may be given in full
+    /// (`public.app-category.developer-tools`) or without the prefix (`developer-tools`).
+    pub fn group_apps_by_category(&mut self, spacer: DockItemKind, order: &[String]) {
+        let Some(apps) = &mut self.applications else {
+            return;
+        };
+
+        let rank = |category: &Option<String>| match category {
+            Some(category) => {
+                let short = category.strip_prefix(CATEGORY_PREFIX).unwrap_or(category);
+                let position = order.iter().position(|wanted| {
+                    wanted.strip_prefix(CATEGORY_PREFIX).unwrap_or(wanted) == short
+                });
+                (position.unwrap_or(order.len()), Some(short.to_string()))
+            }
+            None => (order.len() + 1, None),
+        };
+
+        let mut keyed: Vec<_> = std::mem::take(apps)
+            .into_iter()
+            .filter(|item| !item.is_spacer())
+            .map(|item| (rank(&category(&item)), item))
+            .collect();
+        keyed.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let mut previous = None;
+        for (key, item) in keyed {
+            if previous.is_some_and(|previous| previous != key) {
+                apps.push(DockItem::spacer(spacer));
+            }
+            apps.push(item);
+            previous = Some(key);
+        }
+    }
+}
+
+/// Which apps [`Dock::trim_apps`] drops first when the Dock is over its limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TrimOrder {
+    /// Drop apps from the end of the Dock.
+    #[default]
+    Last,
+
+    /// Drop the apps opened longest ago, per Spotlight. Apps Spotlight has no date for,
+    /// usually because they aren't indexed, go only after every app with one.
+    LeastRecentlyUsed,
+}
+
+impl Dock {
+    /// Removes apps until at most `limit` remain, returning the removed items.
+    ///
+    /// Spacers don't count toward the limit and are never removed.
+    pub fn trim_apps(&mut self, limit: usize, order: TrimOrder) -> Vec<DockItem> {
+        let Some(apps) = &mut self.applications else {
+            return Vec::new();
+        };
+
+        let candidates: Vec<usize> = (0..apps.len()).filter(|&i| !apps[i].is_spacer()).collect();
+        let excess = candidates.len().saturating_sub(limit);
+        if excess == 0 {
+            return Vec::new();
+        }
+
+        let mut doomed = match order {
+            TrimOrder::Last => candidates[candidates.len() - excess..].to_vec(),
+            TrimOrder::LeastRecentlyUsed => {
+                let mut by_use: Vec<_> = candidates
+                    .into_iter()
+                    .map(|i| (apps[i].path().as_deref().and_then(usage::last_used), i))
+                    .collect();
+                // Oldest first, unknown dates last; among equals, the one further right
+                // goes first.
+                by_use.sort_by(|(a, i), (b, j)| {
+                    (a.is_none(), a).cmp(&(b.is_none(), b)).then(j.cmp(i))
+                });
+                by_use.into_iter().take(excess).map(|(_, i)| i).collect()
+            }
+        };
+        doomed.sort_unstable();
+
+        let mut removed: Vec<DockItem> = doomed.iter().rev().map(|&i| apps.remove(i)).collect();
+        removed.reverse();
+        for item in &removed {
+            tracing::warn!(
+                label = item.label(),
+                limit,
+                "dropped app to stay within the Dock size limit"
+            );
+        }
+        removed
+    }
+}
+
+/// Groups any profile can name in `separator_after`, unless it defines its own group of
+/// the same name. Members are bundle identifiers or `category:` and an App Store category.
+const BUILT_IN_GROUPS: &[(&str, &[&str])] = &[
+    (
+        "browsers",
+        &[
+            "com.apple.Safari",
+            "org.mozilla.firefox",
+            "com.google.Chrome",
+            "org.chromium.Chromium",
+            "com.microsoft.edgemac",
+            "com.brave.Browser",
+            "company.thebrowser.Browser",
+            "com.operasoftware.Opera",
+            "com.vivaldi.Vivaldi",
+        ],
+    ),
+    (
+        "communication",
+        &[
+            "com.apple.mail",
+            "com.apple.MobileSMS",
+            "com.apple.FaceTime",
+            "com.microsoft.Outlook",
+            "com.microsoft.teams2",
+            "com.tinyspeck.slackmacgap",
+            "us.zoom.xos",
+            "com.hnc.Discord",
+            "ru.keepcoder.Telegram",
+            "net.whatsapp.WhatsApp",
+            "category:social-networking",
+        ],
+    ),
+    (
+        "office",
+        &[
+            "com.apple.iWork.Pages",
+            "com.apple.iWork.Numbers",
+            "com.apple.iWork.Keynote",
+            "com.microsoft.Word",
+            "com.microsoft.Excel",
+            "com.microsoft.Powerpoint",
+            "category:productivity",
+        ],
+    ),
+    (
+        "development",
+        &[
+            "com.apple.dt.Xcode",
+            "com.apple.Terminal",
+            "com.microsoft.VSCode",
+            "category:developer-tools",
+        ],
+    ),
+    (
+        "media",
+        &[
+            "com.apple.Music",
+            "com.apple.TV",
+            "com.apple.Photos",
+            "com.spotify.client",
+            "category:music",
+            "category:entertainment",
+            "category:photography",
+            "category:video",
+        ],
+    ),
+];
+
+/// The names of the built-in app groups, for error messages.
+pub fn built_in_groups() -> impl Iterator<Item = &'static str> {
+    BUILT_IN_GROUPS.iter().map(|(name, _)| *name)
+}
+
+/// The members of the app group `name`: its definition in `custom`, such as a profile's
+/// `groups`, or else the built-in group of that name.
+pub fn app_group(name: &str, custom: &BTreeMap<String, Vec<String>>) -> Option<Vec<String>> {
+    if let Some(members) = custom.get(name) {
+        return Some(members.clone());
+    }
+    let (_, members) = BUILT_IN_GROUPS.iter().find(|(group, _)| *group == name)?;
+    Some(members.iter().map(|member| member.to_string()).collect())
+}
+
+/// Whether `item` is in a group with `members`: bundle identifiers, labels, or
+/// `category:` followed by a category, in full or without `public.app-category.`.
+fn in_group(item: &DockItem, members: &[String]) -> bool {
+    members
+        .iter()
+        .any(|member| match member.strip_prefix("category:") {
+            Some(wanted) => category(item).is_some_and(|category| {
+                category.strip_prefix(CATEGORY_PREFIX).unwrap_or(&category)
+                    == wanted.strip_prefix(CATEGORY_PREFIX).unwrap_or(wanted)
+            }),
+            None => item.matches(member),
+        })
+}
+
+impl Dock {
+    /// Puts a `spacer` tile after the last app of each group, given as its members (see
+    /// [`app_group`]), returning how many spacers were added.
+    ///
+    /// Nothing is added after the last app in the Dock or where a spacer already follows,
+    /// so running this again on its own result changes nothing.
+    pub fn insert_separators(&mut self, groups: &[Vec<String>], spacer: DockItemKind) -> usize {
+        let Some(apps) = &mut self.applications else {
+            return 0;
+        };
+        let mut added = 0;
+        for members in groups {
+            let Some(last) = apps
+                .iter()
+                .rposition(|item| !item.is_spacer() && in_group(item, members))
+            else {
+                continue;
+            };
+            if apps.get(last + 1).is_some_and(|next| !next.is_spacer()) {
+                apps.insert(last + 1, DockItem::spacer(spacer));
+                added += 1;
+            }
+        }
+        added
+    }
+}
+
+/// The prefix shared by every `LSApplicationCategoryType`.
+const CATEGORY_PREFIX: &str = "public.app-category.";
+
+/// Reads the category from the app bundle a tile points at, if it's still installed.
+pub(crate) fn category(item: &DockItem) -> Option<String> {
+    MacApp::from_path(item.path()?).ok()?.category
+}
@@ -0,0 +1,57 @@
+pub mod agent;
+pub mod audit;
+pub mod blame;
+pub mod build_info;
+pub mod cancel;
+pub mod clean;
+pub mod color;
+pub mod compliance;
+pub mod config;
+pub mod defaults;
+pub mod desktop;
+pub mod diff;
+pub mod dock;
+pub mod export;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod filter;
+pub mod hooks;
+#[cfg(feature = "render")]
+pub mod icons;
+pub mod inherit;
+pub mod layout;
+pub mod lint;
+pub mod listing;
+pub mod lock;
+pub mod login_items;
+pub mod mac_app;
+pub mod macos;
+pub mod merge;
+#[cfg(feature = "async")]
+pub mod nonblocking;
+#[cfg(feature = "notify")]
+pub mod notify;
+pub mod open;
+pub mod parse;
+pub mod paths;
+pub mod preview;
+pub mod profile;
+pub mod progress;
+#[cfg(feature = "network")]
+pub mod remote;
+#[cfg(feature = "render")]
+pub mod render;
+pub mod roundtrip;
+pub mod running;
+pub mod settings;
+pub mod snapshot;
+pub mod state;
+pub mod stats;
+pub mod status;
+#[cfg(feature = "network")]
+pub mod sync;
+pub mod transaction;
+pub mod usage;
+pub mod users;
+pub mod validate;
+pub mod visibility;
@@ -0,0 +1,196 @@
+//! Policy checks for profiles, for teams that review a profile before it ships.
+//!
+//! Unlike [validation](crate::validate), which asks whether a profile works on this Mac,
+//! linting asks whether it follows the team's conventions. Each [`Rule`] can be turned
+//! off or made a warning or an error under `[lint.rules]` in the config file.
+
+use crate::config::{Config, LintConfig, Severity};
+use crate::mac_app::MacApp;
+use crate::profile::{Entry, Profile};
+use crate::validate::{self, Problem};
+use anyhow::{Context, Result};
+use std::fmt;
+use std::path::Path;
+
+/// A convention `docktor profile lint` checks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    /// No paths inside one user's home folder; use `~/` so the profile fits everyone.
+    NoUserPaths,
+
+    /// Apps are named by bundle identifier, which survives apps moving or being renamed.
+    AppsByBundleId,
+
+    /// No more apps than the configured maximum.
+    MaxApps,
+}
+
+impl Rule {
+    /// Every rule, in the order findings are reported.
+    pub const ALL: [Rule; 3] = [Rule::NoUserPaths, Rule::AppsByBundleId, Rule::MaxApps];
+
+    /// The rule's name, as written under `[lint.rules]`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Rule::NoUserPaths => "no-user-paths",
+            Rule::AppsByBundleId => "apps-by-bundle-id",
+            Rule::MaxApps => "max-apps",
+        }
+    }
+
+    /// How seriously `config` takes the rule.
+    pub fn severity(self, config: &LintConfig) -> Severity {
+        match self {
+            Rule::NoUserPaths => config.rules.no_user_paths,
+            Rule::AppsByBundleId => config.rules.apps_by_bundle_id,
+            Rule::MaxApps => config.rules.max_apps,
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A place a profile breaks a rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    /// The rule broken.
+    pub rule: Rule,
+
+    /// How seriously the config takes it; never [`Severity::Off`].
+    pub severity: Severity,
+
+    /// What's wrong and where.
+    pub problem: Problem,
+}
+
+impl Finding {
+    /// Whether this finding fails the lint.
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            _ => "warning",
+        };
+        write!(f, "{severity}: {} ({})", self.problem, self.rule)
+    }
+}
+
+/// Lints the profile at `path` with the rules from the config file.
+pub fn lint_file<P: AsRef<Path>>(path: P) -> Result<Vec<Finding>> {
+    lint_file_with(path, &Config::current().lint)
+}
+
+/// Lints the profile at `path` with the rules from `config`, locating each finding in
+/// the file.
+///
+/// Only the file itself is linted, not the profiles it extends or includes, so each
+/// file is held to the rules on its own.
+pub fn lint_file_with<P: AsRef<Path>>(path: P, config: &LintConfig) -> Result<Vec<Finding>> {
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read profile at {}", path.display()))?;
+    let profile: Profile = serde_yaml::from_str(&source)
+        .with_context(|| format!("Failed to parse profile at {}", path.display()))?;
+    let mut findings = profile.lint(config);
+    for finding in &mut findings {
+        validate::locate(&mut finding.problem, &source);
+    }
+    Ok(findings)
+}
+
+impl Profile {
+    /// Checks the profile against the rules `config` turns on.
+    pub fn lint(&self, config: &LintConfig) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        for rule in Rule::ALL {
+            let severity = rule.severity(config);
+            if severity == Severity::Off {
+                continue;
+            }
+            let problems = match rule {
+                Rule::NoUserPaths => self.user_paths(),
+                Rule::AppsByBundleId => self.apps_not_by_bundle_id(),
+                Rule::MaxApps => self.too_many_apps(config.max_apps),
+            };
+            findings.extend(problems.into_iter().map(|problem| Finding {
+                rule,
+                severity,
+                problem,
+            }));
+        }
+        findings
+    }
+
+    fn user_paths(&self) -> Vec<Problem> {
+        let entries = [&self.apps, &self.others, &self.login_items];
+        entries
+            .into_iter()
+            .flatten()
+            .flatten()
+            .flat_map(Entry::candidates)
+            .chain(self.add.iter().map(|addition| &addition.path))
+            .filter(|candidate| is_user_path(candidate))
+            .map(|candidate| {
+                Problem::new(format!(
+                    "\"{candidate}\" is inside one user's home folder; write it from ~/ instead"
+                ))
+            })
+            .collect()
+    }
+
+    fn apps_not_by_bundle_id(&self) -> Vec<Problem> {
+        [&self.apps, &self.login_items]
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|entry| entry.spacer_kind().is_none())
+            .flat_map(Entry::candidates)
+            .filter(|candidate| !is_bundle_id(candidate))
+            .map(|candidate| {
+                let suggestion = MacApp::resolve(candidate)
+                    .map(|app| format!("; use {}", app.bundle_id))
+                    .unwrap_or_default();
+                Problem::new(format!(
+                    "App \"{candidate}\" isn't named by bundle identifier{suggestion}"
+                ))
+            })
+            .collect()
+    }
+
+    fn too_many_apps(&self, max_apps: usize) -> Vec<Problem> {
+        let count = self
+            .apps
+            .iter()
+            .flatten()
+            .filter(|entry| entry.spacer_kind().is_none())
+            .count();
+        if count <= max_apps {
+            return Vec::new();
+        }
+        vec![Problem::new(format!(
+            "The profile pins {count} apps, more than the {max_apps} allowed"
+        ))]
+    }
+}
+
+/// Whether `path` is inside a particular user's home folder, other than `/Users/Shared`.
+fn is_user_path(path: &str) -> bool {
+    path.strip_prefix("/Users/")
+        .and_then(|rest| rest.split('/').next())
+        .is_some_and(|user| !user.is_empty() && user != "Shared")
+}
+
+/// Whether `entry` reads as a bundle identifier the way [`MacApp::resolve`] treats it:
+/// not a path, but with a dot.
+fn is_bundle_id(entry: &str) -> bool {
+    !entry.contains('/') && !entry.ends_with(".app") && entry.contains('.')
+}
@@ -0,0 +1,173 @@
+use crate::color::{ColorChoice, Style, paint};
+use crate::diff::describe_value;
+use crate::dock::{Dock, DockItem, DockSections, Section};
+use crate::filter::Matcher;
+use crate::mac_app::MacApp;
+use crate::profile::Profile;
+use crate::usage;
+use std::fmt::Write;
+use time::OffsetDateTime;
+
+/// What `docktor list` includes.
+#[derive(Debug, Default)]
+pub struct ListOptions {
+    /// Only list items at least one of these match; everything when empty.
+    pub matchers: Vec<Matcher>,
+
+    /// Running apps, to mark which pinned apps are open and list unpinned ones.
+    pub running: Option<Vec<MacApp>>,
+
+    /// Show when each pinned item was last opened.
+    pub last_used: bool,
+}
+
+impl ListOptions {
+    fn includes(&self, item: &DockItem) -> bool {
+        self.matchers.is_empty() || self.matchers.iter().any(|m| m.matches(item))
+    }
+}
+
+/// Renders the Dock's items by section, one per line.
+pub fn render(dock: &DockSections, options: &ListOptions, color: ColorChoice) -> String {
+    let color = color.enabled();
+    let is_running = |item: &DockItem| {
+        options
+            .running
+            .iter()
+            .flatten()
+            .any(|app| item.metadata.bundle_id.as_deref() == Some(app.bundle_id.as_str()))
+    };
+
+    let mut out = String::new();
+    for section in Section::ALL {
+        let items: Vec<_> = dock
+            .section(section)
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| options.includes(item))
+            .collect();
+        if items.is_empty() {
+            continue;
+        }
+
+        heading(&mut out, section.name(), color);
+        for (index, item) in items {
+            line(&mut out, &format!("  {index:>2}"), item, color);
+            if is_running(item) {
+                let _ = write!(out, "  {}", paint("running", Style::Green, color));
+            }
+            if options.last_used && !item.is_spacer() {
+                let used = match item.last_used() {
+                    Some(when) => format!(
+                        "last used {}",
+                        usage::describe_age(when, OffsetDateTime::now_utc())
+                    ),
+                    None => "last used unknown".to_string(),
+                };
+                let _ = write!(out, "  {}", paint(&used, Style::Yellow, color));
+            }
+            out.push('\n');
+        }
+    }
+
+    if let Some(running) = &options.running {
+        let transient: Vec<_> = running
+            .iter()
+            .filter(|app| !crate::running::BUILT_IN_APPS.contains(&app.bundle_id.as_str()))
+            .map(DockItem::new)
+            .filter(|item| options.includes(item))
+            .filter(|item| {
+                !dock.section(Section::Apps).iter().any(|pinned| {
+                    pinned.metadata.bundle_id.is_some()
+                        && pinned.metadata.bundle_id == item.metadata.bundle_id
+                })
+            })
+            .collect();
+        if !transient.is_empty() {
+            heading(&mut out, "Running (not pinned)", color);
+            for item in &transient {
+                line(&mut out, "   ·", item, color);
+                out.push('\n');
+            }
+        }
+    }
+    out
+}
+
+/// Renders the Dock as a tree of sections, items, and settings, for `docktor show`.
+///
+/// Items `profile` describes are flagged as managed.
+pub fn render_tree(
+    dock: &Dock,
+    title: &str,
+    profile: Option<&Profile>,
+    color: ColorChoice,
+) -> String {
+    let color = color.enabled();
+    let mut out = String::new();
+    let _ = writeln!(out, "{}", paint(title, Style::Bold, color));
+
+    for section in Section::ALL {
+        let items = dock.section(section);
+        let _ = writeln!(
+            out,
+            "├── {}",
+            paint(
+                &format!("{} ({})", section.name(), items.len()),
+                Style::Bold,
+                color
+            )
+        );
+        for (index, item) in items.iter().enumerate() {
+            let twig = if index + 1 == items.len() {
+                "└──"
+            } else {
+                "├──"
+            };
+            line(&mut out, &format!("│   {twig} {index}"), item, color);
+            if profile.is_some_and(|profile| profile.manages(item)) {
+                let _ = write!(out, "  {}", paint("[managed]", Style::Blue, color));
+            }
+            out.push('\n');
+        }
+    }
+
+    let settings: Vec<_> = dock.settings().collect();
+    let _ = writeln!(
+        out,
+        "└── {}",
+        paint(
+            &format!("Settings ({})", settings.len()),
+            Style::Bold,
+            color
+        )
+    );
+    for (index, (key, value)) in settings.iter().enumerate() {
+        let twig = if index + 1 == settings.len() {
+            "└──"
+        } else {
+            "├──"
+        };
+        let _ = writeln!(out, "    {twig} {key} = {}", describe_value(Some(value)));
+    }
+    out
+}
+
+fn heading(out: &mut String, name: &str, color: bool) {
+    let _ = writeln!(out, "{}", paint(&format!("{name}:"), Style::Bold, color));
+}
+
+/// Writes an item's marker, label, and bundle identifier or path, without a newline.
+fn line(out: &mut String, marker: &str, item: &DockItem, color: bool) {
+    let detail = item
+        .metadata
+        .bundle_id
+        .clone()
+        .or_else(|| item.path().map(|path| path.display().to_string()))
+        .or_else(|| item.url().map(String::from))
+        .unwrap_or_default();
+    let _ = write!(out, "{marker}  {}", item.label());
+    if !detail.is_empty() {
+        let _ = write!(out, "  {}", paint(&detail, Style::Dim, color));
+    }
+}
@@ -0,0 +1,130 @@
+//! Freezing the Dock so it can't be rearranged, resized, or moved by dragging.
+//!
+//! A lock is a `*-immutable` Dock preference. Setting it in the user's own
+//! preferences is enough for a family Mac; labs and fleets push the same keys from a
+//! configuration profile instead, which [`payload`] generates.
+
+use crate::dock::Dock;
+use anyhow::{Context, Result};
+
+/// The identifier the configuration profiles [`payload`] generates are installed under.
+const PROFILE_IDENTIFIER: &str = "com.github.targendaz2.docktor.lock";
+
+/// Something about the Dock that can be locked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Lock {
+    /// Which items are in the Dock and their order.
+    Contents,
+
+    /// The icon size.
+    Size,
+
+    /// Which edge of the screen the Dock is on.
+    Position,
+
+    /// Whether icons magnify on hover.
+    Magnification,
+}
+
+impl Lock {
+    /// Every lock, in the order they are listed.
+    pub const ALL: [Lock; 4] = [
+        Lock::Contents,
+        Lock::Size,
+        Lock::Position,
+        Lock::Magnification,
+    ];
+
+    /// The Dock preference that holds this lock.
+    pub fn key(self) -> &'static str {
+        match self {
+            Lock::Contents => "contents-immutable",
+            Lock::Size => "size-immutable",
+            Lock::Position => "position-immutable",
+            Lock::Magnification => "magnify-immutable",
+        }
+    }
+}
+
+impl Dock {
+    /// Whether `lock` is set in this Dock's preferences.
+    pub fn is_locked(&self, lock: Lock) -> bool {
+        self.extra
+            .get(lock.key())
+            .and_then(plist::Value::as_boolean)
+            .unwrap_or(false)
+    }
+
+    /// Sets or clears `lock`. Clearing removes the preference, since unlocked is the default.
+    pub fn set_locked(&mut self, lock: Lock, locked: bool) {
+        if locked {
+            self.extra.insert(lock.key().to_string(), true.into());
+        } else {
+            self.extra.remove(lock.key());
+        }
+    }
+}
+
+/// A `.mobileconfig` configuration profile that sets `locks` to `locked` for every
+/// user of the Macs it is installed on, as XML.
+///
+/// Its UUIDs are derived from its contents, so regenerating the same profile gives
+/// an identical file that MDM servers treat as the same profile.
+pub fn payload(locks: &[Lock], locked: bool) -> Result<String> {
+    let mut settings = plist::Dictionary::new();
+    for lock in locks {
+        settings.insert(lock.key().to_string(), locked.into());
+    }
+    let seed = format!("{locked}:{}", serde_json::to_string(&settings)?);
+
+    let mut dock = settings;
+    dock.insert("PayloadType".into(), "com.apple.dock".into());
+    dock.insert(
+        "PayloadIdentifier".into(),
+        format!("{PROFILE_IDENTIFIER}.dock").into(),
+    );
+    dock.insert("PayloadUUID".into(), uuid(&format!("dock:{seed}")).into());
+    dock.insert("PayloadVersion".into(), 1.into());
+    dock.insert("PayloadDisplayName".into(), "Dock".into());
+
+    let mut profile = plist::Dictionary::new();
+    profile.insert(
+        "PayloadContent".into(),
+        vec![plist::Value::Dictionary(dock)].into(),
+    );
+    profile.insert(
+        "PayloadDisplayName".into(),
+        if locked { "Dock lock" } else { "Dock unlock" }.into(),
+    );
+    profile.insert(
+        "PayloadDescription".into(),
+        "Generated by docktor lock.".into(),
+    );
+    profile.insert("PayloadIdentifier".into(), PROFILE_IDENTIFIER.into());
+    profile.insert("PayloadType".into(), "Configuration".into());
+    profile.insert(
+        "PayloadUUID".into(),
+        uuid(&format!("profile:{seed}")).into(),
+    );
+    profile.insert("PayloadVersion".into(), 1.into());
+
+    let mut xml = Vec::new();
+    plist::to_writer_xml(&mut xml, &plist::Value::Dictionary(profile))
+        .context("Failed to write the configuration profile")?;
+    Ok(String::from_utf8(xml)?)
+}
+
+/// A UUID-shaped identifier derived from `seed`.
+fn uuid(seed: &str) -> String {
+    let high = crate::state::fnv1a(seed.as_bytes());
+    let low = crate::state::fnv1a(format!("{seed}\0").as_bytes());
+    format!(
+        "{:08X}-{:04X}-4{:03X}-{:04X}-{:012X}",
+        high >> 32,
+        (high >> 16) & 0xffff,
+        high & 0xfff,
+        (low >> 48) & 0x3fff | 0x8000,
+        low & 0xffff_ffff_ffff
+    )
+}
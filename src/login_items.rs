@@ -0,0 +1,118 @@
+//! Apps that open when the user logs in, managed alongside the Dock from a profile.
+//!
+//! `SMAppService` only registers an app's own helpers, so other apps are added through
+//! System Events' login items, as Login Items in System Settings does. The first use
+//! asks for permission to control System Events.
+
+use crate::mac_app::MacApp;
+use crate::macos::{self, applescript_string};
+use crate::profile::Profile;
+use anyhow::{Context, Result};
+use std::fmt;
+use std::path::PathBuf;
+
+/// An app that opens at login.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoginItem {
+    /// The name shown in System Settings.
+    pub name: String,
+
+    /// The app bundle that's opened.
+    pub path: PathBuf,
+
+    /// Whether the app is hidden once it opens.
+    pub hidden: bool,
+}
+
+impl fmt::Display for LoginItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.name, self.path.display())
+    }
+}
+
+/// The current user's login items.
+#[tracing::instrument]
+pub fn list() -> Result<Vec<LoginItem>> {
+    let output = run(r#"set out to ""
+tell application "System Events"
+    repeat with item_ in login items
+        set out to out & (name of item_) & tab & (path of item_) & tab & (hidden of item_) & linefeed
+    end repeat
+end tell
+return out"#)?;
+    Ok(output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            Some(LoginItem {
+                name: fields.next()?.to_string(),
+                path: PathBuf::from(fields.next()?),
+                hidden: fields.next()? == "true",
+            })
+        })
+        .collect())
+}
+
+/// Makes `app` open at login.
+#[tracing::instrument(skip_all, fields(app = %app.display_name))]
+pub fn add(app: &MacApp) -> Result<()> {
+    run(&format!(
+        "tell application \"System Events\" to make login item at end with properties {{path:{}, hidden:false}}",
+        applescript_string(&app.path.to_string_lossy())
+    ))?;
+    Ok(())
+}
+
+/// Stops the login item named `name` from opening at login.
+#[tracing::instrument]
+pub fn remove(name: &str) -> Result<()> {
+    run(&format!(
+        "tell application \"System Events\" to delete login item {}",
+        applescript_string(name)
+    ))?;
+    Ok(())
+}
+
+/// Runs an AppleScript against System Events, returning what it printed.
+fn run(script: &str) -> Result<String> {
+    macos::osascript(script, "Managing login items")
+}
+
+impl Profile {
+    /// Adds the apps in the profile's `login_items` that don't open at login yet,
+    /// returning them. With `dry_run`, only reports what would be added.
+    ///
+    /// Login items the profile doesn't list are left alone, since other installers and
+    /// the user add them too.
+    pub fn apply_login_items(&self, dry_run: bool) -> Result<Vec<MacApp>> {
+        let Some(entries) = &self.login_items else {
+            return Ok(Vec::new());
+        };
+        let current = list()?;
+        let mut added = Vec::new();
+        for entry in entries {
+            let Some(app) = entry
+                .resolve_app()
+                .with_context(|| format!("Login item \"{entry}\" can't be found"))?
+            else {
+                continue;
+            };
+            let present = current.iter().any(|item| {
+                crate::paths::canonical(&item.path) == app.path
+                    || MacApp::from_path(&item.path).is_ok_and(|it| it.bundle_id == app.bundle_id)
+            });
+            if present
+                || added
+                    .iter()
+                    .any(|it: &MacApp| it.bundle_id == app.bundle_id)
+            {
+                continue;
+            }
+            if !dry_run {
+                add(&app)?;
+            }
+            added.push(app);
+        }
+        Ok(added)
+    }
+}
@@ -1,7 +1,12 @@
+use crate::cancel::CancellationToken;
+use crate::config::Config;
+use crate::progress::{Progress, Step};
 use anyhow::{Context, Result, bail};
 use serde::Deserialize;
+use std::fmt;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::process::Command;
 
 /// Represents a macOS application bundle and its metadata.
 #[derive(Debug)]
@@ -14,6 +19,15 @@ pub struct MacApp {
 
     /// Bundle identifier (CFBundleIdentifier).
     pub bundle_id: String,
+
+    /// App Store category (LSApplicationCategoryType), e.g. `public.app-category.developer-tools`.
+    pub category: Option<String>,
+
+    /// Version shown to users (CFBundleShortVersionString), e.g. `17.4`.
+    pub version: Option<String>,
+
+    /// The `.icns` file in the bundle's resources named by CFBundleIconFile, if it exists.
+    pub icon: Option<PathBuf>,
 }
 
 impl MacApp {
@@ -22,7 +36,7 @@ impl MacApp {
         let path = path.as_ref();
 
         // Basic validation
-        if !path.exists() || path.extension().map_or(true, |ext| ext != "app") {
+        if !path.exists() || path.extension().is_none_or(|ext| ext != "app") {
             bail!("Invalid macOS application bundle path: {}", path.display());
         }
 
@@ -51,14 +65,278 @@ impl MacApp {
             )
         })?;
 
+        // CFBundleIconFile may leave off the extension.
+        let icon = info_plist
+            .icon_file
+            .map(|name| {
+                let file = path.join("Contents/Resources").join(&name);
+                if file.extension().is_none() {
+                    file.with_extension("icns")
+                } else {
+                    file
+                }
+            })
+            .filter(|file| file.is_file());
+
         Ok(MacApp {
-            path: path.to_path_buf(),
+            path: crate::paths::canonical(path),
             display_name,
             bundle_id,
+            category: info_plist.category,
+            version: info_plist.version,
+            icon,
         })
     }
+
+    /// Finds an installed application by bundle identifier.
+    ///
+    /// Asks Spotlight first, on macOS, then looks through the standard application folders.
+    /// If there are several copies, the one [`install_rank`] prefers wins.
+    pub fn find(bundle_id: &str) -> Result<Self> {
+        let spotlight = cfg!(target_os = "macos")
+            .then(|| {
+                Command::new("mdfind")
+                    .arg(format!("kMDItemCFBundleIdentifier == '{bundle_id}'"))
+                    .output()
+                    .ok()
+            })
+            .flatten()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+            .unwrap_or_default();
+        let found = spotlight
+            .lines()
+            .map(PathBuf::from)
+            .filter_map(|path| Self::from_path(path).ok())
+            .filter(|app| app.bundle_id == bundle_id)
+            .min_by_key(|app| install_rank(&app.path));
+        if let Some(app) = found {
+            return Ok(app);
+        }
+
+        app_dirs()
+            .iter()
+            .filter_map(|dir| fs::read_dir(dir).ok())
+            .flatten()
+            .filter_map(|entry| Self::from_path(entry.ok()?.path()).ok())
+            .filter(|app| app.bundle_id == bundle_id)
+            .min_by_key(|app| install_rank(&app.path))
+            .with_context(|| format!("No installed application has bundle identifier {bundle_id}"))
+    }
+
+    /// Finds an installed application by its bundle name, e.g. `Firefox` for `Firefox.app`.
+    ///
+    /// If there are several copies, the one [`install_rank`] prefers wins.
+    pub fn find_by_name(name: &str) -> Result<Self> {
+        let bundle = format!("{name}.app");
+        app_dirs()
+            .iter()
+            .map(|dir| dir.join(&bundle))
+            .filter(|path| path.exists())
+            .min_by_key(|path| install_rank(path))
+            .with_context(|| format!("No application named {name} is installed"))
+            .and_then(Self::from_path)
+    }
+
+    /// Resolves a path to an `.app` bundle, a bundle identifier, or an app name.
+    ///
+    /// Anything containing `/` or ending in `.app` is a path, anything else with a
+    /// dot is a bundle identifier, and the rest are names.
+    pub fn resolve(spec: &str) -> Result<Self> {
+        if spec.contains('/') || spec.ends_with(".app") {
+            Self::from_path(crate::profile::expand_home(spec))
+        } else if spec.contains('.') {
+            Self::find(spec)
+        } else {
+            Self::find_by_name(spec)
+        }
+    }
+
+    /// Lists every application in the standard application folders, sorted by name.
+    ///
+    /// Bundles that can't be read are skipped.
+    pub fn discover_all() -> Vec<Self> {
+        Self::discover_all_with(&(), &CancellationToken::new()).unwrap_or_default()
+    }
+
+    /// Like [`MacApp::discover_all`], reporting each bundle read to `progress`.
+    ///
+    /// Stops with [`Cancelled`](crate::cancel::Cancelled) once `cancel` is cancelled.
+    pub fn discover_all_with(
+        progress: &dyn Progress,
+        cancel: &CancellationToken,
+    ) -> Result<Vec<Self>> {
+        let bundles: Vec<PathBuf> = app_dirs()
+            .iter()
+            .filter_map(|dir| fs::read_dir(dir).ok())
+            .flatten()
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "app"))
+            .collect();
+
+        let total = bundles.len();
+        let mut apps = Vec::new();
+        for (done, path) in bundles.iter().enumerate() {
+            cancel.check()?;
+            apps.extend(Self::from_path(path).ok());
+            progress.step(Step::Scanned {
+                done: done + 1,
+                total,
+                path,
+            });
+        }
+        apps.sort_by(|a, b| a.display_name.cmp(&b.display_name));
+        Ok(apps)
+    }
 }
 
+/// Why a tile pointing at an app might stop working.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstallRisk {
+    /// The app is inside a mounted disk image, so the tile breaks once it's ejected.
+    DiskImage(PathBuf),
+
+    /// The app was translocated by Gatekeeper and is running from a temporary copy.
+    Translocated,
+
+    /// The app still carries the quarantine attribute and hasn't been opened yet.
+    Quarantined,
+}
+
+impl fmt::Display for InstallRisk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InstallRisk::DiskImage(mount_point) => write!(
+                f,
+                "it is inside the disk image mounted at {}; copy it to /Applications first",
+                mount_point.display()
+            ),
+            InstallRisk::Translocated => write!(
+                f,
+                "it is a temporary translocated copy; move it to /Applications and open it from there"
+            ),
+            InstallRisk::Quarantined => write!(
+                f,
+                "it is still quarantined; open it once so macOS can verify it"
+            ),
+        }
+    }
+}
+
+impl MacApp {
+    /// Checks whether pinning this app is likely to leave a broken tile.
+    pub fn install_risks(&self) -> Vec<InstallRisk> {
+        let mut risks = Vec::new();
+        if let Some(mount_point) = disk_image_mount(&self.path) {
+            risks.push(InstallRisk::DiskImage(mount_point));
+        }
+        if self
+            .path
+            .components()
+            .any(|component| component.as_os_str() == "AppTranslocation")
+        {
+            risks.push(InstallRisk::Translocated);
+        }
+        let quarantined = cfg!(target_os = "macos")
+            && Command::new("xattr")
+                .args(["-p", "com.apple.quarantine"])
+                .arg(&self.path)
+                .output()
+                .is_ok_and(|output| output.status.success());
+        if quarantined {
+            risks.push(InstallRisk::Quarantined);
+        }
+        risks
+    }
+}
+
+/// The mount point of the disk image containing `path`, according to `hdiutil info`.
+fn disk_image_mount(path: &Path) -> Option<PathBuf> {
+    if !cfg!(target_os = "macos") || !path.starts_with("/Volumes") {
+        return None;
+    }
+    let output = Command::new("hdiutil")
+        .args(["info", "-plist"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    let info: HdiutilInfo = plist::from_bytes(&output.stdout).ok()?;
+    info.images
+        .into_iter()
+        .flat_map(|image| image.entities)
+        .filter_map(|entity| entity.mount_point)
+        .find(|mount_point| path.starts_with(mount_point))
+}
+
+/// The parts of `hdiutil info -plist` needed to find disk image mount points.
+#[derive(Debug, Deserialize)]
+struct HdiutilInfo {
+    #[serde(default)]
+    images: Vec<HdiutilImage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HdiutilImage {
+    #[serde(rename = "system-entities", default)]
+    entities: Vec<HdiutilEntity>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HdiutilEntity {
+    #[serde(rename = "mount-point")]
+    mount_point: Option<PathBuf>,
+}
+
+/// The folders applications are installed in, including `~/Applications` and any
+/// others named in [`install_preference`].
+fn app_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = APP_DIRS.iter().map(PathBuf::from).collect();
+    dirs.extend(crate::paths::home_dir().map(|home| home.join("Applications")));
+    for dir in install_preference() {
+        if !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
+    }
+    dirs
+}
+
+/// The folders an app is preferably taken from when it's installed in several, most
+/// preferred first: the configured
+/// [`install_preference`](crate::config::Config::install_preference), or else
+/// `/Applications`, `/System/Applications`, and `~/Applications`.
+pub fn install_preference() -> Vec<PathBuf> {
+    match &Config::current().install_preference {
+        Some(dirs) => dirs
+            .iter()
+            .map(|dir| crate::profile::expand_home(&dir.to_string_lossy()))
+            .collect(),
+        None => ["/Applications", "/System/Applications"]
+            .into_iter()
+            .map(PathBuf::from)
+            .chain(crate::paths::home_dir().map(|home| home.join("Applications")))
+            .collect(),
+    }
+}
+
+/// How preferred `path` is as the place an app is installed, lowest first, by its
+/// folder's position in [`install_preference`]; anywhere else comes last. Subfolders
+/// such as `Utilities` rank with the folder they're in.
+pub fn install_rank(path: &Path) -> usize {
+    let preferred = install_preference();
+    preferred
+        .iter()
+        .position(|dir| path.starts_with(dir))
+        .unwrap_or(preferred.len())
+}
+
+/// Folders searched for applications when Spotlight can't find one.
+const APP_DIRS: &[&str] = &[
+    "/Applications",
+    "/Applications/Utilities",
+    "/System/Applications",
+    "/System/Applications/Utilities",
+];
+
 /// Represents only the relevant fields from an Info.plist file.
 #[allow(dead_code)]
 #[derive(Debug, Deserialize)]
@@ -71,4 +349,13 @@ struct InfoPlist {
 
     #[serde(rename = "CFBundleName")]
     pub name: Option<String>,
+
+    #[serde(rename = "LSApplicationCategoryType")]
+    pub category: Option<String>,
+
+    #[serde(rename = "CFBundleShortVersionString")]
+    pub version: Option<String>,
+
+    #[serde(rename = "CFBundleIconFile")]
+    pub icon_file: Option<String>,
 }
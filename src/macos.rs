@@ -0,0 +1,182 @@
+//! Which macOS docktor is running on, and which Dock features that version has.
+
+use anyhow::{Context, Result, bail};
+use std::fmt;
+use std::process::Command;
+use std::sync::OnceLock;
+
+/// A macOS version number, e.g. `14.4.1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    /// The major version, e.g. `14` for Sonoma or `10` for everything before Big Sur.
+    pub major: u32,
+
+    /// The minor version.
+    pub minor: u32,
+
+    /// The patch version; `0` when `sw_vers` leaves it out.
+    pub patch: u32,
+}
+
+impl Version {
+    /// Creates a version from its parts.
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Version {
+            major,
+            minor,
+            patch,
+        }
+    }
+}
+
+impl std::str::FromStr for Version {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parts = s.trim().split('.').map(|part| {
+            part.parse::<u32>()
+                .with_context(|| format!("Invalid macOS version {s:?}"))
+        });
+        let Some(major) = parts.next() else {
+            bail!("Invalid macOS version {s:?}");
+        };
+        let version = Version::new(
+            major?,
+            parts.next().transpose()?.unwrap_or(0),
+            parts.next().transpose()?.unwrap_or(0),
+        );
+        if parts.next().is_some() {
+            bail!("Invalid macOS version {s:?}");
+        }
+        Ok(version)
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)?;
+        if self.patch > 0 {
+            write!(f, ".{}", self.patch)?;
+        }
+        Ok(())
+    }
+}
+
+/// The oldest macOS docktor supports.
+pub const OLDEST_SUPPORTED: Version = Version::new(10, 13, 0);
+
+/// The newest macOS docktor has been tested on; newer releases usually work too.
+pub const NEWEST_TESTED: Version = Version::new(26, 0, 0);
+
+/// Fails unless docktor is running on macOS, for things that drive macOS itself.
+///
+/// Reading, diffing, and writing plists and profiles work on any platform, so they can
+/// be built and tested on Linux against fixture plists; `what` names what needs macOS.
+pub(crate) fn ensure(what: &str) -> Result<()> {
+    if !cfg!(target_os = "macos") {
+        bail!("{what} only works on macOS");
+    }
+    Ok(())
+}
+
+/// The version of macOS this process is running on, from `sw_vers`.
+///
+/// `None` when it can't be determined, such as when not running on macOS.
+pub fn version() -> Option<Version> {
+    static VERSION: OnceLock<Option<Version>> = OnceLock::new();
+    *VERSION.get_or_init(|| {
+        if !cfg!(target_os = "macos") {
+            return None;
+        }
+        let output = Command::new("sw_vers")
+            .arg("-productVersion")
+            .output()
+            .ok()
+            .filter(|output| output.status.success())?;
+        String::from_utf8_lossy(&output.stdout).parse().ok()
+    })
+}
+
+/// A Dock feature that only some macOS versions have.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feature {
+    /// The "Show recent applications in Dock" section (`show-recents`).
+    ShowRecents,
+
+    /// Web apps saved from Safari with File › Add to Dock.
+    SafariWebApps,
+}
+
+impl Feature {
+    /// The first macOS version with this feature.
+    pub fn since(self) -> Version {
+        match self {
+            Feature::ShowRecents => Version::new(10, 14, 0),
+            Feature::SafariWebApps => Version::new(14, 0, 0),
+        }
+    }
+
+    /// A short description for messages.
+    pub fn name(self) -> &'static str {
+        match self {
+            Feature::ShowRecents => "the recent applications section",
+            Feature::SafariWebApps => "Safari web apps",
+        }
+    }
+
+    /// The feature a Dock setting belongs to, if it isn't available everywhere.
+    pub fn for_setting(key: &str) -> Option<Feature> {
+        match key {
+            "show-recents" => Some(Feature::ShowRecents),
+            _ => None,
+        }
+    }
+
+    /// The feature an app needs to work in the Dock, if it isn't available everywhere.
+    pub fn for_bundle_id(bundle_id: &str) -> Option<Feature> {
+        bundle_id
+            .starts_with("com.apple.Safari.WebApp.")
+            .then_some(Feature::SafariWebApps)
+    }
+
+    /// Whether this Mac's macOS has the feature; assumed so if the version is unknown.
+    pub fn is_supported(self) -> bool {
+        version().is_none_or(|version| version >= self.since())
+    }
+}
+
+/// The short name of this machine, for commit messages and hook payloads.
+pub(crate) fn hostname() -> String {
+    Command::new("hostname")
+        .arg("-s")
+        .output()
+        .ok()
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "unknown host".to_string())
+}
+
+/// Runs an AppleScript through `osascript`, returning what it printed.
+///
+/// `what` names what the script does, e.g. `Managing login items`, for the errors when
+/// not on macOS or when the script fails.
+pub(crate) fn osascript(script: &str, what: &str) -> Result<String> {
+    ensure(what)?;
+    let output = Command::new("osascript")
+        .arg("-e")
+        .arg(script)
+        .output()
+        .context("Failed to run osascript")?;
+    if !output.status.success() {
+        bail!(
+            "{what} failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Quotes `s` as an AppleScript string literal.
+pub(crate) fn applescript_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
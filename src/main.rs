@@ -1,6 +1,2922 @@
-mod dock;
-mod mac_app;
+use anyhow::{Context, Result, bail};
+use clap::{ArgAction, CommandFactory, Parser, Subcommand, ValueEnum};
+use docktor::agent::{self, TimeOfDay};
+use docktor::blame;
+use docktor::build_info::BuildInfo;
+use docktor::cancel::CancellationToken;
+use docktor::clean::CleanOptions;
+use docktor::color::ColorChoice;
+use docktor::compliance::{Compliance, ComplianceFormat};
+use docktor::config::{BackupPolicy, Config};
+use docktor::diff::DockDiff;
+use docktor::dock::{
+    AddPolicy, Arrangement, Dock, DockItem, DockItemKind, FolderDisplay, FolderOptions, FolderView,
+    ItemRef, Position, Section,
+};
+use docktor::export::{self, ExportFormat, ScriptFlavor};
+use docktor::filter::Matcher;
+use docktor::hooks::Hooks;
+use docktor::layout::SortKey;
+use docktor::lint;
+use docktor::listing::{self, ListOptions};
+use docktor::lock::{self, Lock};
+use docktor::mac_app::MacApp;
+use docktor::macos::Feature;
+use docktor::merge::Strategy;
+use docktor::notify;
+use docktor::parse::ParseMode;
+use docktor::preview::{self, PreviewStyle};
+use docktor::profile::{ApplyOptions, CategoryGrouping, Entry, Profile, UnmountedPolicy};
+use docktor::progress::Step;
+use docktor::remote;
+#[cfg(feature = "render")]
+use docktor::render::{self, RenderFormat};
+use docktor::roundtrip;
+use docktor::running;
+use docktor::settings::{DockSettings, Setting, Switch};
+use docktor::snapshot::Snapshot;
+use docktor::state;
+use docktor::stats::Stats;
+use docktor::status::Status;
+use docktor::sync::{PushOutcome, SyncRepo};
+use docktor::transaction::{self, SideEffects};
+use docktor::usage;
+use docktor::users;
+use docktor::validate;
+use docktor::visibility;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use time::OffsetDateTime;
+use tracing_subscriber::EnvFilter;
 
-fn main() {
-    println!("Hello, world!");
+/// Manages the macOS Dock's contents.
+#[derive(Debug, Parser)]
+#[command(version, about, after_help = AFTER_HELP)]
+struct Cli {
+    /// When to color output.
+    #[arg(long, value_enum, global = true, default_value_t)]
+    color: ColorChoice,
+
+    /// Log more detail to stderr (-v info, -vv debug, -vvv trace).
+    #[arg(short, long, global = true, action = ArgAction::Count, conflicts_with = "quiet")]
+    verbose: u8,
+
+    /// Only log errors.
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
+    /// The format of log lines written to stderr.
+    #[arg(long, value_enum, global = true, default_value_t)]
+    log_format: LogFormat,
+
+    /// Take apps installed in several places from these folders, in this order,
+    /// instead of the configured install preference; repeat for more than one.
+    #[arg(long, global = true, value_name = "DIR")]
+    prefer: Vec<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+enum LogFormat {
+    /// Human-readable lines.
+    #[default]
+    Text,
+
+    /// One JSON object per line, for log collectors.
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+enum DiffFormat {
+    /// One line per change, for people.
+    #[default]
+    Text,
+
+    /// An RFC 6902 JSON Patch against the Dock plist.
+    JsonPatch,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Make the live Dock match a profile.
+    ///
+    /// A snapshot of the current Dock is saved before any change is written.
+    Apply {
+        /// The YAML profile to apply.
+        profile: PathBuf,
+
+        /// Show what would change without writing anything.
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Save the changes without restarting the Dock.
+        #[arg(long)]
+        no_restart: bool,
+
+        /// Post a notification summarizing the changes, if there were any.
+        #[arg(long)]
+        notify: bool,
+
+        /// What to do with items on external or network volumes that aren't mounted.
+        #[arg(long, value_enum, default_value_t)]
+        unmounted: UnmountedPolicy,
+
+        /// Whether to undo changes made to the Dock since the last apply.
+        #[arg(long, value_enum, default_value_t)]
+        strategy: Strategy,
+
+        /// Apply to every local user's Dock instead of your own; needs root.
+        #[arg(long, conflicts_with_all = ["strategy", "notify"])]
+        all_users: bool,
+    },
+
+    /// Add apps, folders, or files to the live Dock.
+    ///
+    /// `.app` bundles go in the apps section and everything else in the others section.
+    Add {
+        /// The items to add, in order, as paths or bundle identifiers; `-` reads them from stdin.
+        #[arg(required_unless_present_any = ["from_file", "running", "frontmost"])]
+        items: Vec<String>,
+
+        /// Also read items from this file, one per line.
+        #[arg(long, value_name = "FILE")]
+        from_file: Option<PathBuf>,
+
+        /// Also pin every running app that isn't in the Dock yet, at the end.
+        #[arg(long)]
+        running: bool,
+
+        /// Also add the app that's in front right now, e.g. from a hotkey.
+        #[arg(long)]
+        frontmost: bool,
+
+        /// Where to insert: start, end, an index, after:<item>, or before:<item>.
+        #[arg(long, default_value_t)]
+        position: Position,
+
+        /// What to do with items already in the Dock.
+        #[arg(long, value_enum, default_value_t)]
+        policy: AddPolicy,
+
+        /// Show this label under the tile instead of the item's own name; needs a single item.
+        #[arg(long)]
+        label: Option<String>,
+
+        /// Refuse apps inside a disk image or still quarantined, instead of warning.
+        #[arg(long)]
+        strict: bool,
+
+        /// Show what would change without writing anything.
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Save the changes without restarting the Dock.
+        #[arg(long)]
+        no_restart: bool,
+    },
+
+    /// Remove items matching glob patterns from the live Dock, or clear it out.
+    ///
+    /// Patterns are matched against each item's bundle identifier, label, and path,
+    /// e.g. `'com.adobe.*'` or `'/Applications/Utilities/*'`. Clearing whole sections
+    /// asks first unless `--yes` is given.
+    Remove {
+        /// The patterns to remove.
+        #[arg(
+            required_unless_present_any = ["all_apps", "all_others", "everything"],
+            conflicts_with_all = ["all_apps", "all_others", "everything"]
+        )]
+        patterns: Vec<String>,
+
+        /// Treat patterns as regular expressions instead of globs.
+        #[arg(long)]
+        regex: bool,
+
+        /// Remove every item in the apps section.
+        #[arg(long)]
+        all_apps: bool,
+
+        /// Remove every item in the others section.
+        #[arg(long)]
+        all_others: bool,
+
+        /// Remove every item in both sections.
+        #[arg(long)]
+        everything: bool,
+
+        /// Clear sections without asking.
+        #[arg(short, long)]
+        yes: bool,
+
+        /// Show what would change without writing anything.
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Save the changes without restarting the Dock.
+        #[arg(long)]
+        no_restart: bool,
+    },
+
+    /// List the items in the live Dock.
+    List {
+        /// Only list items matching this glob; may be repeated.
+        #[arg(long = "match", value_name = "PATTERN")]
+        patterns: Vec<String>,
+
+        /// Treat `--match` patterns as regular expressions instead of globs.
+        #[arg(long, requires = "patterns")]
+        regex: bool,
+
+        /// Mark pinned apps that are running and list running apps that aren't pinned.
+        #[arg(long)]
+        include_running: bool,
+
+        /// Show when each item was last opened, according to Spotlight.
+        #[arg(long)]
+        last_used: bool,
+
+        /// Only list the apps section.
+        #[arg(long, conflicts_with = "others_only")]
+        apps_only: bool,
+
+        /// Only list the others section.
+        #[arg(long)]
+        others_only: bool,
+    },
+
+    /// Succeed if the live Dock has an item, for scripts.
+    Contains {
+        /// The item's label, bundle identifier, or path.
+        item: String,
+
+        /// Only look in the apps section.
+        #[arg(long, conflicts_with = "others_only")]
+        apps_only: bool,
+
+        /// Only look in the others section.
+        #[arg(long)]
+        others_only: bool,
+    },
+
+    /// Tidy up the live Dock, or remove pinned apps that haven't been used in a while.
+    ///
+    /// Removes duplicate and broken tiles and tiles for stray copies of an app,
+    /// normalizes URLs, and repairs GUIDs; pick
+    /// fixes with their flags, or give none for all of them. With `--unused`, proposes
+    /// stale apps instead and asks before removing each one unless `--yes` is given.
+    Clean {
+        /// Propose apps not opened within this period, e.g. `90d`, `12w`, `6m`, or `1y`.
+        #[arg(
+            long,
+            value_name = "PERIOD",
+            conflicts_with_all = [
+                "dedupe",
+                "prune_broken",
+                "normalize_urls",
+                "repair_guids",
+                "canonical_copies",
+            ]
+        )]
+        unused: Option<String>,
+
+        /// Remove all but the first of several identical tiles.
+        #[arg(long)]
+        dedupe: bool,
+
+        /// Remove tiles whose file no longer exists.
+        #[arg(long)]
+        prune_broken: bool,
+
+        /// Rewrite file URLs the way the Dock writes them.
+        #[arg(long)]
+        normalize_urls: bool,
+
+        /// Drop malformed or duplicate GUIDs so the Dock assigns new ones.
+        #[arg(long)]
+        repair_guids: bool,
+
+        /// Where tiles point at different copies of the same app, keep only the one in
+        /// the most preferred install location (see --prefer).
+        #[arg(long)]
+        canonical_copies: bool,
+
+        /// Remove every proposed app without asking.
+        #[arg(short, long)]
+        yes: bool,
+
+        /// Show what would be removed without asking or writing anything.
+        #[arg(long, conflicts_with = "yes")]
+        dry_run: bool,
+
+        /// Save the changes without restarting the Dock.
+        #[arg(long)]
+        no_restart: bool,
+    },
+
+    /// Check whether the live Dock still matches a profile, without changing it.
+    ///
+    /// Prints the differences and fails if there are any, running the profile's
+    /// `on_drift` hooks.
+    Check {
+        /// The YAML profile to compare against.
+        profile: PathBuf,
+    },
+
+    /// Report whether the Dock matches a profile, for inventory and compliance tools.
+    ///
+    /// Run as root, as extension attributes are, it checks the logged-in user's Dock.
+    /// Exits 3 when the Dock has drifted, except with `--format jamf-ea`, which always
+    /// exits 0 and puts the answer in `<result>…</result>`.
+    Compliance {
+        /// The YAML profile to compare against.
+        #[arg(long)]
+        profile: PathBuf,
+
+        /// How to report the result.
+        #[arg(long, value_enum, default_value_t)]
+        format: ComplianceFormat,
+    },
+
+    /// Show the version, git commit, build target, supported macOS, and features.
+    Version {
+        /// Print the details as JSON, for support requests and inventory.
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Generate roff man pages for docktor and its subcommands.
+    ///
+    /// Prints the main page, or with `--out-dir` writes `docktor.1` and a
+    /// `docktor-<subcommand>.1` for every subcommand, ready to install into `man1`.
+    Man {
+        /// The folder to write every page into.
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
+    },
+
+    /// Check a profile and the live Dock against it, for CI and pre-push hooks.
+    ///
+    /// Fails if the profile doesn't parse, names something that isn't on this
+    /// machine, or the live Dock differs from it. Nothing is written and no hooks run.
+    Verify {
+        /// The YAML profile to verify.
+        #[arg(long)]
+        profile: PathBuf,
+
+        /// Also fail when an entry falls back to an alternative or an optional entry is missing.
+        #[arg(long)]
+        strict: bool,
+    },
+
+    /// Write a commented profile of the live Dock to start managing it from.
+    Init {
+        /// Where to write the profile.
+        #[arg(default_value = "dock.yaml")]
+        path: PathBuf,
+
+        /// Overwrite the file if it already exists.
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Edit the live Dock as a YAML profile in $EDITOR, then apply it.
+    ///
+    /// The edited profile is validated and the changes are shown before anything is
+    /// written.
+    Edit {
+        /// Save the changes without restarting the Dock.
+        #[arg(long)]
+        no_restart: bool,
+    },
+
+    /// Build a Dock step by step: pick apps to pin, lay them out, choose settings.
+    ///
+    /// The result is saved as a profile and, after the changes are shown, applied.
+    Setup {
+        /// Save the changes without restarting the Dock.
+        #[arg(long)]
+        no_restart: bool,
+    },
+
+    /// Summarize the Dock: item counts, settings, last backup, active profile, and drift.
+    Status {
+        /// Print a JSON object instead of text, for monitoring and inventory scripts.
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Report tile counts by kind and app location, settings, plist size, and backup usage.
+    Stats {
+        /// Print the statistics as JSON, for fleet reporting.
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Check that docktor can rewrite a Dock plist without losing or changing anything.
+    ///
+    /// Warns about tiles docktor doesn't fully understand, lists every key that would
+    /// come out different, and fails if there are any.
+    Roundtrip {
+        /// The plist to check; defaults to the live Dock's.
+        plist: Option<PathBuf>,
+    },
+
+    /// Print the live Dock as a tree of sections, items, and settings.
+    Show {
+        /// Flag the items this profile manages.
+        #[arg(long)]
+        profile: Option<PathBuf>,
+    },
+
+    /// Compare two Docks: the live Dock, a profile file, or a snapshot.
+    ///
+    /// With no arguments, compares the latest snapshot to the live Dock. With one,
+    /// compares it to the live Dock. Use `live` to name the live Dock explicitly, and
+    /// `saved` for the Dock as docktor last wrote it, to see changes made outside docktor.
+    Diff {
+        /// The old side: a profile (`.yaml`), a plist file, a snapshot id, name, or tag, `live`, or `saved`.
+        from: Option<String>,
+
+        /// The new side; defaults to the live Dock.
+        to: Option<String>,
+
+        /// How to print the differences.
+        #[arg(long, value_enum, default_value_t)]
+        format: DiffFormat,
+    },
+
+    /// Print the live Dock as a profile, a nix-darwin or chezmoi file, or a script that rebuilds it.
+    Export {
+        /// Emit a shell script instead of a profile, driving the given tool.
+        #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "docktor")]
+        script: Option<ScriptFlavor>,
+
+        /// The declarative format to write.
+        #[arg(long, value_enum, default_value_t, conflicts_with = "script")]
+        format: ExportFormat,
+    },
+
+    /// Fetch the Dock from another Mac over SSH and convert it to a profile.
+    ///
+    /// Prints the profile unless `--output` or `--apply` is given.
+    Pull {
+        /// The machine to pull from, as passed to `ssh` (e.g. `user@host`).
+        destination: String,
+
+        /// Write the profile to this file.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Apply the pulled profile to the local Dock.
+        #[arg(long)]
+        apply: bool,
+
+        /// With `--apply`, show what would change without writing anything.
+        #[arg(long, requires = "apply")]
+        dry_run: bool,
+
+        /// With `--apply`, save the changes without restarting the Dock.
+        #[arg(long, requires = "apply")]
+        no_restart: bool,
+    },
+
+    /// Sync the Dock between machines through a git repository.
+    Sync {
+        /// The git checkout to sync through [default: docktor's data directory].
+        #[arg(long, global = true)]
+        repo: Option<PathBuf>,
+
+        /// The profile file within the repository.
+        #[arg(long, global = true, default_value = "dock.yaml")]
+        file: PathBuf,
+
+        #[command(subcommand)]
+        command: SyncCommand,
+    },
+
+    /// Open what a Dock item points to, to check that the tile still works.
+    Open {
+        /// The item, by label, bundle identifier, position (`3`), or `others:0`.
+        item: ItemRef,
+    },
+
+    /// Show the file or folder a Dock item points to in Finder.
+    Reveal {
+        /// The item, by label, bundle identifier, position (`3`), or `others:0`.
+        item: ItemRef,
+    },
+
+    /// Move an item to another position, or across to the other section.
+    ///
+    /// Apps and spacers can go in the apps section; folders, files, and spacers in the
+    /// others section.
+    Move {
+        /// The item, by label, bundle identifier, position (`3`), or `others:0`.
+        item: ItemRef,
+
+        /// Where to put it: start, end, an index, after:<item>, or before:<item>.
+        #[arg(long)]
+        to: Position,
+
+        /// The section to move it into; by default the anchor's, or its own.
+        #[arg(long, value_enum)]
+        section: Option<Section>,
+
+        /// Show what would change without writing anything.
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Save the changes without restarting the Dock.
+        #[arg(long)]
+        no_restart: bool,
+    },
+
+    /// Change the label shown under an item, leaving the rest of its tile alone.
+    Rename {
+        /// The item's current label or its bundle identifier.
+        item: String,
+
+        /// The new label.
+        label: String,
+
+        /// Show what would change without writing anything.
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Save the changes without restarting the Dock.
+        #[arg(long)]
+        no_restart: bool,
+    },
+
+    /// Point Dock items at a new home folder after a migration or username change.
+    MigratePaths {
+        /// The old path prefix, e.g. `/Users/olduser` [default: the only other home
+        /// folder the Dock refers to].
+        #[arg(long)]
+        from: Option<PathBuf>,
+
+        /// The new path prefix [default: your home folder].
+        #[arg(long)]
+        to: Option<PathBuf>,
+
+        /// Show what would change without writing anything.
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Save the changes without restarting the Dock.
+        #[arg(long)]
+        no_restart: bool,
+    },
+
+    /// Sort the apps section, keeping spacer-separated groups apart.
+    Sort {
+        /// What to sort by.
+        #[arg(long, value_enum, default_value_t)]
+        by: SortKey,
+
+        /// Keep this app at the front; may be repeated to pin several, in order.
+        #[arg(long, value_name = "LABEL_OR_BUNDLE_ID")]
+        pin: Vec<String>,
+
+        /// Show what would change without writing anything.
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Save the changes without restarting the Dock.
+        #[arg(long)]
+        no_restart: bool,
+    },
+
+    /// Work with profile files.
+    Profile {
+        #[command(subcommand)]
+        command: ProfileCommand,
+    },
+
+    /// Save and list snapshots of the Dock.
+    Snapshot {
+        #[command(subcommand)]
+        command: SnapshotCommand,
+    },
+
+    /// Draw the Dock as text, or the Dock a profile would produce, to check a layout.
+    Preview {
+        /// A profile to preview instead of the live Dock.
+        profile: Option<PathBuf>,
+
+        /// How to draw tiles.
+        #[arg(long, value_enum, default_value_t)]
+        style: PreviewStyle,
+    },
+
+    /// Draw the Dock, or the Dock a profile would produce, as a PNG or SVG image.
+    #[cfg(feature = "render")]
+    Render {
+        /// A profile to draw instead of the live Dock.
+        profile: Option<PathBuf>,
+
+        /// Where to write the image.
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// The image format; by default it follows the output file's extension.
+        #[arg(long, value_enum)]
+        format: Option<RenderFormat>,
+
+        /// The width of each icon, in pixels.
+        #[arg(long, default_value_t = 64)]
+        size: u32,
+    },
+
+    /// Show when each tile appeared and which command or profile put it there.
+    ///
+    /// Tiles docktor added are traced through its audit log; others are dated by the
+    /// snapshots they appear in.
+    Blame {
+        /// Print the results as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Roll the Dock back to a snapshot, the latest one by default, or bring back one item.
+    ///
+    /// The snapshot is checked against its recorded checksum before anything is written.
+    /// With `--item`, only that tile is copied back, next to the item it followed in
+    /// the snapshot; the rest of the Dock is left alone.
+    Restore {
+        /// The snapshot to restore, by id, name, or tag.
+        snapshot: Option<String>,
+
+        /// The snapshot to restore, as an alternative to naming it first.
+        #[arg(long, value_name = "SNAPSHOT", conflicts_with = "snapshot")]
+        from: Option<String>,
+
+        /// Only restore this item, by label or position in the snapshot (`others:0`).
+        #[arg(long)]
+        item: Option<ItemRef>,
+
+        /// Show what would change without writing anything.
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Save the changes without restarting the Dock.
+        #[arg(long)]
+        no_restart: bool,
+    },
+
+    /// Install or remove the launch agent that snapshots the Dock every day.
+    Agent {
+        #[command(subcommand)]
+        command: AgentCommand,
+    },
+
+    /// Manage the snapshots taken automatically before each write.
+    Backup {
+        #[command(subcommand)]
+        command: BackupCommand,
+    },
+
+    /// Stop the Dock from being changed by dragging: its contents, size, position, or magnification.
+    Lock {
+        /// What to lock; by default the Dock's contents.
+        #[arg(value_enum, default_values_t = [Lock::Contents], conflicts_with = "all")]
+        what: Vec<Lock>,
+
+        /// Lock everything.
+        #[arg(long)]
+        all: bool,
+
+        /// Print a configuration profile that locks it for every user, for MDM,
+        /// instead of changing this user's preferences.
+        #[arg(long)]
+        payload: bool,
+
+        /// Show what would change without writing anything.
+        #[arg(long, conflicts_with = "payload")]
+        dry_run: bool,
+
+        /// Save the changes without restarting the Dock.
+        #[arg(long, conflicts_with = "payload")]
+        no_restart: bool,
+    },
+
+    /// Let the Dock be changed by dragging again.
+    Unlock {
+        /// What to unlock; by default the Dock's contents.
+        #[arg(value_enum, default_values_t = [Lock::Contents], conflicts_with = "all")]
+        what: Vec<Lock>,
+
+        /// Unlock everything.
+        #[arg(long)]
+        all: bool,
+
+        /// Print a configuration profile that unlocks it for every user, for MDM,
+        /// instead of changing this user's preferences.
+        #[arg(long)]
+        payload: bool,
+
+        /// Show what would change without writing anything.
+        #[arg(long, conflicts_with = "payload")]
+        dry_run: bool,
+
+        /// Save the changes without restarting the Dock.
+        #[arg(long, conflicts_with = "payload")]
+        no_restart: bool,
+    },
+
+    /// Add folders and change how they open.
+    Folder {
+        #[command(subcommand)]
+        command: FolderCommand,
+    },
+
+    /// Add or remove spacers, which have no label to refer to them by.
+    Spacer {
+        #[command(subcommand)]
+        command: SpacerCommand,
+    },
+
+    /// Hide the Dock until the pointer reaches its edge, or stop hiding it.
+    Autohide {
+        /// Whether to turn autohide on, off, or the other way round.
+        #[arg(value_enum, default_value_t = Switch::Toggle)]
+        switch: Switch,
+
+        /// Show what would change without writing anything.
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Save the changes without restarting the Dock.
+        #[arg(long, conflicts_with = "live")]
+        no_restart: bool,
+
+        /// Change autohide on the spot through System Events, without writing the plist
+        /// or restarting the Dock. The preference still persists, but the change isn't
+        /// snapshotted or logged.
+        #[arg(long)]
+        live: bool,
+    },
+
+    /// Change Dock settings, with their values checked before they're written.
+    Settings {
+        #[command(subcommand)]
+        command: SettingsCommand,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum SyncCommand {
+    /// Clone the repository profiles are synced through.
+    Init {
+        /// The git URL to clone.
+        url: String,
+    },
+
+    /// Commit the live Dock to the repository and push it.
+    Push {
+        /// Overwrite the synced profile even if it changed on another machine.
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Pull the synced profile and apply it to the live Dock.
+    Pull {
+        /// Apply the profile even if the live Dock has unsynced changes.
+        #[arg(long)]
+        force: bool,
+
+        /// Show what would change without writing anything.
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Save the changes without restarting the Dock.
+        #[arg(long)]
+        no_restart: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum ProfileCommand {
+    /// Check a profile for mistakes without touching the Dock.
+    ///
+    /// Reports syntax errors, unknown keys, bad positions, apps that aren't installed,
+    /// and rules that conflict.
+    Validate {
+        /// The YAML profile to check.
+        profile: PathBuf,
+    },
+
+    /// Check a profile against the team's conventions, as set under `[lint]` in the
+    /// config file.
+    ///
+    /// Rules: no-user-paths, apps-by-bundle-id, and max-apps. Each can be `off`, `warn`,
+    /// or `error`; any error fails the lint.
+    Lint {
+        /// The YAML profile to lint.
+        profile: PathBuf,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum SnapshotCommand {
+    /// Save a snapshot of the live Dock.
+    Save {
+        /// A unique name to find the snapshot by later; named snapshots are never pruned.
+        #[arg(long)]
+        name: Option<String>,
+
+        /// A tag to find the snapshot by later; repeat for several.
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+
+        /// Skip saving when the Dock is unchanged since the latest snapshot.
+        #[arg(long)]
+        if_changed: bool,
+    },
+
+    /// List saved snapshots, oldest first.
+    List,
+
+    /// Check every snapshot against its recorded checksum.
+    Verify,
+
+    /// Show what changed between two snapshots, or pick changes to apply to the live Dock.
+    ///
+    /// With `--interactive`, each change from `a` to `b` is shown in turn; the ones
+    /// accepted are replayed on the live Dock, matching items by identity.
+    Diff {
+        /// The older snapshot, by id, name, or tag.
+        a: String,
+
+        /// The newer snapshot, by id, name, or tag.
+        b: String,
+
+        /// How to print the differences.
+        #[arg(long, value_enum, default_value_t, conflicts_with = "interactive")]
+        format: DiffFormat,
+
+        /// Ask about each change and apply the accepted ones to the live Dock.
+        #[arg(short, long)]
+        interactive: bool,
+
+        /// With `--interactive`, show what would change without writing anything.
+        #[arg(long, requires = "interactive")]
+        dry_run: bool,
+
+        /// With `--interactive`, save the changes without restarting the Dock.
+        #[arg(long, requires = "interactive")]
+        no_restart: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum AgentCommand {
+    /// Install the agent, which saves a snapshot tagged `daily` when the Dock changed.
+    Install {
+        /// The time of day to take the snapshot, as HH:MM.
+        #[arg(long, default_value_t)]
+        at: TimeOfDay,
+    },
+
+    /// Unload and remove the agent.
+    Uninstall,
+}
+
+#[derive(Debug, Subcommand)]
+enum BackupCommand {
+    /// Delete old snapshots, following the config's retention limits unless overridden.
+    ///
+    /// The newest snapshot is always kept.
+    Prune {
+        /// Keep at most this many snapshots, instead of `max_backups` from the config.
+        #[arg(long)]
+        max_backups: Option<usize>,
+
+        /// Delete snapshots older than this many days, instead of `max_age_days`.
+        #[arg(long)]
+        max_age_days: Option<u64>,
+
+        /// List what would be deleted without deleting anything.
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum FolderCommand {
+    /// Add a folder, or a smart folder's `.savedSearch` file, to the others section.
+    Add {
+        /// The folder or saved search to add.
+        path: PathBuf,
+
+        /// Show it as a stack of its contents or as a folder icon.
+        #[arg(long, value_enum)]
+        display: Option<FolderDisplay>,
+
+        /// How to show its contents when opened.
+        #[arg(long, value_enum)]
+        view: Option<FolderView>,
+
+        /// How to sort its contents.
+        #[arg(long, value_enum)]
+        sort: Option<Arrangement>,
+
+        /// Where to insert: start, end, an index, after:<item>, or before:<item>.
+        #[arg(long, default_value_t)]
+        position: Position,
+
+        /// Show what would change without writing anything.
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Save the changes without restarting the Dock.
+        #[arg(long)]
+        no_restart: bool,
+    },
+
+    /// Change how a folder already in the Dock is shown; options not given are kept.
+    #[command(group = clap::ArgGroup::new("options").required(true).multiple(true))]
+    SetOptions {
+        /// The folder, by label or position (`others:0`).
+        item: ItemRef,
+
+        /// Show it as a stack of its contents or as a folder icon.
+        #[arg(long, value_enum, group = "options")]
+        display: Option<FolderDisplay>,
+
+        /// How to show its contents when opened.
+        #[arg(long, value_enum, group = "options")]
+        view: Option<FolderView>,
+
+        /// How to sort its contents.
+        #[arg(long, value_enum, group = "options")]
+        sort: Option<Arrangement>,
+
+        /// Show what would change without writing anything.
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Save the changes without restarting the Dock.
+        #[arg(long)]
+        no_restart: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum SpacerCommand {
+    /// Add a spacer, at the end of the apps section unless told otherwise.
+    Add {
+        /// Add a half-width spacer.
+        #[arg(long, conflicts_with = "flex")]
+        small: bool,
+
+        /// Add a spacer that grows to fill the available space.
+        #[arg(long)]
+        flex: bool,
+
+        /// Put it right after this item, by label or bundle identifier.
+        #[arg(long, value_name = "ITEM")]
+        after: Option<String>,
+
+        /// The section to add it to; by default the `--after` item's, or apps.
+        #[arg(long, value_enum)]
+        section: Option<Section>,
+
+        /// Show what would change without writing anything.
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Save the changes without restarting the Dock.
+        #[arg(long)]
+        no_restart: bool,
+    },
+
+    /// Remove the spacer at a position, as shown by `docktor list`.
+    Remove {
+        /// The spacer's zero-based position.
+        #[arg(long)]
+        index: usize,
+
+        /// The section the spacer is in.
+        #[arg(long, value_enum, default_value = "apps")]
+        section: Section,
+
+        /// Show what would change without writing anything.
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Save the changes without restarting the Dock.
+        #[arg(long)]
+        no_restart: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum SettingsCommand {
+    /// Set a setting, e.g. `docktor settings set orientation left`.
+    Set {
+        /// The setting to change.
+        #[arg(value_enum)]
+        setting: Setting,
+
+        /// Its new value.
+        value: String,
+
+        /// Show what would change without writing anything.
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Save the changes without restarting the Dock.
+        #[arg(long)]
+        no_restart: bool,
+    },
+
+    /// Turn an on/off setting on or off, e.g. `docktor settings switch showhidden on`.
+    Switch {
+        /// The setting to change.
+        #[arg(value_enum)]
+        setting: Setting,
+
+        /// Whether to turn it on, off, or the other way round.
+        #[arg(value_enum)]
+        switch: Switch,
+
+        /// Show what would change without writing anything.
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Save the changes without restarting the Dock.
+        #[arg(long)]
+        no_restart: bool,
+    },
+
+    /// Show the typed settings, with the Dock's default for those that aren't set.
+    Show {
+        /// Print the settings as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// List every typed setting with its current value and the Dock's default.
+    List,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    init_logging(&cli);
+
+    match run(cli) {
+        Ok(outcome) => outcome.exit_code(),
+        Err(err) => {
+            eprintln!("Error: {err:?}");
+            error_exit_code(&err)
+        }
+    }
+}
+
+fn run(mut cli: Cli) -> Result<Outcome> {
+    let prefer = std::mem::take(&mut cli.prefer);
+    Config::init_with(|config| {
+        if !prefer.is_empty() {
+            config.install_preference = Some(prefer);
+        }
+    })?;
+
+    match cli.command {
+        Command::Apply {
+            profile,
+            dry_run,
+            no_restart,
+            notify,
+            unmounted,
+            strategy,
+            all_users,
+        } => {
+            let profile = Profile::load(profile)?;
+            let show_progress = !cli.quiet && std::io::stderr().is_terminal();
+            let options = ApplyOptions {
+                dry_run,
+                no_restart,
+                unmounted,
+                strategy,
+                force: false,
+            };
+            if all_users {
+                return apply_all_users(&profile, options, cli.color);
+            }
+            let diff = if show_progress {
+                let diff = profile.apply_with(options, &status_line, &CancellationToken::new());
+                eprint!("\r\x1b[K");
+                diff?
+            } else {
+                profile.apply(options)?
+            };
+            print!("{}", diff.render(cli.color));
+            let login_items = profile.apply_login_items(dry_run)?;
+            if !login_items.is_empty() {
+                println!("Login items:");
+                for app in &login_items {
+                    println!("  + {}", app.display_name);
+                }
+            }
+            let desktop = profile.apply_desktop(dry_run)?;
+            if !desktop.is_empty() {
+                println!("Desktop:");
+                for change in &desktop {
+                    println!("  {change}");
+                }
+            }
+            if dry_run {
+                print!("{}", SideEffects::of(&diff, options, &profile.hooks)?);
+            }
+            if notify && !dry_run && !diff.is_empty() {
+                let message = format!(
+                    "Applied profile {}: {}",
+                    profile.name().unwrap_or_default(),
+                    diff.summary()
+                );
+                if let Err(err) = notify::post("docktor", &message) {
+                    tracing::warn!("{err:#}");
+                }
+            }
+            if !login_items.is_empty() || !desktop.is_empty() {
+                return Ok(Outcome::Changed);
+            }
+            Ok(Outcome::from_diff(&diff))
+        }
+        Command::Add {
+            items,
+            from_file,
+            running,
+            frontmost,
+            position,
+            policy,
+            label,
+            strict,
+            dry_run,
+            no_restart,
+        } => {
+            let live = Dock::load()?;
+            let mut desired = live.clone();
+            let mut entries = read_entries(&items, from_file.as_deref())?;
+            if frontmost {
+                let app = MacApp::frontmost()?;
+                entries.push(app.path.display().to_string());
+            }
+            if label.is_some() && (entries.len() != 1 || running) {
+                bail!("--label needs exactly one item to add");
+            }
+            add(
+                &mut desired,
+                &entries,
+                &position,
+                policy,
+                label.as_deref(),
+                strict,
+            )?;
+            if running {
+                desired.pin_running_apps()?;
+            }
+            write(&live, &desired, dry_run, no_restart, cli.color)
+        }
+        Command::Remove {
+            all_apps,
+            all_others,
+            everything,
+            yes,
+            dry_run,
+            no_restart,
+            ..
+        } if all_apps || all_others || everything => {
+            let sections: &[Section] = match (all_apps || everything, all_others || everything) {
+                (true, true) => &Section::ALL,
+                (true, false) => &[Section::Apps],
+                _ => &[Section::Others],
+            };
+            let live = Dock::load()?;
+            let count: usize = sections
+                .iter()
+                .map(|section| live.section(*section).len())
+                .sum();
+            let names: Vec<&str> = sections.iter().map(|section| section.name()).collect();
+            let question = format!("Remove all {count} items from {}?", names.join(" and "));
+            if !(dry_run || yes || confirm(&question)?) {
+                return Ok(Outcome::Unchanged);
+            }
+
+            let mut desired = live.clone();
+            for section in sections {
+                desired.clear(*section);
+            }
+            write(&live, &desired, dry_run, no_restart, cli.color)
+        }
+        Command::Remove {
+            patterns,
+            regex,
+            dry_run,
+            no_restart,
+            ..
+        } => {
+            let matchers = matchers(&patterns, regex)?;
+            let live = Dock::load()?;
+            let mut desired = live.clone();
+            if desired.remove_matching(&matchers) == 0 {
+                bail!("No Dock items match {}", patterns.join(", "));
+            }
+            write(&live, &desired, dry_run, no_restart, cli.color)
+        }
+        Command::List {
+            patterns,
+            regex,
+            include_running,
+            last_used,
+            apps_only,
+            others_only,
+        } => {
+            let options = ListOptions {
+                matchers: matchers(&patterns, regex)?,
+                running: include_running.then(running::running_apps).transpose()?,
+                last_used,
+            };
+            let dock = Dock::load_sections(sections(apps_only, others_only))?;
+            print!("{}", listing::render(&dock, &options, cli.color));
+            Ok(Outcome::Unchanged)
+        }
+        Command::Contains {
+            item,
+            apps_only,
+            others_only,
+        } => {
+            let sections = sections(apps_only, others_only);
+            let dock = Dock::load_sections(sections)?;
+            let found = sections
+                .iter()
+                .flat_map(|&section| dock.section(section))
+                .any(|existing| {
+                    existing.matches(&item)
+                        || existing.path().is_some_and(|path| path == Path::new(&item))
+                });
+            if !found {
+                bail!("The Dock has no item matching {item:?}");
+            }
+            Ok(Outcome::Unchanged)
+        }
+        Command::Clean {
+            unused: None,
+            dedupe,
+            prune_broken,
+            normalize_urls,
+            repair_guids,
+            canonical_copies,
+            dry_run,
+            no_restart,
+            ..
+        } => {
+            let options =
+                if dedupe || prune_broken || normalize_urls || repair_guids || canonical_copies {
+                    CleanOptions {
+                        dedupe,
+                        prune_broken,
+                        normalize_urls,
+                        repair_guids,
+                        canonical_copies,
+                    }
+                } else {
+                    CleanOptions::all()
+                };
+            let live = Dock::load()?;
+            let mut desired = live.clone();
+            let report = desired.clean(&options);
+            if report.is_empty() {
+                println!("Nothing to clean.");
+                return Ok(Outcome::Unchanged);
+            }
+
+            transaction::write_changes(
+                &live,
+                &desired,
+                ApplyOptions {
+                    dry_run,
+                    no_restart,
+                    force: report.touches_bookkeeping(),
+                    ..ApplyOptions::default()
+                },
+            )?;
+            print!("{report}");
+            Ok(Outcome::Changed)
+        }
+        Command::Clean {
+            unused: Some(unused),
+            yes,
+            dry_run,
+            no_restart,
+            ..
+        } => {
+            let live = Dock::load()?;
+            let now = OffsetDateTime::now_utc();
+            let mut doomed = Vec::new();
+            for (index, item, used) in live.unused_apps(usage::parse_period(&unused)?)? {
+                let question = format!(
+                    "Remove {} (last used {})?",
+                    item.label(),
+                    usage::describe_age(used, now)
+                );
+                if dry_run || yes || confirm(&question)? {
+                    doomed.push(index);
+                }
+            }
+
+            let mut desired = live.clone();
+            if let Some(apps) = &mut desired.applications {
+                for index in doomed.into_iter().rev() {
+                    apps.remove(index);
+                }
+            }
+            write(&live, &desired, dry_run, no_restart, cli.color)
+        }
+        Command::Check { profile } => {
+            let diff = Profile::load(&profile)?.check()?;
+            print!("{}", diff.render(cli.color));
+            if diff.is_empty() {
+                Ok(Outcome::Unchanged)
+            } else {
+                Ok(Outcome::Drift)
+            }
+        }
+        Command::Version { json } => {
+            let info = BuildInfo::current();
+            if json {
+                println!("{}", serde_json::to_string_pretty(&info)?);
+            } else {
+                print!("{info}");
+            }
+            Ok(Outcome::Unchanged)
+        }
+        Command::Man { out_dir } => {
+            match out_dir {
+                Some(dir) => {
+                    std::fs::create_dir_all(&dir)
+                        .with_context(|| format!("Failed to create {}", dir.display()))?;
+                    clap_mangen::generate_to(Cli::command(), &dir).with_context(|| {
+                        format!("Failed to write man pages to {}", dir.display())
+                    })?;
+                    println!("Wrote man pages to {}", dir.display());
+                }
+                None => clap_mangen::Man::new(Cli::command())
+                    .render(&mut std::io::stdout())
+                    .context("Failed to write the man page")?,
+            }
+            Ok(Outcome::Unchanged)
+        }
+        Command::Compliance { profile, format } => {
+            let compliance = Compliance::evaluate(&profile);
+            match format {
+                ComplianceFormat::JamfEa => {
+                    println!("{}", compliance.to_jamf_ea());
+                    return Ok(Outcome::Unchanged);
+                }
+                ComplianceFormat::Json => {
+                    println!("{}", serde_json::to_string_pretty(&compliance.to_json())?)
+                }
+                ComplianceFormat::Text => match &compliance {
+                    Compliance::Compliant => {
+                        println!("The Dock matches {}.", profile.display())
+                    }
+                    Compliance::Drifted(diff) => print!("{}", diff.render(cli.color)),
+                    Compliance::Unknown(_) => {}
+                },
+            }
+            match compliance {
+                Compliance::Compliant => Ok(Outcome::Unchanged),
+                Compliance::Drifted(_) => Ok(Outcome::Drift),
+                Compliance::Unknown(reason) => Err(anyhow::anyhow!(reason)),
+            }
+        }
+        Command::Verify { profile, strict } => {
+            report_problems(&profile, &validate::validate_file_with(&profile, strict)?)?;
+            let live = Dock::load()?;
+            let diff = DockDiff::between(&live, &Profile::load(&profile)?.apply_to(&live)?);
+            if diff.is_empty() {
+                println!(
+                    "{} is valid and the live Dock matches it.",
+                    profile.display()
+                );
+                Ok(Outcome::Unchanged)
+            } else {
+                print!("{}", diff.render(cli.color));
+                Ok(Outcome::Drift)
+            }
+        }
+        Command::Stats { json } => {
+            let stats = Stats::collect()?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&stats)?);
+                return Ok(Outcome::Unchanged);
+            }
+            let counts = |counts: &std::collections::BTreeMap<&str, usize>| {
+                if counts.is_empty() {
+                    return "none".to_string();
+                }
+                counts
+                    .iter()
+                    .map(|(name, count)| format!("{name} {count}"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            println!(
+                "Plist:       {} ({} bytes)",
+                stats.plist_path.display(),
+                stats.plist_bytes
+            );
+            println!("Tiles:       {}", counts(&stats.kinds));
+            println!("Apps from:   {}", counts(&stats.app_sources));
+            let settings: Vec<String> = stats
+                .settings
+                .iter()
+                .map(|(key, value)| format!("{key} {value}"))
+                .collect();
+            if settings.is_empty() {
+                println!("Settings:    none");
+            } else {
+                println!("Settings:    {}", settings.join(", "));
+            }
+            let backups = &stats.backups;
+            match (&backups.oldest, &backups.newest) {
+                (Some(oldest), Some(newest)) => println!(
+                    "Backups:     {} using {} bytes, {oldest} to {newest}",
+                    backups.count, backups.bytes
+                ),
+                _ => println!("Backups:     none"),
+            }
+            Ok(Outcome::Unchanged)
+        }
+        Command::Status { json } => {
+            let status = Status::collect()?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&status)?);
+            } else {
+                let or_none = |value: Option<String>| value.unwrap_or_else(|| "none".to_string());
+                println!("Plist:          {}", status.plist_path.display());
+                println!("Apps:           {}", status.apps);
+                println!("Others:         {}", status.others);
+                println!("Settings:       {}", status.settings.len());
+                println!("Last backup:    {}", or_none(status.last_backup));
+                println!(
+                    "Active profile: {}",
+                    or_none(status.active_profile.map(|path| path.display().to_string()))
+                );
+                println!(
+                    "Drift:          {}",
+                    match status.drifted {
+                        Some(true) => "drifted",
+                        Some(false) => "in sync",
+                        None => "unknown",
+                    }
+                );
+                println!(
+                    "Outside edits:  {}",
+                    match status.modified_externally {
+                        Some(true) => "modified since docktor last wrote it",
+                        Some(false) => "none",
+                        None => "unknown",
+                    }
+                );
+                println!(
+                    "Copies:         {}",
+                    if status.duplicate_copies.is_empty() {
+                        "none".to_string()
+                    } else {
+                        format!(
+                            "{} pinned from several copies; see `docktor clean --canonical-copies --dry-run`",
+                            status.duplicate_copies.join(", ")
+                        )
+                    }
+                );
+                println!(
+                    "Dock PID:       {}",
+                    or_none(status.dock_pid.map(|pid| pid.to_string()))
+                );
+            }
+            Ok(Outcome::Unchanged)
+        }
+        Command::Roundtrip { plist } => {
+            let plist = match plist {
+                Some(plist) => plist,
+                None => Dock::default_path()?,
+            };
+            let (_, warnings) = Dock::load_from_with(&plist, ParseMode::Lenient)?;
+            for warning in &warnings {
+                println!("warning: {warning}");
+            }
+            let differences = roundtrip::roundtrip_check(&plist)?;
+            for difference in &differences {
+                println!("{difference}");
+            }
+            if !differences.is_empty() {
+                bail!(
+                    "{} value{} would change if docktor rewrote {}",
+                    differences.len(),
+                    if differences.len() == 1 { "" } else { "s" },
+                    plist.display()
+                );
+            }
+            println!("{} round-trips cleanly.", plist.display());
+            Ok(Outcome::Unchanged)
+        }
+        Command::Show { profile } => {
+            let profile = profile.map(Profile::load).transpose()?;
+            let title = format!("Dock ({})", Dock::default_path()?.display());
+            print!(
+                "{}",
+                listing::render_tree(&Dock::load()?, &title, profile.as_ref(), cli.color)
+            );
+            Ok(Outcome::Unchanged)
+        }
+        Command::Init { path, force } => {
+            if path.exists() && !force {
+                bail!(
+                    "{} already exists; pass --force to overwrite it",
+                    path.display()
+                );
+            }
+            std::fs::write(&path, export::scaffold(&Dock::load()?)?)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            println!(
+                "Wrote {}; apply it with `docktor apply {}`.",
+                path.display(),
+                path.display()
+            );
+            Ok(Outcome::Unchanged)
+        }
+        Command::Edit { no_restart } => edit(no_restart, cli.color),
+        Command::Setup { no_restart } => setup(no_restart, cli.color, cli.quiet),
+        Command::Diff { from, to, format } => {
+            diff(from.as_deref(), to.as_deref(), format, cli.color)
+        }
+        Command::Export { script, format } => {
+            let dock = Dock::load()?;
+            match script {
+                Some(flavor) => print!("{}", export::script(&dock, flavor)?),
+                None => print!("{}", export::render(&dock, format)?),
+            }
+            Ok(Outcome::Unchanged)
+        }
+        Command::Pull {
+            destination,
+            output,
+            apply: should_apply,
+            dry_run,
+            no_restart,
+        } => {
+            let remote = remote::fetch(&destination)?;
+            let profile = Profile::from_dock_with_home(&remote.dock, Some(&remote.home));
+
+            if let Some(output) = &output {
+                std::fs::write(output, profile.to_yaml()?)
+                    .with_context(|| format!("Failed to write profile to {}", output.display()))?;
+            } else if !should_apply {
+                print!("{}", profile.to_yaml()?);
+            }
+            if should_apply {
+                let diff = apply(&profile, dry_run, no_restart, cli.color)?;
+                return Ok(Outcome::from_diff(&diff));
+            }
+            Ok(Outcome::Unchanged)
+        }
+        Command::Sync {
+            repo,
+            file,
+            command,
+        } => {
+            let path = match repo {
+                Some(path) => path,
+                None => SyncRepo::default_path()?,
+            };
+            match command {
+                SyncCommand::Init { url } => {
+                    SyncRepo::clone_from(&url, &path, &file)?;
+                    println!("Cloned {url} into {}", path.display());
+                    Ok(Outcome::Unchanged)
+                }
+                SyncCommand::Push { force } => {
+                    let repo = SyncRepo::open(&path, &file)?;
+                    match repo.push(&Dock::load()?, force)? {
+                        PushOutcome::UpToDate => println!("Synced profile is already up to date."),
+                        PushOutcome::Pushed => println!("Pushed the live Dock."),
+                    }
+                    // Pushing never changes the local Dock.
+                    Ok(Outcome::Unchanged)
+                }
+                SyncCommand::Pull {
+                    force,
+                    dry_run,
+                    no_restart,
+                } => {
+                    let repo = SyncRepo::open(&path, &file)?;
+                    let profile = repo.pull(&Dock::load()?, force)?;
+                    let diff = apply(&profile, dry_run, no_restart, cli.color)?;
+                    if !dry_run {
+                        repo.mark_synced()?;
+                    }
+                    Ok(Outcome::from_diff(&diff))
+                }
+            }
+        }
+        Command::Open { item } => {
+            let dock = Dock::load()?;
+            let Some(tile) = dock.get(&item) else {
+                bail!("No Dock item matches {item}");
+            };
+            tile.launch()?;
+            Ok(Outcome::Unchanged)
+        }
+        Command::Reveal { item } => {
+            let dock = Dock::load()?;
+            let Some(tile) = dock.get(&item) else {
+                bail!("No Dock item matches {item}");
+            };
+            tile.reveal()?;
+            if let Some(path) = tile.path() {
+                println!("{}", path.display());
+            }
+            Ok(Outcome::Unchanged)
+        }
+        Command::Move {
+            item,
+            to,
+            section,
+            dry_run,
+            no_restart,
+        } => {
+            let live = Dock::load()?;
+            let mut desired = live.clone();
+            desired.move_to(&item, &to, section)?;
+            write(&live, &desired, dry_run, no_restart, cli.color)
+        }
+        Command::Rename {
+            item,
+            label,
+            dry_run,
+            no_restart,
+        } => {
+            let live = Dock::load()?;
+            let mut desired = live.clone();
+            if !desired.rename(&item, &label) {
+                bail!("No Dock item matches {item:?}");
+            }
+            write(&live, &desired, dry_run, no_restart, cli.color)
+        }
+        Command::MigratePaths {
+            from,
+            to,
+            dry_run,
+            no_restart,
+        } => {
+            let live = Dock::load()?;
+            let to = match to {
+                Some(to) => to,
+                None => docktor::paths::home_dir().context("Could not determine home directory")?,
+            };
+            let from = match from {
+                Some(from) => from,
+                None => other_home(&live, &to)?,
+            };
+            let mut desired = live.clone();
+            if desired.rewrite_prefix(&from, &to) == 0 {
+                bail!("No Dock item refers to {}", from.display());
+            }
+            write(&live, &desired, dry_run, no_restart, cli.color)
+        }
+        Command::Sort {
+            by,
+            pin,
+            dry_run,
+            no_restart,
+        } => {
+            let live = Dock::load()?;
+            let mut desired = live.clone();
+            let pinned: Vec<&str> = pin.iter().map(String::as_str).collect();
+            desired.sort_apps(by, &pinned);
+            write(&live, &desired, dry_run, no_restart, cli.color)
+        }
+        Command::Profile { command } => match command {
+            ProfileCommand::Validate { profile } => {
+                report_problems(&profile, &validate::validate_file(&profile)?)?;
+                println!("{} is valid.", profile.display());
+                Ok(Outcome::Unchanged)
+            }
+            ProfileCommand::Lint { profile } => {
+                let findings = lint::lint_file(&profile)?;
+                for finding in &findings {
+                    println!("{}: {finding}", profile.display());
+                }
+                let errors = findings.iter().filter(|finding| finding.is_error()).count();
+                if errors > 0 {
+                    return Err(Invalid(format!(
+                        "Found {errors} lint error{} in {}",
+                        if errors == 1 { "" } else { "s" },
+                        profile.display()
+                    ))
+                    .into());
+                }
+                if findings.is_empty() {
+                    println!("{} follows every lint rule.", profile.display());
+                }
+                Ok(Outcome::Unchanged)
+            }
+        },
+        Command::Snapshot { command } => match command {
+            SnapshotCommand::Save {
+                name,
+                tags,
+                if_changed,
+            } => {
+                let snapshot = if if_changed {
+                    Snapshot::take_labeled_if_changed(name, tags)?
+                } else {
+                    Some(Snapshot::take_labeled(name, tags)?)
+                };
+                match snapshot {
+                    Some(snapshot) => println!("Saved snapshot {}", snapshot.id),
+                    None => println!("The Dock hasn't changed since the latest snapshot."),
+                }
+                // The daily agent only ever saves, so retention has to happen here too.
+                let policy = &Config::current().backup;
+                if policy.has_retention()
+                    && let Err(err) = Snapshot::prune(policy)
+                {
+                    tracing::warn!("{err:#}");
+                }
+                Ok(Outcome::Unchanged)
+            }
+            SnapshotCommand::List => {
+                for snapshot in Snapshot::list()? {
+                    let mut line = snapshot.id.clone();
+                    if let Some(name) = &snapshot.name {
+                        line.push_str(&format!("  {name}"));
+                    }
+                    if !snapshot.tags.is_empty() {
+                        line.push_str(&format!("  [{}]", snapshot.tags.join(", ")));
+                    }
+                    println!("{line}");
+                }
+                Ok(Outcome::Unchanged)
+            }
+            SnapshotCommand::Verify => {
+                let snapshots = Snapshot::list()?;
+                let mut failed = 0;
+                for snapshot in &snapshots {
+                    match snapshot.verify() {
+                        Ok(()) if snapshot.is_compressed() => println!("ok   {}", snapshot.id),
+                        Ok(()) => println!("ok   {} (no checksum recorded)", snapshot.id),
+                        Err(err) => {
+                            failed += 1;
+                            println!("FAIL {err:#}");
+                        }
+                    }
+                }
+                if failed > 0 {
+                    bail!(
+                        "{failed} of {} snapshots failed verification",
+                        snapshots.len()
+                    );
+                }
+                Ok(Outcome::Unchanged)
+            }
+            SnapshotCommand::Diff {
+                a,
+                b,
+                format,
+                interactive,
+                dry_run,
+                no_restart,
+            } => {
+                let diff =
+                    DockDiff::between(&Snapshot::find(&a)?.load()?, &Snapshot::find(&b)?.load()?);
+                if !interactive {
+                    match format {
+                        DiffFormat::Text => print!("{}", diff.render(cli.color)),
+                        DiffFormat::JsonPatch => println!("{:#}", diff.to_json_patch()),
+                    }
+                    return Ok(Outcome::from_diff(&diff));
+                }
+                cherry_pick(&diff, dry_run, no_restart, cli.color)
+            }
+        },
+        Command::Preview { profile, style } => {
+            let live = Dock::load()?;
+            let dock = match profile {
+                Some(path) => Profile::load(&path)?.apply_to(&live)?,
+                None => live,
+            };
+            print!("{}", preview::render(&dock, style));
+            Ok(Outcome::Unchanged)
+        }
+        #[cfg(feature = "render")]
+        Command::Render {
+            profile,
+            output,
+            format,
+            size,
+        } => {
+            let format = format
+                .or_else(|| RenderFormat::for_path(&output))
+                .context("Pass --format, or name the output file .png or .svg")?;
+            let live = Dock::load()?;
+            let dock = match profile {
+                Some(path) => Profile::load(&path)?.apply_to(&live)?,
+                None => live,
+            };
+            std::fs::write(&output, render::render(&dock, format, size)?)
+                .with_context(|| format!("Failed to write {}", output.display()))?;
+            eprintln!("Wrote {}", output.display());
+            Ok(Outcome::Unchanged)
+        }
+        Command::Blame { json } => {
+            let blames = blame::blame(&Dock::load()?)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&blames)?);
+                return Ok(Outcome::Unchanged);
+            }
+            let width = blames
+                .iter()
+                .map(|b| b.label.chars().count())
+                .max()
+                .unwrap_or(0);
+            let mut heading = None;
+            for blame in &blames {
+                if heading != Some(blame.section) {
+                    println!("{}:", blame.section.name());
+                    heading = Some(blame.section);
+                }
+                println!(
+                    "{:>4}  {:<width$}  {}",
+                    blame.index, blame.label, blame.origin
+                );
+            }
+            Ok(Outcome::Unchanged)
+        }
+        Command::Restore {
+            snapshot,
+            from,
+            item,
+            dry_run,
+            no_restart,
+        } => {
+            let snapshot = match snapshot.or(from) {
+                Some(id) => Snapshot::find(&id)?,
+                None => Snapshot::latest()?.context("No snapshots saved yet")?,
+            };
+            let saved = snapshot.load()?;
+            let live = Dock::load()?;
+            let desired = match item {
+                Some(item) => {
+                    let mut desired = live.clone();
+                    let (section, index) = desired.restore_item(&saved, &item)?;
+                    println!(
+                        "Restoring {} to {} {index} from snapshot {}",
+                        desired.section(section)[index].label(),
+                        section.name(),
+                        snapshot.id
+                    );
+                    desired
+                }
+                None => {
+                    println!("Restoring snapshot {}", snapshot.id);
+                    saved
+                }
+            };
+            write(&live, &desired, dry_run, no_restart, cli.color)
+        }
+        Command::Agent { command } => {
+            match command {
+                AgentCommand::Install { at } => {
+                    let path = agent::install(at)?;
+                    println!(
+                        "Installed {}; the Dock will be snapshotted daily at {at}",
+                        path.display()
+                    );
+                }
+                AgentCommand::Uninstall => {
+                    if agent::uninstall()? {
+                        println!("Removed the snapshot agent.");
+                    } else {
+                        println!("The snapshot agent isn't installed.");
+                    }
+                }
+            }
+            Ok(Outcome::Unchanged)
+        }
+        Command::Backup { command } => match command {
+            BackupCommand::Prune {
+                max_backups,
+                max_age_days,
+                dry_run,
+            } => {
+                let config = &Config::current().backup;
+                let policy = BackupPolicy {
+                    max_backups: max_backups.or(config.max_backups),
+                    max_age_days: max_age_days.or(config.max_age_days),
+                    ..config.clone()
+                };
+                if !policy.has_retention() {
+                    bail!(
+                        "No retention limits are set; pass --max-backups or --max-age-days, \
+                         or set max_backups or max_age_days under [backup] in the config file"
+                    );
+                }
+                let expired = if dry_run {
+                    Snapshot::expired(&policy)?
+                } else {
+                    Snapshot::prune(&policy)?
+                };
+                let verb = if dry_run { "Would delete" } else { "Deleted" };
+                for snapshot in &expired {
+                    println!("{verb} {}", snapshot.id);
+                }
+                if expired.is_empty() {
+                    println!("No snapshots to delete.");
+                }
+                Ok(Outcome::Unchanged)
+            }
+        },
+        Command::Lock {
+            what,
+            all,
+            payload,
+            dry_run,
+            no_restart,
+        } => set_locks(&what, all, true, payload, dry_run, no_restart, cli.color),
+        Command::Unlock {
+            what,
+            all,
+            payload,
+            dry_run,
+            no_restart,
+        } => set_locks(&what, all, false, payload, dry_run, no_restart, cli.color),
+        Command::Folder { command } => match command {
+            FolderCommand::Add {
+                path,
+                display,
+                view,
+                sort,
+                position,
+                dry_run,
+                no_restart,
+            } => {
+                let mut item = DockItem::from_path(&path)?;
+                if item.kind != DockItemKind::DirectoryTile {
+                    bail!("{} is not a folder or saved search", path.display());
+                }
+                item.metadata.set_folder_options(&FolderOptions {
+                    display,
+                    view,
+                    arrangement: sort,
+                });
+
+                let live = Dock::load()?;
+                let mut desired = live.clone();
+                if desired
+                    .add_at(Section::Others, item, &position, AddPolicy::Skip)?
+                    .is_none()
+                {
+                    bail!(
+                        "{} is already in the Dock; use `docktor folder set-options` to change it",
+                        path.display()
+                    );
+                }
+                write(&live, &desired, dry_run, no_restart, cli.color)
+            }
+            FolderCommand::SetOptions {
+                item,
+                display,
+                view,
+                sort,
+                dry_run,
+                no_restart,
+            } => {
+                let live = Dock::load()?;
+                let mut desired = live.clone();
+                let folder = match desired.locate(&item) {
+                    Some((Section::Others, index)) => desired
+                        .others
+                        .as_mut()
+                        .and_then(|others| others.get_mut(index)),
+                    _ => None,
+                }
+                .filter(|folder| folder.kind == DockItemKind::DirectoryTile)
+                .with_context(|| format!("No folder in the Dock matches {item}"))?;
+
+                let current = folder.metadata.folder_options();
+                folder.metadata.set_folder_options(&FolderOptions {
+                    display: display.or(current.display),
+                    view: view.or(current.view),
+                    arrangement: sort.or(current.arrangement),
+                });
+                write(&live, &desired, dry_run, no_restart, cli.color)
+            }
+        },
+        Command::Autohide {
+            switch,
+            live: true,
+            dry_run: false,
+            ..
+        } => switch_autohide_live(switch),
+        Command::Autohide {
+            switch,
+            dry_run,
+            no_restart,
+            ..
+        } => {
+            let live = Dock::load()?;
+            let mut desired = live.clone();
+            desired.switch_setting(Setting::Autohide, switch)?;
+            write(&live, &desired, dry_run, no_restart, cli.color)
+        }
+        Command::Settings { command } => match command {
+            SettingsCommand::Set {
+                setting,
+                value,
+                dry_run,
+                no_restart,
+            } => {
+                let live = Dock::load()?;
+                let mut desired = live.clone();
+                desired.set_setting(setting, &value)?;
+                write(&live, &desired, dry_run, no_restart, cli.color)
+            }
+            SettingsCommand::Switch {
+                setting,
+                switch,
+                dry_run,
+                no_restart,
+            } => {
+                let live = Dock::load()?;
+                let mut desired = live.clone();
+                desired.switch_setting(setting, switch)?;
+                write(&live, &desired, dry_run, no_restart, cli.color)
+            }
+            SettingsCommand::List => {
+                let settings = Dock::load()?.typed_settings();
+                let defaults = DockSettings::default();
+                let width = Setting::ALL
+                    .iter()
+                    .map(|s| s.key().len())
+                    .max()
+                    .unwrap_or(0);
+                println!("{:width$}  {:8}  default", "setting", "value");
+                for setting in Setting::ALL {
+                    println!(
+                        "{:width$}  {:8}  {}",
+                        setting.key(),
+                        settings.value(setting),
+                        defaults.value(setting)
+                    );
+                }
+                Ok(Outcome::Unchanged)
+            }
+            SettingsCommand::Show { json } => {
+                let settings = Dock::load()?.typed_settings();
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&settings)?);
+                } else {
+                    print!("{settings}");
+                }
+                Ok(Outcome::Unchanged)
+            }
+        },
+        Command::Spacer { command } => match command {
+            SpacerCommand::Add {
+                small,
+                flex,
+                after,
+                section,
+                dry_run,
+                no_restart,
+            } => {
+                let kind = match (small, flex) {
+                    (true, _) => DockItemKind::SmallSpacerTile,
+                    (_, true) => DockItemKind::FlexSpacerTile,
+                    _ => DockItemKind::SpacerTile,
+                };
+                let live = Dock::load()?;
+                let (position, anchored) = match after {
+                    Some(after) => {
+                        let anchored = live
+                            .locate(&ItemRef::Query(after.clone()))
+                            .map(|(section, _)| section);
+                        (Position::After(after), anchored)
+                    }
+                    None => (Position::End, None),
+                };
+                let section = section.or(anchored).unwrap_or(Section::Apps);
+
+                let mut desired = live.clone();
+                desired.add_at(
+                    section,
+                    DockItem::spacer(kind),
+                    &position,
+                    AddPolicy::AllowDuplicate,
+                )?;
+                write(&live, &desired, dry_run, no_restart, cli.color)
+            }
+            SpacerCommand::Remove {
+                index,
+                section,
+                dry_run,
+                no_restart,
+            } => {
+                let live = Dock::load()?;
+                match live.section(section).get(index) {
+                    Some(item) if item.is_spacer() => {}
+                    Some(item) => bail!(
+                        "{} {index} is {}, not a spacer",
+                        section.name(),
+                        item.label()
+                    ),
+                    None => bail!("{} has no item {index}", section.name()),
+                }
+
+                let mut desired = live.clone();
+                let items = match section {
+                    Section::Apps => &mut desired.applications,
+                    Section::Others => &mut desired.others,
+                };
+                if let Some(items) = items {
+                    items.remove(index);
+                }
+                write(&live, &desired, dry_run, no_restart, cli.color)
+            }
+        },
+    }
+}
+
+/// How a command that succeeded turned out, reported through its exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    /// Nothing changed, or the command doesn't change anything.
+    Unchanged,
+
+    /// The Dock changed, or would change with `--dry-run`.
+    Changed,
+
+    /// The live Dock no longer matches the profile.
+    Drift,
+}
+
+impl Outcome {
+    fn from_diff(diff: &DockDiff) -> Self {
+        if diff.is_empty() {
+            Outcome::Unchanged
+        } else {
+            Outcome::Changed
+        }
+    }
+
+    fn exit_code(self) -> ExitCode {
+        ExitCode::from(match self {
+            Outcome::Unchanged => exit::OK,
+            Outcome::Changed => exit::CHANGED,
+            Outcome::Drift => exit::DRIFT,
+        })
+    }
+}
+
+/// The exit codes docktor promises to scripts; see `AFTER_HELP`.
+mod exit {
+    pub const OK: u8 = 0;
+    pub const ERROR: u8 = 1;
+    pub const CHANGED: u8 = 2;
+    pub const DRIFT: u8 = 3;
+    pub const INVALID: u8 = 4;
+    pub const PERMISSION: u8 = 5;
+}
+
+const AFTER_HELP: &str = "\
+Exit codes:
+  0  Success; nothing changed
+  1  Any other error
+  2  The Dock changed (or would, with --dry-run or diff)
+  3  The live Dock has drifted from the profile (check, compliance)
+  4  A profile or config file is invalid, or an edit was aborted
+  5  Permission denied reading or writing a file
+
+Environment:
+  DOCKTOR_PLIST_PATH          Dock plist to read and write
+  DOCKTOR_STATE_DIR           Where snapshots and other state are kept
+  DOCKTOR_NO_RESTART          Set to 1 to never restart the Dock after writing
+  DOCKTOR_BACKEND             file or defaults: how the Dock is written
+  DOCKTOR_INSTALL_PREFERENCE  :-separated app folders, preferred in order when an
+                              app is installed in more than one
+
+Defaults can also be set in ~/.config/docktor/config.toml; flags override the
+environment, which overrides the config file.";
+
+/// A profile or edit that failed validation.
+#[derive(Debug)]
+struct Invalid(String);
+
+impl std::fmt::Display for Invalid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Invalid {}
+
+/// Picks the exit code for a failed command from the errors in its chain.
+fn error_exit_code(err: &anyhow::Error) -> ExitCode {
+    let code = if err.chain().any(|cause| {
+        cause.is::<Invalid>() || cause.is::<serde_yaml::Error>() || cause.is::<toml::de::Error>()
+    }) {
+        exit::INVALID
+    } else if err.chain().any(|cause| {
+        cause
+            .downcast_ref::<std::io::Error>()
+            .is_some_and(|io| io.kind() == std::io::ErrorKind::PermissionDenied)
+    }) {
+        exit::PERMISSION
+    } else {
+        exit::ERROR
+    };
+    ExitCode::from(code)
+}
+
+/// Sets up the tracing subscriber. `RUST_LOG`, when set, overrides `-v`/`-q`.
+fn init_logging(cli: &Cli) {
+    let level = match (cli.quiet, cli.verbose) {
+        (true, _) => "error",
+        (false, 0) => "warn",
+        (false, 1) => "info",
+        (false, 2) => "debug",
+        (false, _) => "trace",
+    };
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(format!("docktor={level}")));
+
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_ansi(cli.color.enabled())
+        .with_writer(std::io::stderr);
+    match cli.log_format {
+        LogFormat::Text => builder.without_time().init(),
+        LogFormat::Json => builder.json().init(),
+    }
+}
+
+/// Applies `profile` to the live Dock and prints what changed.
+fn apply(
+    profile: &Profile,
+    dry_run: bool,
+    no_restart: bool,
+    color: ColorChoice,
+) -> Result<DockDiff> {
+    let options = ApplyOptions {
+        dry_run,
+        no_restart,
+        ..ApplyOptions::default()
+    };
+    let diff = profile.apply(options)?;
+    print!("{}", diff.render(color));
+    if dry_run {
+        print!("{}", SideEffects::of(&diff, options, &profile.hooks)?);
+    }
+    Ok(diff)
+}
+
+/// Expands `-` and `--from-file` into the full list of items to add.
+///
+/// Lines from stdin or a file are trimmed; blank lines and `#` comments are skipped.
+fn read_entries(items: &[String], from_file: Option<&Path>) -> Result<Vec<String>> {
+    let lines = |text: String| -> Vec<String> {
+        text.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(String::from)
+            .collect()
+    };
+
+    let mut entries = Vec::new();
+    for item in items {
+        if item == "-" {
+            let text = std::io::read_to_string(std::io::stdin()).context("Failed to read stdin")?;
+            entries.extend(lines(text));
+        } else {
+            entries.push(item.clone());
+        }
+    }
+    if let Some(path) = from_file {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        entries.extend(lines(text));
+    }
+    Ok(entries)
+}
+
+/// Adds `entries` (paths or bundle identifiers) to `dock` at `position`, keeping
+/// them in the order given.
+fn add(
+    dock: &mut Dock,
+    entries: &[String],
+    position: &Position,
+    policy: AddPolicy,
+    label: Option<&str>,
+    strict: bool,
+) -> Result<()> {
+    // After the first item lands, later ones in the same section follow it.
+    let mut next = [None, None];
+    for entry in entries {
+        let app = if Path::new(entry).extension().is_some_and(|ext| ext == "app") {
+            Some(MacApp::from_path(entry)?)
+        } else if entry.contains('/') {
+            None
+        } else {
+            Some(MacApp::find(entry)?)
+        };
+        if let Some(app) = &app {
+            for risk in app.install_risks() {
+                if strict {
+                    bail!("Not adding {}: {risk}", app.display_name);
+                }
+                tracing::warn!("{} may not stay in the Dock: {risk}", app.display_name);
+            }
+        }
+        let (section, item) = match &app {
+            Some(app) => (Section::Apps, DockItem::new(app)),
+            None => DockItem::for_path(entry)?,
+        };
+        let item = match label {
+            Some(label) => item.with_label(label),
+            None => item,
+        };
+        let slot = &mut next[section as usize];
+        let at = slot.map_or_else(|| position.clone(), Position::Index);
+        if let Some(index) = dock.add_at(section, item, &at, policy)? {
+            *slot = Some(index + 1);
+        }
+    }
+    Ok(())
+}
+
+/// Prints each problem found in `profile`, failing if there are any.
+///
+/// Items on unmounted volumes are only warned about, since they may be fine once
+/// the volume is back.
+fn report_problems(profile: &Path, problems: &[validate::Problem]) -> Result<()> {
+    let (unmounted, problems): (Vec<_>, Vec<_>) =
+        problems.iter().partition(|problem| problem.is_unmounted());
+    for problem in unmounted {
+        println!("{}: warning: {problem}", profile.display());
+    }
+    for problem in &problems {
+        println!("{}: {problem}", profile.display());
+    }
+    if !problems.is_empty() {
+        return Err(Invalid(format!(
+            "Found {} problem{} in {}",
+            problems.len(),
+            if problems.len() == 1 { "" } else { "s" },
+            profile.display()
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+/// The sections `--apps-only` and `--others-only` narrow a query to.
+fn sections(apps_only: bool, others_only: bool) -> &'static [Section] {
+    match (apps_only, others_only) {
+        (true, _) => &[Section::Apps],
+        (_, true) => &[Section::Others],
+        _ => &Section::ALL,
+    }
+}
+
+/// Finds the one home folder other than `home` that Dock items refer to, for `migrate-paths`.
+fn other_home(dock: &Dock, home: &Path) -> Result<PathBuf> {
+    let mut homes: Vec<PathBuf> = Section::ALL
+        .into_iter()
+        .flat_map(|section| dock.section(section))
+        .filter_map(DockItem::path)
+        .filter_map(|path| {
+            let user = path.strip_prefix("/Users").ok()?.components().next()?;
+            Some(Path::new("/Users").join(user))
+        })
+        .filter(|prefix| prefix != home && !prefix.ends_with("Shared"))
+        .collect();
+    homes.sort();
+    homes.dedup();
+    match homes.as_slice() {
+        [] => bail!("No Dock item refers to another home folder; pass --from"),
+        [only] => Ok(only.clone()),
+        _ => {
+            let homes: Vec<String> = homes.iter().map(|h| h.display().to_string()).collect();
+            bail!(
+                "Dock items refer to several home folders ({}); pass --from",
+                homes.join(", ")
+            )
+        }
+    }
+}
+
+/// Shows a [`Step`] on stderr, overwriting the previous one.
+fn status_line(step: Step<'_>) {
+    let line = match step {
+        Step::Scanned { done, total, path } => {
+            format!("Scanning {done}/{total}: {}", path.display())
+        }
+        Step::Resolving { done, total, entry } => format!("Resolving {done}/{total}: {entry}"),
+        Step::Writing => "Writing the Dock…".to_string(),
+        Step::Restarting => "Restarting the Dock…".to_string(),
+    };
+    eprint!("\r\x1b[K{line}");
+}
+
+/// Compiles CLI patterns as globs, or as regexes with `--regex`.
+fn matchers(patterns: &[String], regex: bool) -> Result<Vec<Matcher>> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            if regex {
+                Matcher::regex(pattern)
+            } else {
+                Matcher::glob(pattern)
+            }
+        })
+        .collect()
+}
+
+/// Runs the `docktor edit` loop: export, edit, validate, confirm, apply.
+fn edit(no_restart: bool, color: ColorChoice) -> Result<Outcome> {
+    let original = Profile::from_dock(&Dock::load()?).to_yaml()?;
+    let path = std::env::temp_dir().join(format!("docktor-edit-{}.yaml", std::process::id()));
+    std::fs::write(&path, &original)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    let result = edit_file(&path, &original, no_restart, color);
+    let _ = std::fs::remove_file(&path);
+    result
+}
+
+fn edit_file(path: &Path, original: &str, no_restart: bool, color: ColorChoice) -> Result<Outcome> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+
+    loop {
+        // Through the shell, so EDITOR may carry arguments such as `code --wait`.
+        let status = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(format!("{editor} \"$1\""))
+            .arg("sh")
+            .arg(path)
+            .status()
+            .with_context(|| format!("Failed to run {editor}"))?;
+        if !status.success() {
+            bail!("{editor} exited with {status}; nothing was changed");
+        }
+
+        let edited = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        if edited == original {
+            println!("No changes.");
+            return Ok(Outcome::Unchanged);
+        }
+
+        let problems = validate::validate_file(path)?;
+        if !problems.is_empty() {
+            for problem in &problems {
+                eprintln!("{problem}");
+            }
+            if confirm("The profile has problems. Edit it again?")? {
+                continue;
+            }
+            return Err(Invalid("Aborted; nothing was changed".to_string()).into());
+        }
+
+        let profile = Profile::load(path)?;
+        let diff = apply(&profile, true, no_restart, color)?;
+        if diff.is_empty() {
+            return Ok(Outcome::Unchanged);
+        }
+        if confirm("Apply these changes?")? {
+            let diff = profile.apply(ApplyOptions {
+                dry_run: false,
+                no_restart,
+                ..ApplyOptions::default()
+            })?;
+            println!("Applied.");
+            return Ok(Outcome::from_diff(&diff));
+        }
+        println!("Nothing was changed.");
+        return Ok(Outcome::Unchanged);
+    }
+}
+
+/// Runs the `docktor setup` wizard: pick apps, order them, choose settings, save a
+/// profile, then confirm and apply it.
+fn setup(no_restart: bool, color: ColorChoice, quiet: bool) -> Result<Outcome> {
+    let live = Dock::load()?;
+    let apps = if !quiet && std::io::stderr().is_terminal() {
+        let apps = MacApp::discover_all_with(&status_line, &CancellationToken::new());
+        eprint!("\r\x1b[K");
+        apps?
+    } else {
+        MacApp::discover_all()
+    };
+    if apps.is_empty() {
+        bail!("No applications were found to choose from");
+    }
+
+    let pinned: Vec<&str> = live
+        .section(Section::Apps)
+        .iter()
+        .filter_map(|item| item.metadata.bundle_id.as_deref())
+        .collect();
+    for (number, app) in apps.iter().enumerate() {
+        let mark = if pinned.contains(&app.bundle_id.as_str()) {
+            '*'
+        } else {
+            ' '
+        };
+        println!("{:>4} {mark} {}", number + 1, app.display_name);
+    }
+    println!("(* is in the Dock now)");
+
+    // `None` is a spacer.
+    let chosen: Vec<Option<&MacApp>> = loop {
+        let answer = ask(
+            "Apps to pin, in order, e.g. 3,1,5-7 (s adds a spacer; Enter keeps the current apps):",
+        )?;
+        if answer.is_empty() {
+            break live
+                .section(Section::Apps)
+                .iter()
+                .filter_map(|item| match item.metadata.bundle_id.as_deref() {
+                    _ if item.is_spacer() => Some(None),
+                    Some(id) => apps.iter().find(|app| app.bundle_id == id).map(Some),
+                    None => None,
+                })
+                .collect();
+        }
+        match parse_selection(&answer, apps.len()) {
+            Ok(selection) => break selection.into_iter().map(|n| n.map(|n| &apps[n])).collect(),
+            Err(err) => eprintln!("{err:#}"),
+        }
+    };
+
+    let mut profile = Profile::from_dock(&live);
+    let mut chosen = chosen;
+    match ask("Order: [k]eep as chosen, sort by [n]ame, or group by [c]ategory? [k]")?.as_str() {
+        "n" | "name" => {
+            for group in chosen.split_mut(Option::is_none) {
+                group.sort_by_key(|app| app.map(|app| app.display_name.to_lowercase()));
+            }
+        }
+        "c" | "category" => {
+            chosen.retain(Option::is_some);
+            profile.group_by_category = Some(CategoryGrouping {
+                spacer: "small-spacer".to_string(),
+                order: Vec::new(),
+            });
+        }
+        _ => {}
+    }
+    let home = docktor::paths::home_dir();
+    profile.apps = Some(
+        chosen
+            .iter()
+            .map(|app| match app {
+                Some(app) => match home
+                    .as_ref()
+                    .and_then(|home| app.path.strip_prefix(home).ok())
+                {
+                    Some(rest) => Entry::Path(format!("~/{}", rest.display())),
+                    None => Entry::Path(app.path.display().to_string()),
+                },
+                None => Entry::Path("small-spacer".to_string()),
+            })
+            .collect(),
+    );
+
+    profile.settings.insert(
+        Setting::Autohide.key().to_string(),
+        confirm("Hide the Dock automatically?")?.into(),
+    );
+    if Feature::ShowRecents.is_supported() {
+        profile.settings.insert(
+            "show-recents".to_string(),
+            confirm("Show recent apps in the Dock?")?.into(),
+        );
+    }
+    loop {
+        let answer = ask(
+            "Icon size: small, medium, large, or 16-128 pixels (Enter keeps the current size):",
+        )?;
+        if answer.is_empty() {
+            break;
+        }
+        match Setting::Tilesize.parse(&answer) {
+            Ok(size) => {
+                profile
+                    .settings
+                    .insert(Setting::Tilesize.key().to_string(), size);
+                break;
+            }
+            Err(err) => eprintln!("{err:#}"),
+        }
+    }
+
+    let path = match ask("Save the profile to [dock.yaml]:")?.as_str() {
+        "" => PathBuf::from("dock.yaml"),
+        answer => PathBuf::from(answer),
+    };
+    if path.exists() && !confirm(&format!("{} already exists. Overwrite it?", path.display()))? {
+        return Err(Invalid("Aborted; nothing was changed".to_string()).into());
+    }
+    std::fs::write(&path, profile.to_yaml()?)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    println!("Wrote {}", path.display());
+
+    let profile = Profile::load(&path)?;
+    let diff = apply(&profile, true, no_restart, color)?;
+    if diff.is_empty() {
+        return Ok(Outcome::Unchanged);
+    }
+    if confirm("Apply these changes?")? {
+        let diff = profile.apply(ApplyOptions {
+            dry_run: false,
+            no_restart,
+            ..ApplyOptions::default()
+        })?;
+        println!("Applied.");
+        return Ok(Outcome::from_diff(&diff));
+    }
+    println!("Nothing was changed; apply the profile later with `docktor apply`.");
+    Ok(Outcome::Unchanged)
+}
+
+/// Parses a `setup` selection such as `3,1,5-7,s` into zero-based indices below
+/// `count`, with `None` for each spacer.
+fn parse_selection(answer: &str, count: usize) -> Result<Vec<Option<usize>>> {
+    let number = |text: &str| -> Result<usize> {
+        match text.trim().parse::<usize>() {
+            Ok(n @ 1..) if n <= count => Ok(n - 1),
+            _ => bail!("{:?} isn't a number from 1 to {count}", text.trim()),
+        }
+    };
+    let mut selection = Vec::new();
+    for token in answer
+        .split([',', ' '])
+        .filter(|token| !token.trim().is_empty())
+    {
+        match token.trim().split_once('-') {
+            _ if token.trim() == "s" => selection.push(None),
+            Some((first, last)) => {
+                let (first, last) = (number(first)?, number(last)?);
+                if first > last {
+                    bail!("{token:?} counts backwards");
+                }
+                selection.extend((first..=last).map(Some));
+            }
+            None => selection.push(Some(number(token)?)),
+        }
+    }
+    Ok(selection)
+}
+
+/// Asks a question on stderr and returns the trimmed answer, which is empty if the
+/// user just pressed Enter.
+fn ask(question: &str) -> Result<String> {
+    eprint!("{question} ");
+    let mut answer = String::new();
+    std::io::stdin()
+        .read_line(&mut answer)
+        .context("Failed to read answer")?;
+    Ok(answer.trim().to_string())
+}
+
+/// Offers each change in `diff` in turn and replays the accepted ones on the live
+/// Dock, for `snapshot diff --interactive`.
+fn cherry_pick(
+    diff: &DockDiff,
+    dry_run: bool,
+    no_restart: bool,
+    color: ColorChoice,
+) -> Result<Outcome> {
+    if diff.is_empty() {
+        print!("{}", diff.render(color));
+        return Ok(Outcome::Unchanged);
+    }
+
+    let mut picked = DockDiff::default();
+    let total = diff.changes.len();
+    for (n, change) in diff.changes.iter().enumerate() {
+        let single = DockDiff {
+            changes: vec![change.clone()],
+        };
+        print!("[{}/{total}] {}", n + 1, single.render(color));
+        if confirm("Apply this change?")? {
+            picked.changes.push(change.clone());
+        }
+    }
+    if picked.is_empty() {
+        println!("No changes picked; nothing was changed.");
+        return Ok(Outcome::Unchanged);
+    }
+
+    let live = Dock::load()?;
+    write(&live, &picked.replay(&live), dry_run, no_restart, color)
+}
+
+/// Asks a yes/no question on stderr, defaulting to no.
+fn confirm(question: &str) -> Result<bool> {
+    eprint!("{question} [y/N] ");
+    let mut answer = String::new();
+    std::io::stdin()
+        .read_line(&mut answer)
+        .context("Failed to read answer")?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+/// Applies a profile to every local user's Dock for `apply --all-users`, printing
+/// each user's changes and carrying on past users that fail.
+///
+/// Users who have never logged in are skipped rather than failing.
+fn apply_all_users(
+    profile: &Profile,
+    options: ApplyOptions,
+    color: ColorChoice,
+) -> Result<Outcome> {
+    if !users::is_root() {
+        bail!("Applying to every user needs root; run it again with sudo");
+    }
+
+    let users = users::local_users()?;
+    let (mut changed, mut failed) = (false, 0);
+    for user in &users {
+        println!("{}:", user.name);
+        if !user.dock_path().exists() {
+            println!("  Skipped: hasn't logged in yet, so there is no Dock to change.");
+            continue;
+        }
+        match profile.apply_for(user, options) {
+            Ok(diff) => {
+                changed |= !diff.is_empty();
+                print!("{}", diff.render(color));
+            }
+            Err(err) => {
+                failed += 1;
+                eprintln!("Error: {err:#}");
+            }
+        }
+    }
+    if failed > 0 {
+        bail!(
+            "Applying the profile failed for {failed} of {} users",
+            users.len()
+        );
+    }
+    Ok(if changed {
+        Outcome::Changed
+    } else {
+        Outcome::Unchanged
+    })
+}
+
+/// Sets or clears Dock locks for `lock` and `unlock`, or prints the equivalent payload.
+fn set_locks(
+    what: &[Lock],
+    all: bool,
+    locked: bool,
+    payload: bool,
+    dry_run: bool,
+    no_restart: bool,
+    color: ColorChoice,
+) -> Result<Outcome> {
+    let locks = if all { &Lock::ALL[..] } else { what };
+    if payload {
+        print!("{}", lock::payload(locks, locked)?);
+        return Ok(Outcome::Unchanged);
+    }
+
+    let live = Dock::load()?;
+    let mut desired = live.clone();
+    for lock in locks {
+        desired.set_locked(*lock, locked);
+    }
+    write(&live, &desired, dry_run, no_restart, color)
+}
+
+/// Writes `desired` over the live Dock and prints what changed.
+fn write(
+    live: &Dock,
+    desired: &Dock,
+    dry_run: bool,
+    no_restart: bool,
+    color: ColorChoice,
+) -> Result<Outcome> {
+    let options = ApplyOptions {
+        dry_run,
+        no_restart,
+        ..ApplyOptions::default()
+    };
+    let diff = transaction::write_changes(live, desired, options)?;
+    print!("{}", diff.render(color));
+    if dry_run {
+        print!("{}", SideEffects::of(&diff, options, &Hooks::default())?);
+    }
+    Ok(Outcome::from_diff(&diff))
+}
+
+/// Turns autohide on, off, or the other way round through System Events, if that
+/// changes anything.
+fn switch_autohide_live(switch: Switch) -> Result<Outcome> {
+    let current = visibility::is_hidden()?;
+    let hidden = switch.apply(current);
+    if hidden == current {
+        eprintln!("Autohide is already {}.", if hidden { "on" } else { "off" });
+        return Ok(Outcome::Unchanged);
+    }
+    visibility::set_hidden(hidden)?;
+    println!("Autohide is now {}.", if hidden { "on" } else { "off" });
+    Ok(Outcome::Changed)
+}
+
+/// One side of a comparison, before profiles are resolved against a base Dock.
+enum Side {
+    Dock(Dock),
+    Profile(Box<Profile>),
+}
+
+impl Side {
+    /// Resolves a CLI argument to a Dock or profile.
+    fn resolve(spec: &str) -> Result<Self> {
+        let path = Path::new(spec);
+        if spec == "live" {
+            Ok(Side::Dock(Dock::load()?))
+        } else if spec == "saved" {
+            Ok(Side::Dock(state::last_saved()?.context(
+                "docktor hasn't written the Dock yet, so there is no saved state",
+            )?))
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext == "yaml" || ext == "yml")
+        {
+            Ok(Side::Profile(Box::new(Profile::load(path)?)))
+        } else if path.is_file() {
+            Ok(Side::Dock(Dock::load_from(path)?))
+        } else {
+            Ok(Side::Dock(Snapshot::find(spec)?.load()?))
+        }
+    }
+
+    /// Produces a concrete Dock, applying a profile on top of `base` if needed.
+    fn into_dock(self, base: &Dock) -> Result<Dock> {
+        match self {
+            Side::Dock(dock) => Ok(dock),
+            Side::Profile(profile) => profile.apply_to(base),
+        }
+    }
+}
+
+fn diff(
+    from: Option<&str>,
+    to: Option<&str>,
+    format: DiffFormat,
+    color: ColorChoice,
+) -> Result<Outcome> {
+    let from = match from {
+        Some(spec) => Side::resolve(spec)?,
+        None => Side::Dock(
+            Snapshot::latest()?
+                .context("No snapshots saved yet; run `docktor snapshot save` first")?
+                .load()?,
+        ),
+    };
+    let to = Side::resolve(to.unwrap_or("live"))?;
+
+    // A profile only describes part of the Dock, so it is applied on top of the
+    // other side (or the live Dock when both sides are profiles).
+    let (old, new) = match (from, to) {
+        (Side::Dock(old), to) => {
+            let new = to.into_dock(&old)?;
+            (old, new)
+        }
+        (from, Side::Dock(new)) => (from.into_dock(&new)?, new),
+        (from, to) => {
+            let live = Dock::load()?;
+            (from.into_dock(&live)?, to.into_dock(&live)?)
+        }
+    };
+
+    let diff = DockDiff::between(&old, &new);
+    match format {
+        DiffFormat::Text => print!("{}", diff.render(color)),
+        DiffFormat::JsonPatch => println!("{:#}", diff.to_json_patch()),
+    }
+    // Comparing never writes anything, so differences count as changes pending.
+    Ok(Outcome::from_diff(&diff))
 }
@@ -0,0 +1,60 @@
+//! Three-way merges between the Dock as last applied, the live Dock, and a profile.
+//!
+//! A plain apply makes the Dock match the profile, putting back anything the user
+//! removed since. A merge instead takes the Dock as the last apply left it as the
+//! common base: only what the profile changes relative to that base is replayed on
+//! the live Dock, so the user's own additions, removals, and moves survive.
+
+use crate::cancel::CancellationToken;
+use crate::diff::DockDiff;
+use crate::dock::Dock;
+use crate::profile::{Profile, UnmountedPolicy};
+use crate::progress::Progress;
+use crate::state;
+use anyhow::Result;
+
+/// How applying a profile treats changes the user made to the Dock since the last apply.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Strategy {
+    /// Make the Dock match the profile exactly, undoing the user's changes.
+    #[default]
+    Replace,
+
+    /// Apply only what the profile changes relative to the last apply.
+    Merge,
+}
+
+/// Replays the changes from `base` to `ours` on `theirs`.
+///
+/// Items `theirs` added or removed relative to `base` stay that way unless `ours`
+/// changed the same item too, in which case `ours` wins.
+pub fn three_way(base: &Dock, ours: &Dock, theirs: &Dock) -> Dock {
+    DockDiff::between(base, ours).replay(theirs)
+}
+
+impl Profile {
+    /// Returns the Dock that results from merging this profile into `live`.
+    ///
+    /// The base is the Dock as the last profile applied left it. Without one there is
+    /// nothing to tell the user's changes apart by, so this falls back to
+    /// [`Profile::apply_to_with`].
+    pub fn merge_into(&self, live: &Dock, unmounted: UnmountedPolicy) -> Result<Dock> {
+        self.merge_into_with(live, unmounted, &(), &CancellationToken::new())
+    }
+
+    pub(crate) fn merge_into_with(
+        &self,
+        live: &Dock,
+        unmounted: UnmountedPolicy,
+        progress: &dyn Progress,
+        cancel: &CancellationToken,
+    ) -> Result<Dock> {
+        let Some(base) = state::last_applied()? else {
+            tracing::info!("no applied Dock to merge against; applying the whole profile");
+            return self.build(live, unmounted, progress, cancel);
+        };
+        let ours = self.build(&base, unmounted, progress, cancel)?;
+        Ok(three_way(&base, &ours, live))
+    }
+}
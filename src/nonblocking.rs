@@ -0,0 +1,94 @@
+//! Async versions of the calls that block on the disk, `ssh`, `git`, `launchctl`, or
+//! the Dock itself.
+//!
+//! Each call runs its blocking counterpart on tokio's blocking thread pool through
+//! [`tokio::task::spawn_blocking`], so it must be awaited inside a tokio runtime.
+//! docktor has no file watcher or daemon of its own; its only background service is
+//! the [launch agent](crate::agent), whose installation is covered here.
+
+use crate::agent::{self, TimeOfDay};
+use crate::diff::DockDiff;
+use crate::dock::Dock;
+use crate::profile::{ApplyOptions, Profile};
+#[cfg(feature = "network")]
+use crate::remote::{self, RemoteDock};
+#[cfg(feature = "network")]
+use crate::sync::{PushOutcome, SyncRepo};
+use anyhow::{Result, anyhow};
+use std::path::PathBuf;
+
+/// Runs `f` on tokio's blocking thread pool and waits for its result.
+///
+/// A panic in `f` becomes an error rather than unwinding into the caller's task.
+pub async fn spawn<T, F>(f: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|err| anyhow!("A docktor background task failed: {err}"))?
+}
+
+impl Dock {
+    /// Async [`Dock::load`].
+    pub async fn load_async() -> Result<Dock> {
+        spawn(Dock::load).await
+    }
+
+    /// Async [`Dock::load_from`].
+    pub async fn load_from_async(path: impl Into<PathBuf>) -> Result<Dock> {
+        let path = path.into();
+        spawn(move || Dock::load_from(path)).await
+    }
+
+    /// Async [`Dock::save`], handing the Dock back once it is written.
+    pub async fn save_async(self) -> Result<Dock> {
+        spawn(move || self.save().map(|()| self)).await
+    }
+}
+
+impl Profile {
+    /// Async [`Profile::load`].
+    pub async fn load_async(path: impl Into<PathBuf>) -> Result<Profile> {
+        let path = path.into();
+        spawn(move || Profile::load(path)).await
+    }
+
+    /// Async [`Profile::apply`].
+    pub async fn apply_async(self, options: ApplyOptions) -> Result<DockDiff> {
+        spawn(move || self.apply(options)).await
+    }
+}
+
+#[cfg(feature = "network")]
+impl SyncRepo {
+    /// Async [`SyncRepo::push`].
+    pub async fn push_async(&self, live: Dock, force: bool) -> Result<PushOutcome> {
+        let repo = self.clone();
+        spawn(move || repo.push(&live, force)).await
+    }
+
+    /// Async [`SyncRepo::pull`], fetching the shared profile.
+    pub async fn pull_async(&self, live: Dock, force: bool) -> Result<Profile> {
+        let repo = self.clone();
+        spawn(move || repo.pull(&live, force)).await
+    }
+}
+
+/// Async [`remote::fetch`].
+#[cfg(feature = "network")]
+pub async fn fetch(destination: impl Into<String>) -> Result<RemoteDock> {
+    let destination = destination.into();
+    spawn(move || remote::fetch(&destination)).await
+}
+
+/// Async [`agent::install`].
+pub async fn install_agent(at: TimeOfDay) -> Result<PathBuf> {
+    spawn(move || agent::install(at)).await
+}
+
+/// Async [`agent::uninstall`].
+pub async fn uninstall_agent() -> Result<bool> {
+    spawn(agent::uninstall).await
+}
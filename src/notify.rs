@@ -0,0 +1,16 @@
+use crate::macos::{self, applescript_string};
+use anyhow::Result;
+
+/// Posts a macOS user notification from docktor.
+///
+/// Goes through `osascript`, so it shows up under Script Editor in System Settings.
+#[tracing::instrument]
+pub fn post(title: &str, message: &str) -> Result<()> {
+    let script = format!(
+        "display notification {} with title {}",
+        applescript_string(message),
+        applescript_string(title)
+    );
+    macos::osascript(&script, "Posting a notification")?;
+    Ok(())
+}
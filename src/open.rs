@@ -0,0 +1,85 @@
+use crate::dock::{Dock, DockItem, ItemRef};
+use anyhow::{Context, Result, bail};
+use std::process::Command;
+
+impl Dock {
+    /// Returns the item `item` refers to, if there is one.
+    pub fn get(&self, item: &ItemRef) -> Option<&DockItem> {
+        let (section, index) = self.locate(item)?;
+        self.section(section).get(index)
+    }
+}
+
+impl DockItem {
+    /// Opens what the tile points to, as clicking it would.
+    ///
+    /// Apps whose bundle has moved are found by bundle identifier instead. Fails if
+    /// the tile points nowhere, or at a file that no longer exists.
+    #[tracing::instrument(skip_all, fields(label = self.label()))]
+    pub fn launch(&self) -> Result<()> {
+        let Some(location) = &self.metadata.location else {
+            bail!("{} doesn't point at anything", self.label());
+        };
+        let mut command = Command::new("open");
+        match location.path() {
+            Some(path) if path.exists() => command.arg(path),
+            Some(path) => match &self.metadata.bundle_id {
+                Some(bundle_id) => {
+                    tracing::warn!(path = %path.display(), "tile target is missing; opening by bundle identifier");
+                    command.args(["-b", bundle_id])
+                }
+                None => bail!(
+                    "{} points at {}, which doesn't exist",
+                    self.label(),
+                    path.display()
+                ),
+            },
+            None => command.arg(&location.url),
+        };
+        run(command, || format!("Failed to open {}", self.label()))
+    }
+}
+
+impl DockItem {
+    /// Selects the tile's file or folder in a Finder window.
+    ///
+    /// Fails for tiles that aren't backed by a local file, or whose file is gone;
+    /// the error says where the tile points either way.
+    #[tracing::instrument(skip_all, fields(label = self.label()))]
+    pub fn reveal(&self) -> Result<()> {
+        let Some(location) = &self.metadata.location else {
+            bail!("{} doesn't point at anything", self.label());
+        };
+        let Some(path) = location.path() else {
+            bail!(
+                "{} points at {}, which isn't a local file",
+                self.label(),
+                location.url
+            );
+        };
+        if !path.exists() {
+            bail!(
+                "{} points at {}, which doesn't exist",
+                self.label(),
+                path.display()
+            );
+        }
+        let mut command = Command::new("open");
+        command.arg("-R").arg(&path);
+        run(command, || format!("Failed to reveal {}", path.display()))
+    }
+}
+
+/// Runs `command`, failing with its stderr if it doesn't succeed.
+fn run(mut command: Command, context: impl Fn() -> String) -> Result<()> {
+    crate::macos::ensure("Opening Dock items")?;
+    let output = command.output().with_context(&context)?;
+    if !output.status.success() {
+        bail!(
+            "{}: {}",
+            context(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
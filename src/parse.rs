@@ -0,0 +1,213 @@
+//! Loading Dock plists that don't quite match what docktor expects.
+//!
+//! Plists carried over from old macOS versions, or edited by other tools, can hold
+//! tiles of unknown types, tiles missing their `tile-data`, or values of the wrong
+//! type. [`Dock::load_from`] fails outright on a malformed value; the modes here
+//! either keep going and report what they had to work around, or refuse anything
+//! docktor doesn't fully understand.
+
+use crate::dock::{
+    BUNDLE_ID_KEY, Dock, DockItem, DockItemKind, FILE_DATA_KEY, FILE_LABEL_KEY, Section,
+    TILE_DATA_KEY, TILE_TYPE_KEY, TileMetadata,
+};
+use anyhow::{Context, Result, bail};
+use std::fmt;
+use std::path::Path;
+
+/// How forgiving [`Dock::load_with`] is.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ParseMode {
+    /// Load whatever can be understood, collecting a warning for everything else.
+    ///
+    /// Values of the wrong type are kept as-is so they are written back unchanged;
+    /// only entries that aren't tiles at all are dropped.
+    #[default]
+    Lenient,
+
+    /// Fail on unknown tile types, missing tile data, and malformed values.
+    Strict,
+}
+
+/// Something [`ParseMode::Lenient`] had to work around.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseWarning {
+    /// Where the problem is, as a JSON Pointer, e.g. `/persistent-apps/3/tile-data`.
+    pub pointer: String,
+
+    /// What was wrong and what was done about it.
+    pub message: String,
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.pointer, self.message)
+    }
+}
+
+impl Dock {
+    /// Loads the user's Dock plist in the given mode, returning any warnings.
+    pub fn load_with(mode: ParseMode) -> Result<(Self, Vec<ParseWarning>)> {
+        Self::load_from_with(Self::default_path()?, mode)
+    }
+
+    /// Loads a Dock plist from `path` in the given mode, returning any warnings.
+    ///
+    /// In [`ParseMode::Strict`] the warnings are always empty; the first problem is an error.
+    pub fn load_from_with<P: AsRef<Path>>(
+        path: P,
+        mode: ParseMode,
+    ) -> Result<(Self, Vec<ParseWarning>)> {
+        let path = path.as_ref();
+        let value = plist::Value::from_file(path)
+            .with_context(|| format!("Failed to parse Dock plist at {}", path.display()))?;
+        let Some(dict) = value.into_dictionary() else {
+            bail!("The Dock plist at {} is not a dictionary", path.display());
+        };
+        let (dock, warnings) = parse(dict, mode)
+            .with_context(|| format!("Failed to load Dock plist at {}", path.display()))?;
+        tracing::debug!(
+            warnings = warnings.len(),
+            "loaded Dock with {mode:?} parsing"
+        );
+        Ok((dock, warnings))
+    }
+}
+
+fn parse(mut dict: plist::Dictionary, mode: ParseMode) -> Result<(Dock, Vec<ParseWarning>)> {
+    let mut parser = Parser {
+        mode,
+        warnings: Vec::new(),
+    };
+    let mut sections = [None, None];
+    for (slot, section) in sections.iter_mut().zip(Section::ALL) {
+        let pointer = format!("/{}", section.key());
+        match dict.remove(section.key()) {
+            Some(plist::Value::Array(entries)) => {
+                let mut items = Vec::new();
+                for (index, entry) in entries.into_iter().enumerate() {
+                    items.extend(parser.item(format!("{pointer}/{index}"), entry)?);
+                }
+                *slot = Some(items);
+            }
+            Some(other) => {
+                parser.problem(&pointer, "is not an array", "left as it is")?;
+                dict.insert(section.key().to_string(), other);
+            }
+            None => {}
+        }
+    }
+    let [applications, others] = sections;
+
+    Ok((
+        Dock {
+            applications,
+            others,
+            extra: dict,
+            ..Dock::default()
+        },
+        parser.warnings,
+    ))
+}
+
+struct Parser {
+    mode: ParseMode,
+    warnings: Vec<ParseWarning>,
+}
+
+impl Parser {
+    /// Records a warning that `issue` was worked around with `action`, or fails in strict mode.
+    fn problem(&mut self, pointer: &str, issue: &str, action: &str) -> Result<()> {
+        if self.mode == ParseMode::Strict {
+            bail!("{pointer} {issue}");
+        }
+        self.warnings.push(ParseWarning {
+            pointer: pointer.to_string(),
+            message: format!("{issue}; {action}"),
+        });
+        Ok(())
+    }
+
+    fn item(&mut self, pointer: String, entry: plist::Value) -> Result<Option<DockItem>> {
+        let Some(mut dict) = entry.into_dictionary() else {
+            self.problem(&pointer, "is not a tile", "dropped")?;
+            return Ok(None);
+        };
+
+        let kind = match dict.get(TILE_TYPE_KEY) {
+            Some(plist::Value::String(name)) => {
+                let kind = plist::from_value(&plist::Value::String(name.clone()))
+                    .unwrap_or(DockItemKind::Unknown);
+                if kind == DockItemKind::Unknown {
+                    self.problem(
+                        &pointer,
+                        &format!("has unknown tile type {name:?}"),
+                        "kept as it is",
+                    )?;
+                }
+                kind
+            }
+            Some(_) => {
+                self.problem(
+                    &pointer,
+                    "has a tile-type that isn't a string",
+                    "kept as it is",
+                )?;
+                DockItemKind::Unknown
+            }
+            None => {
+                self.problem(&pointer, "has no tile-type", "kept as it is")?;
+                DockItemKind::Unknown
+            }
+        };
+        if kind != DockItemKind::Unknown {
+            dict.remove(TILE_TYPE_KEY);
+        }
+
+        let tile_pointer = format!("{pointer}/{TILE_DATA_KEY}");
+        let metadata = match dict.remove(TILE_DATA_KEY) {
+            Some(plist::Value::Dictionary(tile_data)) => self.metadata(&tile_pointer, tile_data)?,
+            Some(_) => {
+                self.problem(&tile_pointer, "is not a dictionary", "dropped")?;
+                TileMetadata::default()
+            }
+            None => TileMetadata::default(),
+        };
+        let on_disk = matches!(kind, DockItemKind::FileTile | DockItemKind::DirectoryTile);
+        if on_disk && metadata.location.is_none() {
+            self.problem(&tile_pointer, "has no file-data", "the tile points nowhere")?;
+        }
+
+        Ok(Some(DockItem {
+            metadata,
+            kind,
+            extra: dict,
+        }))
+    }
+
+    /// Parses each known tile-data key on its own, keeping any that fail in `extra`.
+    fn metadata(&mut self, pointer: &str, mut dict: plist::Dictionary) -> Result<TileMetadata> {
+        let mut metadata = TileMetadata::default();
+        for key in [FILE_DATA_KEY, FILE_LABEL_KEY, BUNDLE_ID_KEY] {
+            let Some(value) = dict.remove(key) else {
+                continue;
+            };
+            let parsed = match key {
+                FILE_DATA_KEY => plist::from_value(&value).map(|v| metadata.location = Some(v)),
+                FILE_LABEL_KEY => {
+                    plist::from_value(&value).map(|v| metadata.display_name = Some(v))
+                }
+                _ => plist::from_value(&value).map(|v| metadata.bundle_id = Some(v)),
+            };
+            if let Err(err) = parsed {
+                self.problem(
+                    &format!("{pointer}/{key}"),
+                    &format!("is malformed ({err})"),
+                    "kept as it is",
+                )?;
+                dict.insert(key.to_string(), value);
+            }
+        }
+        metadata.extra = dict;
+        Ok(metadata)
+    }
+}
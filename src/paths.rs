@@ -0,0 +1,97 @@
+use std::path::{Path, PathBuf};
+
+/// Locations macOS exposes under a second path, mapped to the one users see.
+///
+/// Safari and other cryptex-delivered apps really live under `/System/Cryptexes`,
+/// and the data volume's firmlinks make `/System/Volumes/Data/Users` the same as
+/// `/Users`. Longer prefixes come first.
+const ALIASES: &[(&str, &str)] = &[
+    (
+        "/System/Volumes/Preboot/Cryptexes/App/System/Applications",
+        "/Applications",
+    ),
+    ("/System/Cryptexes/App/System/Applications", "/Applications"),
+    ("/System/Volumes/Data", "/"),
+];
+
+/// The user's home folder.
+///
+/// `None` when it can't be determined, or always when docktor is built without the
+/// `dirs` feature; then the plist and state folder have to be configured explicitly.
+pub fn home_dir() -> Option<PathBuf> {
+    #[cfg(feature = "dirs")]
+    return dirs::home_dir();
+    #[cfg(not(feature = "dirs"))]
+    None
+}
+
+/// The user's application support folder, e.g. `~/Library/Application Support`.
+///
+/// `None` in the same cases as [`home_dir`].
+pub fn data_dir() -> Option<PathBuf> {
+    #[cfg(feature = "dirs")]
+    return dirs::data_dir();
+    #[cfg(not(feature = "dirs"))]
+    None
+}
+
+/// Rewrites `path` to the user-visible form of the location it names.
+///
+/// Paths that don't go through a cryptex or firmlink are returned unchanged.
+pub fn canonical(path: &Path) -> PathBuf {
+    for (alias, visible) in ALIASES {
+        if let Ok(rest) = path.strip_prefix(alias) {
+            return Path::new(visible).join(rest);
+        }
+    }
+    path.to_path_buf()
+}
+
+/// Whether the volume a path lives on is available right now.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VolumeState {
+    /// On the startup disk.
+    Local,
+
+    /// On an external or network volume that is mounted at the given point.
+    Mounted(PathBuf),
+
+    /// On an external or network volume that isn't mounted, so its items can't be
+    /// checked until it is.
+    Unmounted(PathBuf),
+}
+
+impl VolumeState {
+    /// Looks at where `path` would be mounted: `/Volumes/<name>`, `/Network/...`, or `/net/<host>`.
+    pub fn of(path: &Path) -> Self {
+        let mut components = path.components().skip(1);
+        let mount_point = match components.next().and_then(|c| c.as_os_str().to_str()) {
+            Some("Volumes") => components
+                .next()
+                .map(|name| Path::new("/Volumes").join(name)),
+            Some("Network") => components
+                .next()
+                .zip(components.next())
+                .map(|(kind, host)| Path::new("/Network").join(kind).join(host)),
+            Some("net") => components.next().map(|host| Path::new("/net").join(host)),
+            _ => None,
+        };
+        let Some(mount_point) = mount_point else {
+            return VolumeState::Local;
+        };
+        // The startup disk appears in /Volumes as a symlink to /.
+        if mount_point.is_symlink() {
+            return VolumeState::Local;
+        }
+        if mount_point.is_dir() {
+            VolumeState::Mounted(mount_point)
+        } else {
+            VolumeState::Unmounted(mount_point)
+        }
+    }
+
+    /// Returns `true` when the path's volume isn't mounted.
+    pub fn is_unmounted(&self) -> bool {
+        matches!(self, VolumeState::Unmounted(_))
+    }
+}
@@ -0,0 +1,139 @@
+//! Drawing the Dock as a line of text, to eyeball a layout before applying it.
+//!
+//! Each tile is two columns wide: its initials, or an emoji for well-known apps and
+//! for folders, links, and files. Spacers are gaps, and a legend underneath spells
+//! out what each tile is.
+
+use crate::dock::{Dock, DockItem, DockItemKind, Section};
+
+/// How tiles are drawn.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum PreviewStyle {
+    /// Two letters from the tile's label.
+    #[default]
+    Initials,
+
+    /// An emoji where there is an obvious one, initials otherwise.
+    Emoji,
+}
+
+/// Emoji for apps that come with macOS, by bundle identifier.
+const APP_EMOJI: &[(&str, &str)] = &[
+    ("com.apple.Safari", "🧭"),
+    ("com.apple.mail", "📧"),
+    ("com.apple.Notes", "📝"),
+    ("com.apple.iCal", "📅"),
+    ("com.apple.reminders", "📋"),
+    ("com.apple.Music", "🎵"),
+    ("com.apple.Photos", "🌸"),
+    ("com.apple.MobileSMS", "💬"),
+    ("com.apple.FaceTime", "📹"),
+    ("com.apple.Maps", "🌍"),
+    ("com.apple.AppStore", "🛒"),
+    ("com.apple.Terminal", "💻"),
+    ("com.apple.finder", "😀"),
+    ("com.apple.systempreferences", "🔧"),
+    ("com.apple.AddressBook", "📒"),
+    ("com.apple.podcasts", "🎙"),
+    ("com.apple.TV", "📺"),
+    ("com.apple.news", "📰"),
+];
+
+/// Draws `dock` with `style`: a boxed line of tiles, apps then others, followed by a
+/// legend.
+pub fn render(dock: &Dock, style: PreviewStyle) -> String {
+    let section = |section| -> Vec<(String, usize)> {
+        dock.section(section)
+            .iter()
+            .map(|item| (tile(item, style), columns(item)))
+            .collect()
+    };
+    let apps = section(Section::Apps);
+    let mut others = section(Section::Others);
+    others.push(match style {
+        PreviewStyle::Initials => ("Tr".to_string(), 2),
+        PreviewStyle::Emoji => ("🗑\u{fe0f}".to_string(), 2),
+    });
+
+    let (apps, apps_width) = join(&apps);
+    let (others, others_width) = join(&others);
+    let width = apps_width + others_width + 5;
+
+    let mut out = String::new();
+    out.push_str(&format!("╭{}╮\n", "─".repeat(width)));
+    out.push_str(&format!("│ {apps} │ {others} │\n"));
+    out.push_str(&format!("╰{}╯\n", "─".repeat(width)));
+    for section in Section::ALL {
+        let legend: Vec<String> = dock
+            .section(section)
+            .iter()
+            .filter(|item| !item.is_spacer())
+            .map(|item| format!("{} {}", tile(item, style), item.label()))
+            .collect();
+        if !legend.is_empty() {
+            out.push_str(&format!("{}: {}\n", section.name(), legend.join(", ")));
+        }
+    }
+    out
+}
+
+/// How one tile is drawn.
+fn tile(item: &DockItem, style: PreviewStyle) -> String {
+    if item.is_spacer() {
+        return " ".repeat(columns(item));
+    }
+    if style == PreviewStyle::Emoji {
+        let emoji = match item.kind {
+            DockItemKind::DirectoryTile => Some("📁"),
+            DockItemKind::UrlTile => Some("🔗"),
+            _ => match &item.metadata.bundle_id {
+                Some(id) => APP_EMOJI
+                    .iter()
+                    .find(|(bundle_id, _)| bundle_id == id)
+                    .map(|(_, emoji)| *emoji),
+                None => Some("📄"),
+            },
+        };
+        if let Some(emoji) = emoji {
+            return emoji.to_string();
+        }
+    }
+    format!("{:<2}", initials(item.label()))
+}
+
+/// How many columns a tile takes up. Emoji count as two, as terminals draw them.
+fn columns(item: &DockItem) -> usize {
+    match item.kind {
+        DockItemKind::SmallSpacerTile => 1,
+        DockItemKind::FlexSpacerTile => 4,
+        _ => 2,
+    }
+}
+
+/// Up to two letters standing for `label`: the first letters of its first two words,
+/// or the first two letters of a single word.
+pub(crate) fn initials(label: &str) -> String {
+    let words: Vec<&str> = label.split_whitespace().collect();
+    match words.as_slice() {
+        [first, second, ..] => first
+            .chars()
+            .take(1)
+            .chain(second.chars().take(1))
+            .collect(),
+        [only] => only.chars().take(2).collect(),
+        [] => String::new(),
+    }
+}
+
+/// Joins drawn tiles with single spaces, returning the line and its width in columns.
+fn join(tiles: &[(String, usize)]) -> (String, usize) {
+    let line = tiles
+        .iter()
+        .map(|(tile, _)| tile.as_str())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let width =
+        tiles.iter().map(|(_, columns)| columns).sum::<usize>() + tiles.len().saturating_sub(1);
+    (line, width)
+}
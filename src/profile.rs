@@ -0,0 +1,762 @@
+use crate::cancel::CancellationToken;
+use crate::desktop::DesktopSettings;
+use crate::diff::DockDiff;
+use crate::dock::{self, AddPolicy, Dock, DockItem, DockItemKind, Position, Section};
+use crate::hooks::{self, Event, Hooks};
+use crate::layout::{self, TrimOrder};
+use crate::mac_app::MacApp;
+use crate::macos::{self, Feature};
+use crate::merge::Strategy;
+use crate::paths::VolumeState;
+use crate::progress::{Progress, Step};
+use crate::settings::Setting;
+use crate::state;
+use crate::status;
+use crate::transaction;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// A declarative description of the Dock, usually kept as YAML in a user's dotfiles.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    /// A profile to start from, such as a company-wide base; this one's fields win.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
+
+    /// Profiles layered over `extends`, in order, before this one's own fields.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+
+    /// Applications to pin, as paths to `.app` bundles, bundle identifiers, app
+    /// names, or spacer keywords.
+    ///
+    /// When omitted, the apps already in the Dock are left alone.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub apps: Option<Vec<Entry>>,
+
+    /// Folders and documents to pin on the right side of the Dock, or spacer keywords.
+    ///
+    /// When omitted, the items already there are left alone.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub others: Option<Vec<Entry>>,
+
+    /// Items to insert at a specific position, after `apps` and `others` are laid out.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub add: Vec<Addition>,
+
+    /// Dock preferences to set, keyed by their `com.apple.dock` name (e.g. `autohide`).
+    #[serde(default)]
+    pub settings: plist::Dictionary,
+
+    /// Arrange apps into category groups separated by spacers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group_by_category: Option<CategoryGrouping>,
+
+    /// Cap the number of apps, trimming the excess on apply.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_items: Option<MaxItems>,
+
+    /// App groups to end with a spacer, e.g. `[browsers, communication]`, placed after
+    /// each group's last app every time the profile is applied.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub separator_after: Vec<String>,
+
+    /// The spacer keyword `separator_after` inserts; `small-spacer` by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub separator: Option<String>,
+
+    /// Apps to open at login, in the same forms as `apps`, added by `docktor apply`
+    /// alongside the Dock (see [`Profile::apply_login_items`]).
+    ///
+    /// Login items already set up are kept, whether or not they're listed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub login_items: Option<Vec<Entry>>,
+
+    /// Desktop & Dock settings kept outside the Dock's preferences, such as clicking the
+    /// wallpaper to reveal the desktop and window tiling. Nothing outside the Dock's
+    /// preferences is touched without it. See [`Profile::apply_desktop`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub desktop: Option<DesktopSettings>,
+
+    /// App groups for `separator_after`, by name, as bundle identifiers, app names, or
+    /// `category:<category>`. They replace built-in groups of the same name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub groups: BTreeMap<String, Vec<String>>,
+
+    /// Commands and webhooks to notify when the profile is applied or drifts.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub hooks: Hooks,
+
+    /// The file the profile was loaded from, if any.
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
+}
+
+/// One item in a profile's `apps` or `others` list.
+///
+/// Usually just a path, but it can also list alternatives, as in
+/// `app: [Firefox, Safari]`, and be marked `optional: true`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Entry {
+    /// A single item that must be present.
+    Path(String),
+
+    /// Alternatives tried in order, and whether it's fine for none to be present.
+    Choice(Choice),
+}
+
+/// The long form of an [`Entry`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Choice {
+    /// Candidates in order of preference; the first one present is pinned.
+    #[serde(alias = "path", deserialize_with = "one_or_many")]
+    pub app: Vec<String>,
+
+    /// Skip the entry, rather than failing, when no candidate is present.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub optional: bool,
+}
+
+impl Entry {
+    /// The candidates for this entry, most preferred first.
+    pub fn candidates(&self) -> &[String] {
+        match self {
+            Entry::Path(path) => std::slice::from_ref(path),
+            Entry::Choice(choice) => &choice.app,
+        }
+    }
+
+    /// Whether the entry may be left out when nothing it names is present.
+    pub fn is_optional(&self) -> bool {
+        matches!(self, Entry::Choice(Choice { optional: true, .. }))
+    }
+
+    /// The spacer tile this entry stands for, if it is a spacer keyword.
+    pub fn spacer_kind(&self) -> Option<DockItemKind> {
+        match self {
+            Entry::Path(path) => spacer_kind(path),
+            Entry::Choice(_) => None,
+        }
+    }
+
+    /// Finds the first candidate that is an installed app.
+    ///
+    /// Returns `None` for an optional entry with nothing installed.
+    pub fn resolve_app(&self) -> Result<Option<MacApp>> {
+        self.resolve(MacApp::resolve)
+    }
+
+    /// Finds the first candidate that exists on disk, as a Dock tile. Network share
+    /// URLs such as `smb://server/share` always resolve, to a tile named after the share.
+    ///
+    /// Returns `None` for an optional entry with nothing present.
+    pub fn resolve_item(&self) -> Result<Option<DockItem>> {
+        self.resolve(|candidate| {
+            if dock::is_network_share(candidate) {
+                DockItem::network_share(candidate, dock::network_share_name(candidate))
+            } else {
+                DockItem::from_path(expand_home(candidate))
+            }
+        })
+    }
+
+    fn resolve<T>(&self, find: impl Fn(&str) -> Result<T>) -> Result<Option<T>> {
+        let mut last_error = None;
+        for candidate in self.candidates() {
+            match find(candidate) {
+                Ok(found) => return Ok(Some(found)),
+                Err(err) => last_error = Some(err),
+            }
+        }
+        if self.is_optional() {
+            tracing::info!(entry = %self, "skipping optional entry; nothing it names is present");
+            return Ok(None);
+        }
+        match (last_error, self.candidates()) {
+            (Some(err), [_]) => Err(err),
+            (Some(err), _) => Err(err.context(format!("None of {self} is present"))),
+            (None, _) => anyhow::bail!("The profile entry lists no alternatives"),
+        }
+    }
+}
+
+impl std::fmt::Display for Entry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.candidates().join(" | "))
+    }
+}
+
+impl From<String> for Entry {
+    fn from(path: String) -> Self {
+        Entry::Path(path)
+    }
+}
+
+/// Accepts either a single string or a list of them.
+fn one_or_many<'de, D>(deserializer: D) -> std::result::Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(one) => vec![one],
+        OneOrMany::Many(many) => many,
+    })
+}
+
+/// A limit on how many apps a profile leaves in the Dock.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MaxItems {
+    /// The most apps to keep, not counting spacers.
+    pub limit: usize,
+
+    /// Which apps to drop first.
+    #[serde(default)]
+    pub drop: TrimOrder,
+}
+
+/// An item a profile inserts at a given position, leaving its neighbors alone.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Addition {
+    /// The `.app` bundle, folder, or file to add.
+    pub path: String,
+
+    /// Where to put it, e.g. `after:Safari`; defaults to the end of its section.
+    #[serde(default)]
+    pub position: Position,
+}
+
+/// How a profile groups its apps by category (`LSApplicationCategoryType`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CategoryGrouping {
+    /// The spacer keyword placed between groups.
+    #[serde(default = "default_group_spacer")]
+    pub spacer: String,
+
+    /// Categories to put first, in order, e.g. `developer-tools`; others follow alphabetically.
+    #[serde(default)]
+    pub order: Vec<String>,
+}
+
+fn default_group_spacer() -> String {
+    "small-spacer".to_string()
+}
+
+/// Options controlling how a profile is applied to the live Dock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ApplyOptions {
+    /// Compute the changes without writing anything.
+    pub dry_run: bool,
+
+    /// Save the changes without restarting the Dock.
+    pub no_restart: bool,
+
+    /// What to do with items on external or network volumes that aren't mounted.
+    pub unmounted: UnmountedPolicy,
+
+    /// Whether to keep changes the user made since the last apply.
+    pub strategy: Strategy,
+
+    /// Write the Dock even when the diff is empty, for changes to bookkeeping such
+    /// as tile GUIDs that a diff doesn't show.
+    pub force: bool,
+}
+
+/// What applying a profile does with items on a volume that isn't mounted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum UnmountedPolicy {
+    /// Keep the item's current tile if it has one, and leave it out until the volume is back.
+    #[default]
+    Defer,
+
+    /// Leave the item out of the Dock.
+    Skip,
+
+    /// Fail, as for any other missing item.
+    Fail,
+}
+
+impl Profile {
+    /// Loads a profile from a YAML file, merging in any profiles it extends or includes.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::load_layer(path.as_ref())?.resolve()
+    }
+
+    /// Loads a single profile file, leaving `extends` and `include` unresolved.
+    pub(crate) fn load_layer(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read profile at {}", path.display()))?;
+
+        let mut profile: Profile = serde_yaml::from_str(&contents)
+            .with_context(|| format!("Failed to parse profile at {}", path.display()))?;
+        profile.source = Some(path.to_path_buf());
+        Ok(profile)
+    }
+
+    /// Captures an existing Dock as a profile.
+    ///
+    /// Tiles that don't point at a local file (such as web links) are skipped.
+    pub fn from_dock(dock: &Dock) -> Self {
+        Self::from_dock_with_home(dock, crate::paths::home_dir().as_deref())
+    }
+
+    /// Captures a Dock as a profile, writing paths under `home` as `~/…`.
+    ///
+    /// Used for Docks from other accounts or machines, whose home directory differs
+    /// from the current user's.
+    pub fn from_dock_with_home(dock: &Dock, home: Option<&Path>) -> Self {
+        let entries = |section| {
+            dock.section(section)
+                .iter()
+                .filter_map(|item| match spacer_keyword(item.kind) {
+                    Some(keyword) => Some(Entry::Path(keyword.to_string())),
+                    None => item
+                        .path()
+                        .map(|path| contract_home(&path, home))
+                        .or_else(|| {
+                            item.url()
+                                .filter(|url| dock::is_network_share(url))
+                                .map(String::from)
+                        })
+                        .map(Entry::Path),
+                })
+                .collect()
+        };
+
+        Profile {
+            extends: None,
+            include: Vec::new(),
+            apps: Some(entries(Section::Apps)),
+            others: Some(entries(Section::Others)),
+            add: Vec::new(),
+            settings: dock
+                .settings()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+            group_by_category: None,
+            max_items: None,
+            separator_after: Vec::new(),
+            separator: None,
+            login_items: None,
+            desktop: None,
+            groups: BTreeMap::new(),
+            hooks: Hooks::default(),
+            source: None,
+        }
+    }
+
+    /// A copy of the profile with each leading `~/` expanded against `home` instead of
+    /// the current user's home folder, for applying it to another account.
+    pub fn with_home(&self, home: &Path) -> Profile {
+        let expand = |path: &mut String| {
+            if let Some(rest) = path.strip_prefix("~/") {
+                *path = home.join(rest).display().to_string();
+            }
+        };
+        let mut profile = self.clone();
+        for entry in profile.apps.iter_mut().chain(&mut profile.others).flatten() {
+            match entry {
+                Entry::Path(path) => expand(path),
+                Entry::Choice(choice) => choice.app.iter_mut().for_each(expand),
+            }
+        }
+        for addition in &mut profile.add {
+            expand(&mut addition.path);
+        }
+        profile
+    }
+
+    /// Serializes the profile as YAML.
+    pub fn to_yaml(&self) -> Result<String> {
+        serde_yaml::to_string(self).context("Failed to serialize profile")
+    }
+
+    /// Makes the live Dock match this profile, returning what changed.
+    ///
+    /// A snapshot of the current Dock is taken before anything is written, and
+    /// nothing is written when the Dock already matches.
+    pub fn apply(&self, options: ApplyOptions) -> Result<DockDiff> {
+        self.apply_with(options, &(), &CancellationToken::new())
+    }
+
+    /// Like [`Profile::apply`], reporting each entry resolved and each write step to `progress`.
+    ///
+    /// Stops with [`Cancelled`](crate::cancel::Cancelled) if `cancel` is cancelled
+    /// before the new plist starts being written.
+    #[tracing::instrument(skip_all, fields(dry_run = options.dry_run, no_restart = options.no_restart))]
+    pub fn apply_with(
+        &self,
+        options: ApplyOptions,
+        progress: &dyn Progress,
+        cancel: &CancellationToken,
+    ) -> Result<DockDiff> {
+        let live = Dock::load()?;
+        let desired = match options.strategy {
+            Strategy::Replace => self.build(&live, options.unmounted, progress, cancel)?,
+            Strategy::Merge => self.merge_into_with(&live, options.unmounted, progress, cancel)?,
+        };
+        let diff = transaction::write_changes_with(
+            &live,
+            &desired,
+            options,
+            &self.hooks,
+            self.name().as_deref(),
+            progress,
+            cancel,
+        )?;
+        if !options.dry_run {
+            if let Some(source) = &self.source {
+                status::set_active_profile(source)?;
+            }
+            if let Err(err) = Dock::load().and_then(|dock| state::record_applied(&dock)) {
+                tracing::warn!("Could not record the applied Dock: {err:#}");
+            }
+        }
+        if !options.dry_run && !diff.is_empty() {
+            hooks::fire(
+                &self.hooks.on_apply,
+                Event::Applied,
+                self.name().as_deref(),
+                &diff,
+            );
+        }
+        Ok(diff)
+    }
+
+    /// Compares the live Dock to this profile without changing anything.
+    ///
+    /// Runs the `on_drift` hooks when they differ.
+    #[tracing::instrument(skip_all)]
+    pub fn check(&self) -> Result<DockDiff> {
+        let live = Dock::load()?;
+        let diff = DockDiff::between(&live, &self.apply_to(&live)?);
+        if !diff.is_empty() {
+            hooks::fire(
+                &self.hooks.on_drift,
+                Event::Drift,
+                self.name().as_deref(),
+                &diff,
+            );
+        }
+        Ok(diff)
+    }
+
+    /// Whether this profile names `item`, in `apps`, `others`, or `add`.
+    ///
+    /// Apps match by bundle identifier, so a tile pointing at another copy still counts.
+    pub fn manages(&self, item: &DockItem) -> bool {
+        fn candidates(entries: &Option<Vec<Entry>>) -> impl Iterator<Item = &String> {
+            entries.iter().flatten().flat_map(Entry::candidates)
+        }
+        if let Some(url) = item.url() {
+            return candidates(&self.others).any(|entry| entry == url);
+        }
+        let Some(path) = item.path() else {
+            return false;
+        };
+        let same_app = |candidate: &String| {
+            item.metadata.bundle_id.is_some()
+                && MacApp::resolve(candidate)
+                    .is_ok_and(|app| item.metadata.bundle_id.as_ref() == Some(&app.bundle_id))
+        };
+        if candidates(&self.apps).any(same_app) {
+            return true;
+        }
+        candidates(&self.apps)
+            .chain(candidates(&self.others))
+            .chain(self.add.iter().map(|addition| &addition.path))
+            .any(|entry| expand_home(entry) == path)
+    }
+
+    /// A short name for the profile: its file name without the extension.
+    pub fn name(&self) -> Option<String> {
+        Some(
+            self.source
+                .as_ref()?
+                .file_stem()?
+                .to_string_lossy()
+                .into_owned(),
+        )
+    }
+
+    /// Returns the Dock that results from applying this profile on top of `base`.
+    ///
+    /// Items already in `base` keep their existing tile data; settings not mentioned
+    /// by the profile keep their current values.
+    ///
+    /// Items on volumes that aren't mounted are deferred; see [`UnmountedPolicy`].
+    pub fn apply_to(&self, base: &Dock) -> Result<Dock> {
+        self.apply_to_with(base, UnmountedPolicy::default())
+    }
+
+    /// Like [`Profile::apply_to`], choosing what happens to items on unmounted volumes.
+    pub fn apply_to_with(&self, base: &Dock, unmounted: UnmountedPolicy) -> Result<Dock> {
+        self.build(base, unmounted, &(), &CancellationToken::new())
+    }
+
+    /// Applies this profile on top of `base`, reporting each entry before it is resolved.
+    #[tracing::instrument(skip_all)]
+    pub(crate) fn build(
+        &self,
+        base: &Dock,
+        unmounted: UnmountedPolicy,
+        progress: &dyn Progress,
+        cancel: &CancellationToken,
+    ) -> Result<Dock> {
+        let mut dock = base.clone();
+        let total = self.apps.iter().chain(&self.others).flatten().count() + self.add.len();
+        let mut done = 0;
+        let mut resolving = |entry: &str| {
+            cancel.check()?;
+            progress.step(Step::Resolving { done, total, entry });
+            done += 1;
+            anyhow::Ok(())
+        };
+
+        if let Some(apps) = &self.apps {
+            let mut items = Vec::new();
+            for entry in apps {
+                resolving(&entry.to_string())?;
+                if let Some(kind) = entry.spacer_kind() {
+                    items.push(DockItem::spacer(kind));
+                } else {
+                    match entry.resolve_app() {
+                        Ok(Some(app)) => {
+                            if let Some(feature) = Feature::for_bundle_id(&app.bundle_id) {
+                                warn_unsupported(feature, &app.display_name);
+                            }
+                            items.push(
+                                find_existing(base, Section::Apps, &app.bundle_id)
+                                    .unwrap_or_else(|| DockItem::new(&app)),
+                            )
+                        }
+                        Ok(None) => {}
+                        Err(err) => items.extend(on_unmounted(
+                            base,
+                            Section::Apps,
+                            entry.candidates(),
+                            unmounted,
+                            err,
+                        )?),
+                    }
+                }
+            }
+            dock.applications = Some(items);
+        }
+
+        if let Some(others) = &self.others {
+            let mut items = Vec::new();
+            for entry in others {
+                resolving(&entry.to_string())?;
+                if let Some(kind) = entry.spacer_kind() {
+                    items.push(DockItem::spacer(kind));
+                } else {
+                    match entry.resolve_item() {
+                        Ok(Some(item)) => items.push(
+                            find_existing(base, Section::Others, &item.identity()).unwrap_or(item),
+                        ),
+                        Ok(None) => {}
+                        Err(err) => items.extend(on_unmounted(
+                            base,
+                            Section::Others,
+                            entry.candidates(),
+                            unmounted,
+                            err,
+                        )?),
+                    }
+                }
+            }
+            dock.others = Some(items);
+        }
+
+        for addition in &self.add {
+            resolving(&addition.path)?;
+            let (section, item) = match DockItem::for_path(expand_home(&addition.path)) {
+                Ok(found) => found,
+                Err(err) => {
+                    // The tile, if any, is already in `dock`; there's nothing to insert.
+                    let section = if addition.path.ends_with(".app") {
+                        Section::Apps
+                    } else {
+                        Section::Others
+                    };
+                    let paths = std::slice::from_ref(&addition.path);
+                    on_unmounted(base, section, paths, unmounted, err)?;
+                    continue;
+                }
+            };
+            let item = find_existing(base, section, &item.identity()).unwrap_or(item);
+            dock.add_at(section, item, &addition.position, AddPolicy::Replace)
+                .with_context(|| format!("Failed to add {}", addition.path))?;
+        }
+
+        if let Some(grouping) = &self.group_by_category {
+            let spacer = spacer_kind(&grouping.spacer).with_context(|| {
+                format!(
+                    "Unknown spacer {:?} in group_by_category; use spacer, small-spacer, or flex-spacer",
+                    grouping.spacer
+                )
+            })?;
+            dock.group_apps_by_category(spacer, &grouping.order);
+        }
+        if let Some(max_items) = &self.max_items {
+            dock.trim_apps(max_items.limit, max_items.drop);
+        }
+        if !self.separator_after.is_empty() {
+            dock.insert_separators(&self.separator_groups()?, self.separator_kind()?);
+        }
+        for (key, value) in &self.settings {
+            let value = match Setting::for_key(key) {
+                Some(setting) => setting
+                    .normalize(value)
+                    .with_context(|| format!("Invalid setting {key}"))?,
+                None => value.clone(),
+            };
+            if let Some(feature) = Feature::for_setting(key) {
+                warn_unsupported(feature, key);
+            }
+            dock.extra.insert(key.clone(), value);
+        }
+
+        Ok(dock)
+    }
+}
+
+impl Profile {
+    /// The members of each group in `separator_after`, failing on a group that is
+    /// neither defined in `groups` nor built in.
+    pub fn separator_groups(&self) -> Result<Vec<Vec<String>>> {
+        self.separator_after
+            .iter()
+            .map(|name| {
+                layout::app_group(name, &self.groups).with_context(|| {
+                    let built_in: Vec<_> = layout::built_in_groups().collect();
+                    format!(
+                        "Unknown group {name:?} in separator_after; define it under groups or use {}",
+                        built_in.join(", ")
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// The spacer `separator_after` inserts, failing on an unknown spacer keyword.
+    pub fn separator_kind(&self) -> Result<DockItemKind> {
+        let keyword = self.separator.as_deref().unwrap_or("small-spacer");
+        spacer_kind(keyword).with_context(|| {
+            format!("Unknown separator {keyword:?}; use spacer, small-spacer, or flex-spacer")
+        })
+    }
+}
+
+/// Warns that the profile uses `feature` for `what` on a macOS without it.
+fn warn_unsupported(feature: Feature, what: &str) {
+    if !feature.is_supported() {
+        tracing::warn!(
+            "{what} needs {} from macOS {}, but this Mac runs {}",
+            feature.name(),
+            feature.since(),
+            macos::version().map_or_else(|| "an unknown version".to_string(), |v| v.to_string())
+        );
+    }
+}
+
+fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+/// Handles an entry that failed to resolve, returning the tile to keep for it.
+///
+/// Fails with `err` unless one of `candidates` is on a volume that isn't mounted.
+fn on_unmounted(
+    base: &Dock,
+    section: Section,
+    candidates: &[String],
+    policy: UnmountedPolicy,
+    err: anyhow::Error,
+) -> Result<Option<DockItem>> {
+    let unmounted = candidates.iter().find_map(|candidate| {
+        let path = expand_home(candidate);
+        match VolumeState::of(&path) {
+            VolumeState::Unmounted(volume) => Some((path, volume)),
+            _ => None,
+        }
+    });
+    let Some((path, volume)) = unmounted else {
+        return Err(err);
+    };
+
+    let existing = match policy {
+        UnmountedPolicy::Fail => return Err(err),
+        UnmountedPolicy::Skip => None,
+        UnmountedPolicy::Defer => base
+            .section(section)
+            .iter()
+            .find(|item| item.path().as_deref() == Some(path.as_path()))
+            .cloned(),
+    };
+    if existing.is_some() {
+        tracing::warn!(path = %path.display(), volume = %volume.display(), "volume isn't mounted; keeping the current tile");
+    } else {
+        tracing::warn!(path = %path.display(), volume = %volume.display(), "volume isn't mounted; leaving the item out");
+    }
+    Ok(existing)
+}
+
+/// Finds an item in `dock` matching `identity`, so its tile data can be reused.
+fn find_existing(dock: &Dock, section: Section, identity: &str) -> Option<DockItem> {
+    dock.section(section)
+        .iter()
+        .find(|item| item.identity() == identity)
+        .cloned()
+}
+
+/// Maps a profile entry such as `spacer` to the spacer tile type it names.
+pub(crate) fn spacer_kind(entry: &str) -> Option<DockItemKind> {
+    match entry {
+        "spacer" => Some(DockItemKind::SpacerTile),
+        "small-spacer" => Some(DockItemKind::SmallSpacerTile),
+        "flex-spacer" => Some(DockItemKind::FlexSpacerTile),
+        _ => None,
+    }
+}
+
+/// The profile keyword for a spacer tile type, the inverse of [`spacer_kind`].
+pub(crate) fn spacer_keyword(kind: DockItemKind) -> Option<&'static str> {
+    match kind {
+        DockItemKind::SpacerTile => Some("spacer"),
+        DockItemKind::SmallSpacerTile => Some("small-spacer"),
+        DockItemKind::FlexSpacerTile => Some("flex-spacer"),
+        _ => None,
+    }
+}
+
+/// Replaces `home` with `~` so profiles stay portable.
+fn contract_home(path: &Path, home: Option<&Path>) -> String {
+    match home.and_then(|home| path.strip_prefix(home).ok()) {
+        Some(rest) => format!("~/{}", rest.display()),
+        None => path.display().to_string(),
+    }
+}
+
+/// Expands a leading `~/` to the user's home directory.
+pub(crate) fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), crate::paths::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
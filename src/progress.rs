@@ -0,0 +1,49 @@
+//! Reporting how far along a long-running operation is.
+//!
+//! Scanning for installed apps and applying a profile can take seconds, mostly spent
+//! waiting on the file system and the Dock. Callers pass a [`Progress`] to hear about
+//! each step, e.g. to draw a progress bar or update a status line.
+
+use std::path::Path;
+
+/// One step of a long-running operation.
+#[derive(Debug, Clone, Copy)]
+pub enum Step<'a> {
+    /// An app bundle was read while scanning; `done` of `total` so far.
+    Scanned {
+        done: usize,
+        total: usize,
+        path: &'a Path,
+    },
+
+    /// A profile entry is about to be resolved; `done` of `total` are finished.
+    Resolving {
+        done: usize,
+        total: usize,
+        entry: &'a str,
+    },
+
+    /// The new Dock is being written.
+    Writing,
+
+    /// The Dock is being restarted to pick up the changes.
+    Restarting,
+}
+
+/// Receives [`Step`]s as an operation advances.
+///
+/// Any `Fn(Step)` closure is a `Progress`; `()` ignores every step.
+pub trait Progress {
+    /// Called at each step.
+    fn step(&self, step: Step<'_>);
+}
+
+impl Progress for () {
+    fn step(&self, _step: Step<'_>) {}
+}
+
+impl<F: Fn(Step<'_>)> Progress for F {
+    fn step(&self, step: Step<'_>) {
+        self(step)
+    }
+}
@@ -0,0 +1,52 @@
+use crate::dock::Dock;
+use anyhow::{Context, Result, bail};
+use std::path::PathBuf;
+use std::process::Command;
+
+/// A Dock configuration fetched from another Mac.
+#[derive(Debug)]
+pub struct RemoteDock {
+    /// The remote user's Dock.
+    pub dock: Dock,
+
+    /// The remote user's home directory, used to make paths portable.
+    pub home: PathBuf,
+}
+
+/// Fetches the Dock of the user `destination` logs in as, using `ssh`.
+///
+/// `destination` is anything `ssh` accepts, such as `user@host` or a host alias, but
+/// never an option: one starting with `-` is refused. The plist is read with
+/// `defaults export` so it reflects what cfprefsd has cached, not just what has been
+/// flushed to disk.
+#[tracing::instrument]
+pub fn fetch(destination: &str) -> Result<RemoteDock> {
+    if destination.starts_with('-') {
+        bail!("Invalid destination {destination:?}; it can't start with -");
+    }
+    let output = Command::new("ssh")
+        .arg("--")
+        .arg(destination)
+        .arg("printf '%s\\n' \"$HOME\" && defaults export com.apple.dock -")
+        .output()
+        .with_context(|| format!("Failed to run ssh {destination}"))?;
+
+    if !output.status.success() {
+        bail!(
+            "Fetching the Dock from {destination} failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let stdout = output.stdout;
+    let newline = stdout
+        .iter()
+        .position(|&b| b == b'\n')
+        .with_context(|| format!("Unexpected output from {destination}"))?;
+    let home = PathBuf::from(String::from_utf8_lossy(&stdout[..newline]).into_owned());
+
+    let dock: Dock = plist::from_bytes(&stdout[newline + 1..])
+        .with_context(|| format!("Failed to parse the Dock plist from {destination}"))?;
+
+    Ok(RemoteDock { dock, home })
+}
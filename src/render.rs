@@ -0,0 +1,294 @@
+//! Drawing the Dock as a PNG or SVG image with the apps' real icons, for
+//! documentation and change approvals.
+//!
+//! Icons are read from each app's `.icns` file through the [`IconCache`]; folders,
+//! files, and links use the generic icons macOS ships, and anything without an icon is
+//! drawn as a grey tile with its initials. Only built with the `render` feature.
+
+use crate::dock::{Dock, DockItem, DockItemKind, Section};
+use crate::icons::{self, IconCache};
+use crate::mac_app::MacApp;
+use crate::preview::initials;
+use anyhow::{Context, Result, bail};
+use base64::Engine;
+use icns::{Image, PixelFormat};
+use std::path::Path;
+
+/// The generic icons macOS uses for tiles that aren't apps.
+const CORE_TYPES: &str = "/System/Library/CoreServices/CoreTypes.bundle/Contents/Resources";
+
+/// The Trash icon the Dock itself draws.
+const TRASH_ICON: &str = "/System/Library/CoreServices/Dock.app/Contents/Resources/trashempty.png";
+
+/// The image formats [`render`] can produce.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum RenderFormat {
+    /// A PNG bitmap.
+    Png,
+
+    /// An SVG document with the icons embedded as PNGs.
+    Svg,
+}
+
+impl RenderFormat {
+    /// The format a file name's extension asks for, if it names one.
+    pub fn for_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "png" => Some(RenderFormat::Png),
+            "svg" => Some(RenderFormat::Svg),
+            _ => None,
+        }
+    }
+}
+
+/// One thing drawn along the Dock.
+enum Slot {
+    /// A tile, with its icon if one could be read.
+    Tile { label: String, icon: Option<Image> },
+
+    /// Empty space, such as a spacer, this many tile widths wide in eighths.
+    Gap(u32),
+
+    /// The line between the apps and the others.
+    Divider,
+}
+
+/// Draws `dock` in `format`, with icons `tile_size` pixels square, returning the file's bytes.
+pub fn render(dock: &Dock, format: RenderFormat, tile_size: u32) -> Result<Vec<u8>> {
+    if !(16..=512).contains(&tile_size) {
+        bail!("The tile size must be from 16 to 512 pixels, not {tile_size}");
+    }
+
+    let cache = IconCache::open()?;
+    let mut slots = Vec::new();
+    for section in Section::ALL {
+        if section == Section::Others {
+            slots.push(Slot::Divider);
+        }
+        for item in dock.section(section) {
+            slots.push(slot(&cache, item, tile_size));
+        }
+    }
+    slots.push(Slot::Tile {
+        label: "Trash".to_string(),
+        icon: std::fs::File::open(TRASH_ICON)
+            .ok()
+            .and_then(|file| Image::read_png(std::io::BufReader::new(file)).ok())
+            .map(|icon| icons::scale(&icon.convert_to(PixelFormat::RGBA), tile_size)),
+    });
+
+    let layout = Layout::new(&slots, tile_size);
+    match format {
+        RenderFormat::Png => png(&slots, &layout),
+        RenderFormat::Svg => svg(&slots, &layout),
+    }
+}
+
+/// What `item` looks like along the Dock.
+fn slot(cache: &IconCache, item: &DockItem, tile_size: u32) -> Slot {
+    let gap = match item.kind {
+        DockItemKind::SmallSpacerTile => Some(4),
+        DockItemKind::SpacerTile => Some(8),
+        DockItemKind::FlexSpacerTile => Some(16),
+        _ => None,
+    };
+    if let Some(gap) = gap {
+        return Slot::Gap(gap);
+    }
+
+    let generic = |name: &str| cache.file_icon(&Path::new(CORE_TYPES).join(name), tile_size);
+    let png = match item.kind {
+        DockItemKind::DirectoryTile if item.is_smart_folder() => {
+            generic("SmartFolderIcon.icns").map(Some)
+        }
+        DockItemKind::DirectoryTile => generic("GenericFolderIcon.icns").map(Some),
+        DockItemKind::UrlTile => generic("GenericURLIcon.icns").map(Some),
+        _ if item.metadata.bundle_id.is_some() => item
+            .path()
+            .context("The app has no path")
+            .and_then(MacApp::from_path)
+            .and_then(|app| cache.app_icon(&app, tile_size)),
+        _ => generic("GenericDocumentIcon.icns").map(Some),
+    };
+    let icon = match png.and_then(|png| png.map(|png| icons::load(&png)).transpose()) {
+        Ok(icon) => icon,
+        Err(err) => {
+            tracing::debug!("no icon for {}: {err:#}", item.label());
+            None
+        }
+    };
+    Slot::Tile {
+        label: item.label().to_string(),
+        icon,
+    }
+}
+
+/// Where each slot goes.
+struct Layout {
+    tile: u32,
+    padding: u32,
+    width: u32,
+    height: u32,
+    /// The left edge of each slot.
+    x: Vec<u32>,
+}
+
+impl Layout {
+    fn new(slots: &[Slot], tile: u32) -> Self {
+        let padding = tile / 8;
+        let mut x = Vec::with_capacity(slots.len());
+        let mut cursor = padding * 2;
+        for slot in slots {
+            x.push(cursor);
+            cursor += match slot {
+                Slot::Tile { .. } => tile,
+                Slot::Gap(eighths) => tile * eighths / 8,
+                Slot::Divider => 0,
+            } + padding;
+            if matches!(slot, Slot::Divider) {
+                cursor += padding;
+            }
+        }
+        Layout {
+            tile,
+            padding,
+            width: cursor + padding,
+            height: tile + padding * 2,
+            x,
+        }
+    }
+}
+
+/// Composes the Dock into a PNG.
+fn png(slots: &[Slot], layout: &Layout) -> Result<Vec<u8>> {
+    let mut canvas = Image::new(PixelFormat::RGBA, layout.width, layout.height);
+    fill_rounded(&mut canvas, [236, 236, 236, 230], layout.padding * 2);
+
+    for (slot, &x) in slots.iter().zip(&layout.x) {
+        match slot {
+            Slot::Tile {
+                icon: Some(icon), ..
+            } => blend(&mut canvas, icon, x, layout.padding),
+            Slot::Tile { icon: None, .. } => {
+                let mut placeholder = Image::new(PixelFormat::RGBA, layout.tile, layout.tile);
+                fill_rounded(&mut placeholder, [180, 180, 180, 255], layout.tile / 5);
+                blend(&mut canvas, &placeholder, x, layout.padding);
+            }
+            Slot::Divider => {
+                let mut line = Image::new(PixelFormat::RGBA, 1, layout.tile);
+                for pixel in line.data_mut().chunks_mut(4) {
+                    pixel.copy_from_slice(&[140, 140, 140, 255]);
+                }
+                blend(&mut canvas, &line, x, layout.padding);
+            }
+            Slot::Gap(_) => {}
+        }
+    }
+
+    let mut out = Vec::new();
+    canvas
+        .write_png(&mut out)
+        .context("Failed to encode the PNG")?;
+    Ok(out)
+}
+
+/// Draws the Dock as an SVG, embedding each icon as a PNG.
+fn svg(slots: &[Slot], layout: &Layout) -> Result<Vec<u8>> {
+    let Layout {
+        tile,
+        padding,
+        width,
+        height,
+        ..
+    } = *layout;
+    let mut out = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+         viewBox=\"0 0 {width} {height}\">\n  <rect width=\"{width}\" height=\"{height}\" \
+         rx=\"{}\" fill=\"#ececec\" fill-opacity=\"0.9\"/>\n",
+        padding * 2
+    );
+    for (slot, &x) in slots.iter().zip(&layout.x) {
+        match slot {
+            Slot::Tile {
+                label,
+                icon: Some(icon),
+            } => {
+                let mut png = Vec::new();
+                icon.write_png(&mut png)
+                    .context("Failed to encode an icon")?;
+                out.push_str(&format!(
+                    "  <image x=\"{x}\" y=\"{padding}\" width=\"{tile}\" height=\"{tile}\" \
+                     href=\"data:image/png;base64,{}\"><title>{}</title></image>\n",
+                    base64::engine::general_purpose::STANDARD.encode(&png),
+                    escape(label)
+                ));
+            }
+            Slot::Tile { label, icon: None } => out.push_str(&format!(
+                "  <g><title>{label}</title><rect x=\"{x}\" y=\"{padding}\" width=\"{tile}\" \
+                 height=\"{tile}\" rx=\"{}\" fill=\"#b4b4b4\"/><text x=\"{}\" y=\"{}\" \
+                 font-family=\"-apple-system, Helvetica, sans-serif\" font-size=\"{}\" \
+                 text-anchor=\"middle\" dominant-baseline=\"central\" fill=\"#fff\">{}</text></g>\n",
+                tile / 5,
+                x + tile / 2,
+                padding + tile / 2,
+                tile * 2 / 5,
+                escape(&initials(label)),
+                label = escape(label),
+            )),
+            Slot::Divider => out.push_str(&format!(
+                "  <line x1=\"{x}\" y1=\"{padding}\" x2=\"{x}\" y2=\"{}\" stroke=\"#8c8c8c\"/>\n",
+                padding + tile
+            )),
+            Slot::Gap(_) => {}
+        }
+    }
+    out.push_str("</svg>\n");
+    Ok(out.into_bytes())
+}
+
+/// Fills `image` with `color` inside a rectangle with corners rounded by `radius`.
+fn fill_rounded(image: &mut Image, color: [u8; 4], radius: u32) {
+    let (width, height) = (image.width(), image.height());
+    let r = radius.min(width / 2).min(height / 2) as i64;
+    for (i, pixel) in image.data_mut().chunks_mut(4).enumerate() {
+        let (x, y) = ((i as u32 % width) as i64, (i as u32 / width) as i64);
+        // Distance into the corner square, if the pixel is in one.
+        let dx = (r - x).max(x - (width as i64 - 1 - r)).max(0);
+        let dy = (r - y).max(y - (height as i64 - 1 - r)).max(0);
+        if dx * dx + dy * dy <= r * r {
+            pixel.copy_from_slice(&color);
+        }
+    }
+}
+
+/// Draws `top` over `canvas` with its top-left corner at `x`, `y`, blending by alpha.
+fn blend(canvas: &mut Image, top: &Image, x: u32, y: u32) {
+    let width = canvas.width();
+    let height = canvas.height();
+    let top_width = top.width();
+    let source = top.data().to_vec();
+    let data = canvas.data_mut();
+    for (i, pixel) in source.chunks(4).enumerate() {
+        let (tx, ty) = (x + i as u32 % top_width, y + i as u32 / top_width);
+        if tx >= width || ty >= height {
+            continue;
+        }
+        let under = &mut data[((ty * width + tx) * 4) as usize..][..4];
+        let alpha = u32::from(pixel[3]);
+        for channel in 0..3 {
+            under[channel] = ((u32::from(pixel[channel]) * alpha
+                + u32::from(under[channel]) * (255 - alpha))
+                / 255) as u8;
+        }
+        under[3] = (alpha + u32::from(under[3]) * (255 - alpha) / 255) as u8;
+    }
+}
+
+/// Escapes text for use in SVG.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,102 @@
+//! Checking that docktor reads and writes a particular Dock plist without losing anything.
+
+use crate::dock::Dock;
+use anyhow::{Context, Result};
+use std::fmt;
+use std::path::Path;
+
+/// A value that didn't survive loading a plist into a [`Dock`] and serializing it again.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Difference {
+    /// Where the value is, as a JSON Pointer, e.g. `/persistent-apps/0/tile-data/book`.
+    pub pointer: String,
+
+    /// The value in the original plist, if it had one.
+    pub before: Option<plist::Value>,
+
+    /// The value after the round trip, if it has one.
+    pub after: Option<plist::Value>,
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let describe = |value: &Option<plist::Value>| match value {
+            Some(value) => serde_json::to_string(value).unwrap_or_else(|_| format!("{value:?}")),
+            None => "(missing)".to_string(),
+        };
+        write!(
+            f,
+            "{}: {} → {}",
+            self.pointer,
+            describe(&self.before),
+            describe(&self.after)
+        )
+    }
+}
+
+/// Loads the plist at `path` as a [`Dock`], serializes it again, and lists every key
+/// or value that came out different.
+///
+/// An empty list means docktor can rewrite this plist without corrupting it.
+pub fn roundtrip_check<P: AsRef<Path>>(path: P) -> Result<Vec<Difference>> {
+    let path = path.as_ref();
+    let original = plist::Value::from_file(path)
+        .with_context(|| format!("Failed to parse plist at {}", path.display()))?;
+    let dock = Dock::load_from(path)?;
+    let rewritten = plist::to_value(&dock).context("Failed to serialize the Dock")?;
+
+    let mut differences = Vec::new();
+    compare(
+        String::new(),
+        Some(&original),
+        Some(&rewritten),
+        &mut differences,
+    );
+    Ok(differences)
+}
+
+fn compare(
+    pointer: String,
+    before: Option<&plist::Value>,
+    after: Option<&plist::Value>,
+    differences: &mut Vec<Difference>,
+) {
+    match (before, after) {
+        (Some(plist::Value::Dictionary(before)), Some(plist::Value::Dictionary(after))) => {
+            for (key, value) in before {
+                compare(
+                    child(&pointer, key),
+                    Some(value),
+                    after.get(key),
+                    differences,
+                );
+            }
+            for (key, value) in after {
+                if !before.contains_key(key) {
+                    compare(child(&pointer, key), None, Some(value), differences);
+                }
+            }
+        }
+        (Some(plist::Value::Array(before)), Some(plist::Value::Array(after))) => {
+            for index in 0..before.len().max(after.len()) {
+                compare(
+                    child(&pointer, &index.to_string()),
+                    before.get(index),
+                    after.get(index),
+                    differences,
+                );
+            }
+        }
+        (before, after) if before != after => differences.push(Difference {
+            pointer,
+            before: before.cloned(),
+            after: after.cloned(),
+        }),
+        _ => {}
+    }
+}
+
+/// Appends `key` to a JSON Pointer, escaping it.
+fn child(pointer: &str, key: &str) -> String {
+    format!("{pointer}/{}", key.replace('~', "~0").replace('/', "~1"))
+}
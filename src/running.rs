@@ -0,0 +1,134 @@
+use crate::dock::{AddPolicy, Dock};
+use crate::mac_app::MacApp;
+use crate::macos;
+use anyhow::{Context, Result, bail};
+use std::process::Command;
+
+/// Bundle identifiers the Dock always shows on its own and that can't be pinned.
+pub(crate) const BUILT_IN_APPS: &[&str] = &["com.apple.finder"];
+
+/// Lists the GUI applications that are currently running, in launch order.
+///
+/// Reads `lsappinfo list` and keeps foreground apps, the ones that get a Dock tile.
+#[tracing::instrument]
+pub fn running_apps() -> Result<Vec<MacApp>> {
+    macos::ensure("Listing running apps")?;
+    let stdout = lsappinfo(&["list"])?;
+    let apps = parse_lsappinfo(&stdout)
+        .into_iter()
+        .filter_map(|path| match MacApp::from_path(&path) {
+            Ok(app) => Some(app),
+            Err(err) => {
+                tracing::debug!(path, error = %err, "skipping running app");
+                None
+            }
+        })
+        .collect();
+    Ok(apps)
+}
+
+/// Extracts the bundle paths of foreground apps from `lsappinfo list` output.
+///
+/// Each app is a numbered block such as `1) "Safari" ASN:...` followed by indented
+/// `key="value"` lines, including `bundle path` and `type`.
+fn parse_lsappinfo(output: &str) -> Vec<String> {
+    let mut paths = Vec::new();
+    let (mut path, mut foreground) = (None, false);
+    let mut flush = |path: &mut Option<String>, foreground: &mut bool| {
+        if let Some(path) = path.take()
+            && *foreground
+        {
+            paths.push(path);
+        }
+        *foreground = false;
+    };
+
+    for line in output.lines() {
+        let line = line.trim();
+        if line
+            .split_once(") ")
+            .is_some_and(|(n, _)| n.parse::<u32>().is_ok())
+        {
+            flush(&mut path, &mut foreground);
+        } else if let Some(value) = line.strip_prefix("bundle path=") {
+            path = Some(value.trim_matches('"').to_string());
+        } else if let Some(value) = line.strip_prefix("type=") {
+            foreground = value.trim_matches('"') == "Foreground";
+        }
+    }
+    flush(&mut path, &mut foreground);
+    paths
+}
+
+impl MacApp {
+    /// The app a running process belongs to.
+    ///
+    /// Fails if the process isn't part of an app bundle, e.g. a command-line tool.
+    pub fn from_pid(pid: u32) -> Result<Self> {
+        macos::ensure("Looking up running apps")?;
+        let path = bundle_path(&format!("pid:{pid}"))?
+            .with_context(|| format!("Process {pid} isn't a running app"))?;
+        Self::from_path(path)
+    }
+
+    /// The app that's in front, the one whose menus are in the menu bar.
+    pub fn frontmost() -> Result<Self> {
+        macos::ensure("Looking up the frontmost app")?;
+        let asn = lsappinfo(&["front"])?;
+        if asn.is_empty() {
+            bail!("No app is in front");
+        }
+        let path = bundle_path(&asn)?
+            .with_context(|| format!("The frontmost app ({asn}) has no bundle"))?;
+        Self::from_path(path)
+    }
+}
+
+/// The bundle path `lsappinfo` has for the app matching `specifier`, an ASN or
+/// `pid:<pid>`.
+fn bundle_path(specifier: &str) -> Result<Option<String>> {
+    let output = lsappinfo(&["info", "-only", "bundlepath", specifier])?;
+    Ok(parse_bundle_path(&output))
+}
+
+/// Extracts the path from `lsappinfo info -only bundlepath` output, a single
+/// `"LSBundlePath"="/Applications/Safari.app"` line, or nothing for an unknown app.
+fn parse_bundle_path(output: &str) -> Option<String> {
+    let (_, value) = output.trim().split_once('=')?;
+    let value = value.trim().trim_matches('"');
+    (!value.is_empty() && value != "[ NULL ]").then(|| value.to_string())
+}
+
+/// Runs `lsappinfo` with `args`, returning what it printed.
+fn lsappinfo(args: &[&str]) -> Result<String> {
+    let output = Command::new("lsappinfo")
+        .args(args)
+        .output()
+        .context("Failed to run lsappinfo")?;
+    if !output.status.success() {
+        bail!(
+            "lsappinfo {} failed: {}",
+            args[0],
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+impl Dock {
+    /// Pins every running GUI app that isn't already in the apps section.
+    ///
+    /// Returns the apps that were added.
+    pub fn pin_running_apps(&mut self) -> Result<Vec<MacApp>> {
+        let mut pinned = Vec::new();
+        for app in running_apps()? {
+            if BUILT_IN_APPS.contains(&app.bundle_id.as_str()) {
+                continue;
+            }
+            if self.add_app(&app, AddPolicy::Skip) {
+                pinned.push(app);
+            }
+        }
+        Ok(pinned)
+    }
+}
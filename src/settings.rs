@@ -0,0 +1,519 @@
+//! Typed access to the Dock preferences docktor knows the meaning of.
+//!
+//! Settings are stored as loose `com.apple.dock` values, and the Dock quietly ignores or
+//! misreads ones it doesn't expect. [`Setting`] checks a value before it is written,
+//! whether it comes from the command line or a profile.
+
+use crate::dock::Dock;
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::RangeInclusive;
+
+/// Which edge of the screen the Dock is on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum Orientation {
+    /// Down the left edge.
+    Left,
+
+    /// Along the bottom edge.
+    #[default]
+    Bottom,
+
+    /// Down the right edge.
+    Right,
+}
+
+/// Where along its edge the Dock sits, for Docks shorter than the screen.
+///
+/// The Dock only honours this on some macOS versions, but it is kept for completeness.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum Pinning {
+    /// At the start of the edge: the left, or the top for a vertical Dock.
+    Start,
+
+    /// Centred.
+    #[default]
+    Middle,
+
+    /// At the end of the edge: the right, or the bottom for a vertical Dock.
+    End,
+}
+
+impl Orientation {
+    /// The value as the Dock stores it.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Orientation::Left => "left",
+            Orientation::Bottom => "bottom",
+            Orientation::Right => "right",
+        }
+    }
+}
+
+impl Pinning {
+    /// The value as the Dock stores it.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Pinning::Start => "start",
+            Pinning::Middle => "middle",
+            Pinning::End => "end",
+        }
+    }
+}
+
+/// A named icon size, for profiles and the command line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum TilesizePreset {
+    /// 32 points.
+    Small,
+
+    /// 48 points, close to the Dock's own default.
+    Medium,
+
+    /// 64 points.
+    Large,
+}
+
+impl TilesizePreset {
+    /// Every preset, smallest first.
+    pub const ALL: [TilesizePreset; 3] = [
+        TilesizePreset::Small,
+        TilesizePreset::Medium,
+        TilesizePreset::Large,
+    ];
+
+    /// The preset's name, as written in profiles.
+    pub fn name(self) -> &'static str {
+        match self {
+            TilesizePreset::Small => "small",
+            TilesizePreset::Medium => "medium",
+            TilesizePreset::Large => "large",
+        }
+    }
+
+    /// The icon size the preset stands for, in points.
+    pub fn points(self) -> u32 {
+        match self {
+            TilesizePreset::Small => 32,
+            TilesizePreset::Medium => 48,
+            TilesizePreset::Large => 64,
+        }
+    }
+}
+
+/// What to do to an on/off setting from a one-word command such as `docktor autohide`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Switch {
+    /// Turn it on.
+    On,
+
+    /// Turn it off.
+    Off,
+
+    /// Turn it on if it's off, and off if it's on.
+    Toggle,
+}
+
+impl Switch {
+    /// The setting's value after switching it from `current`.
+    pub fn apply(self, current: bool) -> bool {
+        match self {
+            Switch::On => true,
+            Switch::Off => false,
+            Switch::Toggle => !current,
+        }
+    }
+}
+
+/// A setting value that is one of a fixed set of words.
+trait Keyword: Copy + 'static {
+    /// Every value, in the order they are listed.
+    const ALL: &'static [Self];
+
+    /// The word for this value.
+    fn word(self) -> &'static str;
+
+    /// The value `word` names, ignoring case.
+    fn find(word: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|value| value.word().eq_ignore_ascii_case(word))
+    }
+}
+
+impl Keyword for Orientation {
+    const ALL: &'static [Self] = &[Orientation::Left, Orientation::Bottom, Orientation::Right];
+
+    fn word(self) -> &'static str {
+        self.as_str()
+    }
+}
+
+impl Keyword for Pinning {
+    const ALL: &'static [Self] = &[Pinning::Start, Pinning::Middle, Pinning::End];
+
+    fn word(self) -> &'static str {
+        self.as_str()
+    }
+}
+
+impl Keyword for TilesizePreset {
+    const ALL: &'static [Self] = &TilesizePreset::ALL;
+
+    fn word(self) -> &'static str {
+        self.name()
+    }
+}
+
+/// Parses one of a keyword setting's values, naming the allowed ones if `value` isn't.
+fn keyword<T: Keyword>(setting: Setting, value: &str) -> Result<T> {
+    T::find(value).with_context(|| {
+        let allowed: Vec<_> = T::ALL.iter().map(|value| value.word()).collect();
+        format!(
+            "{value:?} isn't a valid {setting}; use {}",
+            allowed.join(", ")
+        )
+    })
+}
+
+/// The sizes, in points, the Dock can show icons at.
+pub const TILESIZE_RANGE: RangeInclusive<u32> = 16..=128;
+
+/// The sizes, in points, the Dock can magnify icons to.
+pub const LARGESIZE_RANGE: RangeInclusive<u32> = 16..=128;
+
+/// The icon size when `tilesize` isn't set.
+const DEFAULT_TILESIZE: u32 = 48;
+
+/// The size icons magnify to when `largesize` isn't set.
+const DEFAULT_LARGESIZE: u32 = 128;
+
+/// The Dock's typed settings, with the Dock's own default for any that aren't set.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DockSettings {
+    /// Which edge of the screen the Dock is on (`orientation`).
+    pub orientation: Orientation,
+
+    /// Where along that edge it sits (`pinning`).
+    pub pinning: Pinning,
+
+    /// The size of icons in points (`tilesize`).
+    pub tilesize: u32,
+
+    /// Whether the Dock hides until the pointer reaches its edge (`autohide`).
+    pub autohide: bool,
+
+    /// Whether icons grow as the pointer passes over them (`magnification`).
+    pub magnification: bool,
+
+    /// The size in points icons grow to when magnified (`largesize`).
+    pub largesize: u32,
+
+    /// Whether running apps get a dot under their icon (`show-process-indicators`).
+    pub show_process_indicators: bool,
+
+    /// Whether the icons of hidden apps are dimmed (`showhidden`).
+    pub show_hidden: bool,
+}
+
+impl Default for DockSettings {
+    fn default() -> Self {
+        DockSettings {
+            orientation: Orientation::default(),
+            pinning: Pinning::default(),
+            tilesize: DEFAULT_TILESIZE,
+            autohide: false,
+            magnification: false,
+            largesize: DEFAULT_LARGESIZE,
+            show_process_indicators: true,
+            show_hidden: false,
+        }
+    }
+}
+
+impl DockSettings {
+    /// Reads the settings from `dock`, falling back to the default for unreadable values.
+    pub fn of(dock: &Dock) -> Self {
+        let defaults = DockSettings::default();
+        let value = |setting: Setting| dock.extra.get(setting.key());
+        DockSettings {
+            orientation: read_keyword(value(Setting::Orientation)),
+            pinning: read_keyword(value(Setting::Pinning)),
+            tilesize: value(Setting::Tilesize)
+                .and_then(as_size)
+                .filter(|size| TILESIZE_RANGE.contains(size))
+                .unwrap_or(defaults.tilesize),
+            autohide: value(Setting::Autohide)
+                .and_then(as_bool)
+                .unwrap_or(defaults.autohide),
+            magnification: value(Setting::Magnification)
+                .and_then(as_bool)
+                .unwrap_or(defaults.magnification),
+            largesize: value(Setting::Largesize)
+                .and_then(as_size)
+                .filter(|size| LARGESIZE_RANGE.contains(size))
+                .unwrap_or(defaults.largesize),
+            show_process_indicators: value(Setting::ShowProcessIndicators)
+                .and_then(as_bool)
+                .unwrap_or(defaults.show_process_indicators),
+            show_hidden: value(Setting::ShowHidden)
+                .and_then(as_bool)
+                .unwrap_or(defaults.show_hidden),
+        }
+    }
+
+    /// The value of an on/off setting, or `None` for settings that aren't on/off.
+    pub fn flag(&self, setting: Setting) -> Option<bool> {
+        match setting {
+            Setting::Autohide => Some(self.autohide),
+            Setting::Magnification => Some(self.magnification),
+            Setting::ShowProcessIndicators => Some(self.show_process_indicators),
+            Setting::ShowHidden => Some(self.show_hidden),
+            _ => None,
+        }
+    }
+
+    /// The value of `setting` as the command line writes it, e.g. `left`, `48`, or `on`.
+    pub fn value(&self, setting: Setting) -> String {
+        match setting {
+            Setting::Orientation => self.orientation.as_str().to_string(),
+            Setting::Pinning => self.pinning.as_str().to_string(),
+            Setting::Tilesize => self.tilesize.to_string(),
+            Setting::Autohide => on_off(self.autohide).to_string(),
+            Setting::Magnification => on_off(self.magnification).to_string(),
+            Setting::Largesize => self.largesize.to_string(),
+            Setting::ShowProcessIndicators => on_off(self.show_process_indicators).to_string(),
+            Setting::ShowHidden => on_off(self.show_hidden).to_string(),
+        }
+    }
+}
+
+impl fmt::Display for DockSettings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = Setting::ALL
+            .iter()
+            .map(|s| s.key().len())
+            .max()
+            .unwrap_or(0);
+        for setting in Setting::ALL {
+            writeln!(f, "{:width$}  {}", setting.key(), self.value(setting))?;
+        }
+        Ok(())
+    }
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value { "on" } else { "off" }
+}
+
+/// Parses a keyword setting's stored value, or its default if it isn't set or invalid.
+fn read_keyword<T: Keyword + Default>(value: Option<&plist::Value>) -> T {
+    value
+        .and_then(plist::Value::as_string)
+        .and_then(T::find)
+        .unwrap_or_default()
+}
+
+/// A stored flag, which `defaults` may have written as a number.
+fn as_bool(value: &plist::Value) -> Option<bool> {
+    value
+        .as_boolean()
+        .or_else(|| value.as_signed_integer().map(|value| value != 0))
+}
+
+/// A stored size in points, which the Dock itself writes as a real number.
+fn as_size(value: &plist::Value) -> Option<u32> {
+    match value {
+        plist::Value::Integer(value) => value.as_unsigned().and_then(|v| v.try_into().ok()),
+        plist::Value::Real(value) if *value >= 0.0 => Some(value.round() as u32),
+        _ => None,
+    }
+}
+
+/// Parses an on/off flag typed on the command line.
+fn parse_bool(setting: Setting, value: &str) -> Result<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "on" | "true" | "yes" | "1" => Ok(true),
+        "off" | "false" | "no" | "0" => Ok(false),
+        _ => bail!("{value:?} isn't a valid {setting}; use on or off"),
+    }
+}
+
+/// Checks a size is in `range`, which the Dock silently misbehaves outside of.
+fn check_size(setting: Setting, size: u32, range: &RangeInclusive<u32>) -> Result<u32> {
+    if !range.contains(&size) {
+        bail!(
+            "{setting} must be from {} to {}, not {size}",
+            range.start(),
+            range.end()
+        );
+    }
+    Ok(size)
+}
+
+/// A Dock preference whose values docktor checks before writing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Setting {
+    /// Which edge of the screen the Dock is on: left, bottom, or right.
+    Orientation,
+
+    /// Where along its edge the Dock sits: start, middle, or end.
+    Pinning,
+
+    /// The size of icons, from 16 to 128, or small, medium, or large.
+    Tilesize,
+
+    /// Whether the Dock hides until the pointer reaches its edge: on or off.
+    Autohide,
+
+    /// Whether icons grow as the pointer passes over them: on or off.
+    Magnification,
+
+    /// The size icons grow to when magnified, from 16 to 128.
+    Largesize,
+
+    /// Whether running apps get a dot under their icon: on or off.
+    ShowProcessIndicators,
+
+    /// Whether the icons of hidden apps are dimmed: on or off.
+    #[cfg_attr(feature = "cli", value(name = "showhidden"))]
+    ShowHidden,
+}
+
+impl Setting {
+    /// Every typed setting, in the order they are listed.
+    pub const ALL: [Setting; 8] = [
+        Setting::Orientation,
+        Setting::Pinning,
+        Setting::Tilesize,
+        Setting::Autohide,
+        Setting::Magnification,
+        Setting::Largesize,
+        Setting::ShowProcessIndicators,
+        Setting::ShowHidden,
+    ];
+
+    /// The `com.apple.dock` key the setting is stored under.
+    pub fn key(self) -> &'static str {
+        match self {
+            Setting::Orientation => "orientation",
+            Setting::Pinning => "pinning",
+            Setting::Tilesize => "tilesize",
+            Setting::Autohide => "autohide",
+            Setting::Magnification => "magnification",
+            Setting::Largesize => "largesize",
+            Setting::ShowProcessIndicators => "show-process-indicators",
+            Setting::ShowHidden => "showhidden",
+        }
+    }
+
+    /// The setting stored under `key`, if docktor knows it.
+    pub fn for_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|setting| setting.key() == key)
+    }
+
+    /// Parses a value typed on the command line into what the Dock stores.
+    pub fn parse(self, value: &str) -> Result<plist::Value> {
+        Ok(match self {
+            Setting::Orientation => keyword::<Orientation>(self, value)?.as_str().into(),
+            Setting::Pinning => keyword::<Pinning>(self, value)?.as_str().into(),
+            Setting::Tilesize => {
+                let size = match TilesizePreset::find(value) {
+                    Some(preset) => preset.points(),
+                    None => value.parse().map_err(|_| {
+                        anyhow::anyhow!(
+                            "{value:?} isn't a valid {self}; use a number, small, medium, or large"
+                        )
+                    })?,
+                };
+                check_size(self, size, &TILESIZE_RANGE)?.into()
+            }
+            Setting::Autohide
+            | Setting::Magnification
+            | Setting::ShowProcessIndicators
+            | Setting::ShowHidden => parse_bool(self, value)?.into(),
+            Setting::Largesize => {
+                let size = value
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("{value:?} isn't a valid {self}; use a number"))?;
+                check_size(self, size, &LARGESIZE_RANGE)?.into()
+            }
+        })
+    }
+
+    /// Checks a value from a profile, returning what the Dock should store for it, or
+    /// failing with why the Dock wouldn't accept it. Presets such as `tilesize: large`
+    /// become the number they stand for.
+    pub fn normalize(self, value: &plist::Value) -> Result<plist::Value> {
+        match self {
+            Setting::Orientation | Setting::Pinning => {
+                let Some(text) = value.as_string() else {
+                    bail!("{self} must be a string");
+                };
+                self.parse(text)
+            }
+            Setting::Tilesize => match value.as_string() {
+                Some(text) => self.parse(text),
+                None => {
+                    let size = as_size(value)
+                        .with_context(|| format!("{self} must be a number or a preset"))?;
+                    check_size(self, size, &TILESIZE_RANGE)?;
+                    Ok(value.clone())
+                }
+            },
+            Setting::Autohide
+            | Setting::Magnification
+            | Setting::ShowProcessIndicators
+            | Setting::ShowHidden => {
+                as_bool(value).with_context(|| format!("{self} must be true or false"))?;
+                Ok(value.clone())
+            }
+            Setting::Largesize => {
+                let size = as_size(value).with_context(|| format!("{self} must be a number"))?;
+                check_size(self, size, &LARGESIZE_RANGE)?;
+                Ok(value.clone())
+            }
+        }
+    }
+}
+
+impl fmt::Display for Setting {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.key())
+    }
+}
+
+impl Dock {
+    /// The Dock's typed settings.
+    pub fn typed_settings(&self) -> DockSettings {
+        DockSettings::of(self)
+    }
+
+    /// Turns an on/off setting on, off, or the other way round, returning whether it's
+    /// now on. Fails for settings that aren't on/off.
+    pub fn switch_setting(&mut self, setting: Setting, switch: Switch) -> Result<bool> {
+        let current = self
+            .typed_settings()
+            .flag(setting)
+            .with_context(|| format!("{setting} isn't an on/off setting"))?;
+        let on = switch.apply(current);
+        self.extra.insert(setting.key().to_string(), on.into());
+        Ok(on)
+    }
+
+    /// Sets `setting` from a command-line value, failing if the value isn't valid for it.
+    pub fn set_setting(&mut self, setting: Setting, value: &str) -> Result<()> {
+        let value = setting.parse(value)?;
+        self.extra.insert(setting.key().to_string(), value);
+        Ok(())
+    }
+}
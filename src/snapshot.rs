@@ -0,0 +1,396 @@
+//! Saved copies of the Dock plist to roll back to.
+//!
+//! Snapshots are stored gzip-compressed next to a `.sha256` file holding the
+//! checksum of the uncompressed plist, which is checked every time one is read.
+//! Uncompressed `.plist` snapshots from older versions are still read, unchecked.
+//! A snapshot can also be given a name and tags, kept in a `.json` file beside it.
+
+use crate::config::{self, BackupPolicy};
+use crate::dock::{AddPolicy, Dock, ItemRef, Position, Section};
+use anyhow::{Context, Result, bail};
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use time::macros::format_description;
+use time::{Duration, OffsetDateTime, PrimitiveDateTime};
+
+/// A saved copy of the Dock plist, identified by when it was taken.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    /// The snapshot identifier, e.g. `20240102-150405`.
+    pub id: String,
+
+    /// Where the snapshot's plist is stored: `<id>.plist.gz`, or `<id>.plist` for
+    /// snapshots taken before they were compressed.
+    pub path: PathBuf,
+
+    /// A name to look the snapshot up by, e.g. `pre-onboarding`, unique among snapshots.
+    pub name: Option<String>,
+
+    /// Tags to look the snapshot up by, e.g. `q3`; several snapshots can share one.
+    pub tags: Vec<String>,
+}
+
+/// A snapshot's name and tags, stored next to it as `<id>.json`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Labels {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+}
+
+/// The extension of compressed snapshots.
+const COMPRESSED_EXTENSION: &str = "plist.gz";
+
+impl Snapshot {
+    /// Returns the directory snapshots are stored in.
+    pub fn dir() -> Result<PathBuf> {
+        Ok(config::state_dir()?.join("snapshots"))
+    }
+
+    /// Copies the current Dock plist into a new snapshot.
+    pub fn take() -> Result<Self> {
+        Self::take_from(Dock::default_path()?)
+    }
+
+    /// Like [`Snapshot::take`], giving the snapshot a name and tags to look it up by.
+    ///
+    /// Fails before taking anything if another snapshot already has the name.
+    pub fn take_labeled(name: Option<String>, tags: Vec<String>) -> Result<Self> {
+        for label in name.iter().chain(&tags) {
+            if label.is_empty()
+                || !label
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'))
+            {
+                bail!(
+                    "{label:?} can't name or tag a snapshot; use letters, digits, dashes, \
+                     underscores, and dots"
+                );
+            }
+        }
+        if let Some(name) = &name
+            && Self::list()?
+                .iter()
+                .any(|snapshot| snapshot.name.as_ref() == Some(name))
+        {
+            bail!("A snapshot named {name} already exists");
+        }
+
+        let mut snapshot = Self::take()?;
+        if name.is_some() || !tags.is_empty() {
+            let labels = Labels { name, tags };
+            std::fs::write(snapshot.labels_path(), serde_json::to_string(&labels)?)
+                .with_context(|| format!("Failed to label snapshot {}", snapshot.id))?;
+            snapshot.name = labels.name;
+            snapshot.tags = labels.tags;
+        }
+        Ok(snapshot)
+    }
+
+    /// Like [`Snapshot::take_labeled`], unless the Dock plist is byte-for-byte the same
+    /// as the latest snapshot, in which case nothing is saved and `None` is returned.
+    pub fn take_labeled_if_changed(
+        name: Option<String>,
+        tags: Vec<String>,
+    ) -> Result<Option<Self>> {
+        if let Some(latest) = Self::latest()? {
+            let path = Dock::default_path()?;
+            let current = std::fs::read(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            if sha256(&latest.read()?) == sha256(&current) {
+                tracing::info!(id = latest.id, "Dock unchanged since the latest snapshot");
+                return Ok(None);
+            }
+        }
+        Self::take_labeled(name, tags).map(Some)
+    }
+
+    /// Copies the plist at `source` into a new snapshot.
+    pub fn take_from<P: AsRef<Path>>(source: P) -> Result<Self> {
+        let dir = Self::dir()?;
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create snapshot directory {}", dir.display()))?;
+
+        let stamp = OffsetDateTime::now_utc()
+            .format(format_description!(
+                "[year][month][day]-[hour][minute][second]"
+            ))
+            .context("Failed to format snapshot timestamp")?;
+
+        // Several snapshots can be taken within the same second.
+        let mut id = stamp.clone();
+        let mut n = 1;
+        while dir.join(format!("{id}.{COMPRESSED_EXTENSION}")).exists()
+            || dir.join(format!("{id}.plist")).exists()
+        {
+            n += 1;
+            id = format!("{stamp}-{n}");
+        }
+
+        let source = source.as_ref();
+        let plist = std::fs::read(source)
+            .with_context(|| format!("Failed to read {}", source.display()))?;
+        let snapshot = Snapshot {
+            path: dir.join(format!("{id}.{COMPRESSED_EXTENSION}")),
+            id,
+            name: None,
+            tags: Vec::new(),
+        };
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&plist)?;
+        std::fs::write(&snapshot.path, encoder.finish()?)
+            .with_context(|| format!("Failed to write snapshot {}", snapshot.path.display()))?;
+        std::fs::write(snapshot.checksum_path(), format!("{}\n", sha256(&plist)))
+            .with_context(|| format!("Failed to write the checksum of snapshot {}", snapshot.id))?;
+        tracing::info!(id = snapshot.id, path = %snapshot.path.display(), "took snapshot");
+
+        Ok(snapshot)
+    }
+
+    /// Lists all snapshots, oldest first.
+    pub fn list() -> Result<Vec<Self>> {
+        let dir = Self::dir()?;
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut snapshots = Vec::new();
+        for entry in std::fs::read_dir(&dir)
+            .with_context(|| format!("Failed to read snapshot directory {}", dir.display()))?
+        {
+            let path = entry?.path();
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let id = name
+                .strip_suffix(&format!(".{COMPRESSED_EXTENSION}"))
+                .or_else(|| name.strip_suffix(".plist"))
+                .map(str::to_string);
+            if let Some(id) = id {
+                let mut snapshot = Snapshot {
+                    id,
+                    path,
+                    name: None,
+                    tags: Vec::new(),
+                };
+                if let Ok(text) = std::fs::read_to_string(snapshot.labels_path()) {
+                    let labels: Labels = serde_json::from_str(&text).with_context(|| {
+                        format!("Failed to read the labels of snapshot {}", snapshot.id)
+                    })?;
+                    snapshot.name = labels.name;
+                    snapshot.tags = labels.tags;
+                }
+                snapshots.push(snapshot);
+            }
+        }
+
+        snapshots.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(snapshots)
+    }
+
+    /// Returns the most recent snapshot, if any.
+    pub fn latest() -> Result<Option<Self>> {
+        Ok(Self::list()?.pop())
+    }
+
+    /// Looks up a snapshot by identifier, by name, or by tag, which finds the newest
+    /// snapshot with that tag. `tag:<tag>` only looks at tags.
+    pub fn find(spec: &str) -> Result<Self> {
+        let snapshots = Self::list()?;
+        let tagged = |tag: &str| {
+            snapshots
+                .iter()
+                .rev()
+                .find(|s| s.tags.iter().any(|t| t == tag))
+                .cloned()
+        };
+        let found = match spec.strip_prefix("tag:") {
+            Some(tag) => tagged(tag),
+            None => snapshots
+                .iter()
+                .find(|s| s.id == spec || s.name.as_deref() == Some(spec))
+                .cloned()
+                .or_else(|| tagged(spec)),
+        };
+        found.with_context(|| format!("No snapshot has the id, name, or tag {spec}"))
+    }
+
+    /// When the snapshot was taken, read from its identifier.
+    pub fn taken_at(&self) -> Option<OffsetDateTime> {
+        let stamp = self.id.get(..15)?;
+        PrimitiveDateTime::parse(
+            stamp,
+            format_description!("[year][month][day]-[hour][minute][second]"),
+        )
+        .ok()
+        .map(PrimitiveDateTime::assume_utc)
+    }
+
+    /// The snapshots `policy`'s retention limits would delete, oldest first.
+    ///
+    /// The newest snapshot is always kept, however old it is, and named snapshots are
+    /// never deleted or counted towards `max_backups`.
+    pub fn expired(policy: &BackupPolicy) -> Result<Vec<Self>> {
+        let mut snapshots = Self::list()?;
+        snapshots.retain(|snapshot| snapshot.name.is_none());
+        snapshots.pop();
+
+        let keep = policy.max_backups.unwrap_or(usize::MAX).saturating_sub(1);
+        let over = snapshots.len().saturating_sub(keep);
+        // An age too great to subtract from now is no limit at all.
+        let cutoff = policy.max_age_days.and_then(|days| {
+            i64::try_from(days)
+                .ok()
+                .and_then(|days| days.checked_mul(Duration::DAY.whole_seconds()))
+                .and_then(|seconds| {
+                    OffsetDateTime::now_utc().checked_sub(Duration::seconds(seconds))
+                })
+        });
+        Ok(snapshots
+            .into_iter()
+            .enumerate()
+            .filter(|(index, snapshot)| {
+                *index < over
+                    || cutoff.is_some_and(|cutoff| {
+                        snapshot.taken_at().is_some_and(|taken| taken < cutoff)
+                    })
+            })
+            .map(|(_, snapshot)| snapshot)
+            .collect())
+    }
+
+    /// Deletes the snapshots `policy`'s retention limits expire, returning them.
+    pub fn prune(policy: &BackupPolicy) -> Result<Vec<Self>> {
+        let expired = Self::expired(policy)?;
+        for snapshot in &expired {
+            snapshot.delete()?;
+            tracing::info!(id = snapshot.id, "pruned snapshot");
+        }
+        Ok(expired)
+    }
+
+    /// Deletes the snapshot and its checksum.
+    pub fn delete(&self) -> Result<()> {
+        std::fs::remove_file(&self.path)
+            .with_context(|| format!("Failed to delete snapshot {}", self.path.display()))?;
+        for path in [self.checksum_path(), self.labels_path()] {
+            match std::fs::remove_file(&path) {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                    return Err(err)
+                        .with_context(|| format!("Failed to delete {}", path.display()));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the snapshot is stored compressed, with a checksum.
+    pub fn is_compressed(&self) -> bool {
+        self.path.to_string_lossy().ends_with(COMPRESSED_EXTENSION)
+    }
+
+    /// Reads the snapshot's plist, decompressing it and checking it against its
+    /// recorded checksum.
+    ///
+    /// Fails if the checksum is missing or doesn't match, so a corrupted snapshot is
+    /// never restored.
+    pub fn read(&self) -> Result<Vec<u8>> {
+        let data = std::fs::read(&self.path)
+            .with_context(|| format!("Failed to read snapshot {}", self.path.display()))?;
+        if !self.is_compressed() {
+            return Ok(data);
+        }
+
+        let mut plist = Vec::new();
+        GzDecoder::new(data.as_slice())
+            .read_to_end(&mut plist)
+            .with_context(|| format!("Snapshot {} is corrupted", self.id))?;
+        let recorded = std::fs::read_to_string(self.checksum_path()).with_context(|| {
+            format!("Snapshot {} has no checksum to verify it against", self.id)
+        })?;
+        let actual = sha256(&plist);
+        if recorded.trim() != actual {
+            bail!(
+                "Snapshot {} is corrupted: its checksum is {actual}, but {} was recorded",
+                self.id,
+                recorded.trim()
+            );
+        }
+        Ok(plist)
+    }
+
+    /// Checks the snapshot against its recorded checksum without parsing it.
+    pub fn verify(&self) -> Result<()> {
+        self.read().map(drop)
+    }
+
+    /// Parses the Dock stored in this snapshot, after checking its checksum.
+    pub fn load(&self) -> Result<Dock> {
+        plist::from_bytes(&self.read()?)
+            .with_context(|| format!("Failed to parse snapshot {}", self.id))
+    }
+
+    /// Where the checksum of the snapshot's plist is recorded.
+    fn checksum_path(&self) -> PathBuf {
+        self.path.with_file_name(format!("{}.sha256", self.id))
+    }
+
+    /// Where the snapshot's name and tags are recorded.
+    fn labels_path(&self) -> PathBuf {
+        self.path.with_file_name(format!("{}.json", self.id))
+    }
+}
+
+impl Dock {
+    /// Puts the item `item` names in `from`, usually a snapshot, back into this Dock
+    /// where it was in `from`, returning where it went.
+    ///
+    /// It lands right after the closest item that preceded it in `from` and is still
+    /// here, or at the start of its section if none is. Fails if `from` has no such
+    /// item or this Dock already has it.
+    pub fn restore_item(&mut self, from: &Dock, item: &ItemRef) -> Result<(Section, usize)> {
+        let (section, index) = from
+            .locate(item)
+            .with_context(|| format!("The snapshot has no item {item}"))?;
+        let saved = &from.section(section)[index];
+        let here = self.section(section);
+        if !saved.is_spacer()
+            && here
+                .iter()
+                .any(|existing| existing.identity() == saved.identity())
+        {
+            bail!("{} is already in the Dock", saved.label());
+        }
+
+        let position = from.section(section)[..index]
+            .iter()
+            .rev()
+            .filter(|before| !before.is_spacer())
+            .find_map(|before| {
+                here.iter()
+                    .position(|existing| existing.identity() == before.identity())
+            })
+            .map_or(0, |at| at + 1);
+        self.add_at(
+            section,
+            saved.clone(),
+            &Position::Index(position),
+            AddPolicy::AllowDuplicate,
+        )?;
+        Ok((section, position))
+    }
+}
+
+/// The SHA-256 of `bytes`, in lowercase hex.
+pub(crate) fn sha256(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
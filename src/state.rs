@@ -0,0 +1,153 @@
+//! Remembering what docktor last wrote, to notice changes made outside it.
+//!
+//! Every [`Dock::save`] records a fingerprint of the Dock and a copy of it in the
+//! state directory. Comparing the live Dock against that record tells whether the
+//! user (or another tool) changed the Dock since, and what they changed.
+//!
+//! Applying a profile also keeps a copy of the Dock it left behind, which
+//! [merges](crate::merge) use as their base.
+
+use crate::config;
+use crate::diff::DockDiff;
+use crate::dock::{Dock, Section};
+use crate::settings::Setting;
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// What happened to the Dock since docktor last wrote it.
+#[derive(Debug, Clone)]
+pub enum ExternalChanges {
+    /// docktor has never written this Dock, so there is nothing to compare against.
+    Unknown,
+
+    /// The Dock is as docktor left it.
+    Unchanged,
+
+    /// The Dock was changed outside docktor; the diff goes from what docktor wrote to the live Dock.
+    Modified(DockDiff),
+}
+
+/// Where the fingerprint of the last save is stored.
+fn hash_file() -> Result<PathBuf> {
+    Ok(config::state_dir()?.join("last-save.hash"))
+}
+
+/// Where a copy of the last saved Dock is stored.
+fn dock_file() -> Result<PathBuf> {
+    Ok(config::state_dir()?.join("last-save.plist"))
+}
+
+/// Where a copy of the Dock as the last profile applied left it is stored.
+fn applied_file() -> Result<PathBuf> {
+    Ok(config::state_dir()?.join("last-applied.plist"))
+}
+
+/// A stable fingerprint of a Dock's contents, as a hex string.
+///
+/// Only what docktor manages goes in: each tile's kind, identity, and label, and the
+/// user settings. Bookkeeping the Dock rewrites on its own, such as tile GUIDs and
+/// `mod-count`, is left out so a restart doesn't look like an edit.
+pub fn fingerprint(dock: &Dock) -> String {
+    let sections: Vec<Vec<(&str, String, &str)>> = Section::ALL
+        .into_iter()
+        .map(|section| {
+            dock.section(section)
+                .iter()
+                .map(|item| (item.kind.name(), item.identity().into_owned(), item.label()))
+                .collect()
+        })
+        .collect();
+    let settings: BTreeMap<&String, serde_json::Value> = dock
+        .settings()
+        .map(|(key, value)| (key, serde_json::to_value(value).unwrap_or_default()))
+        .collect();
+    let content = serde_json::json!({ "sections": sections, "settings": settings });
+
+    format!("{:016x}", fnv1a(content.to_string().as_bytes()))
+}
+
+/// 64-bit FNV-1a: short and fast, and enough to tell two Docks apart.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Records `dock`, just written, as what docktor last wrote.
+///
+/// The copy is serialized from `dock` rather than taken from the plist on disk, which
+/// the [`Defaults`](crate::config::Backend::Defaults) backend may not have updated yet.
+pub(crate) fn record(dock: &Dock) -> Result<()> {
+    let file = hash_file()?;
+    if let Some(dir) = file.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    dock.save_to(dock_file()?)?;
+    std::fs::write(&file, fingerprint(dock))
+        .with_context(|| format!("Failed to write {}", file.display()))
+}
+
+/// Updates the record of the last save after docktor changed `autohide` without
+/// writing the plist, so its own [live autohide](crate::visibility) doesn't look like an edit.
+pub(crate) fn record_autohide(hidden: bool) -> Result<()> {
+    let Some(mut saved) = last_saved()? else {
+        return Ok(());
+    };
+    saved
+        .extra
+        .insert(Setting::Autohide.key().to_string(), hidden.into());
+    record(&saved)
+}
+
+/// The Dock as docktor last wrote it, if it has written one.
+pub fn last_saved() -> Result<Option<Dock>> {
+    let file = dock_file()?;
+    if !file.exists() {
+        return Ok(None);
+    }
+    Dock::load_from(&file).map(Some)
+}
+
+/// Records `dock` as the result of the last profile applied, the base for merges.
+pub(crate) fn record_applied(dock: &Dock) -> Result<()> {
+    let file = applied_file()?;
+    if let Some(dir) = file.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    dock.save_to(file)
+}
+
+/// The Dock as the last profile applied left it, if one has been applied.
+pub fn last_applied() -> Result<Option<Dock>> {
+    let file = applied_file()?;
+    if !file.exists() {
+        return Ok(None);
+    }
+    Dock::load_from(&file).map(Some)
+}
+
+/// Compares `live` against what docktor last wrote.
+pub fn external_changes(live: &Dock) -> Result<ExternalChanges> {
+    let file = hash_file()?;
+    if !file.exists() {
+        return Ok(ExternalChanges::Unknown);
+    }
+    let recorded = std::fs::read_to_string(&file)
+        .with_context(|| format!("Failed to read {}", file.display()))?;
+    if recorded.trim() == fingerprint(live) {
+        return Ok(ExternalChanges::Unchanged);
+    }
+
+    let Some(saved) = last_saved()? else {
+        return Ok(ExternalChanges::Unknown);
+    };
+    let diff = DockDiff::between(&saved, live);
+    Ok(if diff.is_empty() {
+        ExternalChanges::Unchanged
+    } else {
+        ExternalChanges::Modified(diff)
+    })
+}
@@ -0,0 +1,122 @@
+//! Counts and sizes describing the Dock and docktor's storage, for `docktor stats`
+//! and fleet reporting.
+
+use crate::dock::{Dock, Section};
+use crate::snapshot::Snapshot;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Statistics about the live Dock.
+#[derive(Debug, Serialize)]
+pub struct Stats {
+    /// The Dock preferences plist.
+    pub plist_path: PathBuf,
+
+    /// The plist's size in bytes.
+    pub plist_bytes: u64,
+
+    /// Tiles by kind, e.g. `file-tile`, across both sections.
+    pub kinds: BTreeMap<&'static str, usize>,
+
+    /// Apps by where they are installed: `system` (`/System/Applications`), `local`
+    /// (`/Applications`), `user` (`~/Applications`), or `other`.
+    pub app_sources: BTreeMap<&'static str, usize>,
+
+    /// The user settings in the plist, as JSON values.
+    pub settings: serde_json::Map<String, serde_json::Value>,
+
+    /// The snapshots kept, and the space they take up.
+    pub backups: BackupUsage,
+}
+
+/// How many snapshots there are and how much space they use.
+#[derive(Debug, Default, Serialize)]
+pub struct BackupUsage {
+    /// The number of snapshots.
+    pub count: usize,
+
+    /// Their total size on disk in bytes, checksums and labels included.
+    pub bytes: u64,
+
+    /// The oldest snapshot's id.
+    pub oldest: Option<String>,
+
+    /// The newest snapshot's id.
+    pub newest: Option<String>,
+}
+
+impl Stats {
+    /// Gathers statistics about the live Dock.
+    pub fn collect() -> Result<Self> {
+        let plist_path = Dock::default_path()?;
+        let dock = Dock::load_from(&plist_path)?;
+        let plist_bytes = std::fs::metadata(&plist_path)
+            .with_context(|| format!("Failed to read {}", plist_path.display()))?
+            .len();
+        let home = crate::paths::home_dir();
+
+        let mut kinds = BTreeMap::new();
+        for section in Section::ALL {
+            for item in dock.section(section) {
+                *kinds.entry(item.kind.name()).or_default() += 1;
+            }
+        }
+        let mut app_sources = BTreeMap::new();
+        for item in dock.section(Section::Apps) {
+            if let Some(path) = item.path() {
+                *app_sources
+                    .entry(app_source(&path, home.as_deref()))
+                    .or_default() += 1;
+            }
+        }
+
+        Ok(Stats {
+            plist_path,
+            plist_bytes,
+            kinds,
+            app_sources,
+            settings: dock
+                .settings()
+                .map(|(key, value)| (key.clone(), serde_json::to_value(value).unwrap_or_default()))
+                .collect(),
+            backups: BackupUsage::collect()?,
+        })
+    }
+}
+
+impl BackupUsage {
+    /// Measures the snapshot directory.
+    pub fn collect() -> Result<Self> {
+        let snapshots = Snapshot::list()?;
+        let dir = Snapshot::dir()?;
+        let bytes = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok()?.metadata().ok())
+                .filter(|metadata| metadata.is_file())
+                .map(|metadata| metadata.len())
+                .sum(),
+            Err(_) => 0,
+        };
+        Ok(BackupUsage {
+            count: snapshots.len(),
+            bytes,
+            oldest: snapshots.first().map(|snapshot| snapshot.id.clone()),
+            newest: snapshots.last().map(|snapshot| snapshot.id.clone()),
+        })
+    }
+}
+
+/// Which kind of application folder `path` is in.
+fn app_source(path: &Path, home: Option<&Path>) -> &'static str {
+    if path.starts_with("/System/Applications") {
+        "system"
+    } else if path.starts_with("/Applications") {
+        "local"
+    } else if home.is_some_and(|home| path.starts_with(home.join("Applications"))) {
+        "user"
+    } else {
+        "other"
+    }
+}
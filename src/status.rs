@@ -0,0 +1,132 @@
+use crate::config;
+use crate::diff::DockDiff;
+use crate::dock::{Dock, Section};
+use crate::profile::Profile;
+use crate::snapshot::Snapshot;
+use crate::state::{self, ExternalChanges};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// A summary of the Dock and docktor's view of it, for `docktor status`.
+#[derive(Debug, Serialize)]
+pub struct Status {
+    /// The Dock preferences plist.
+    pub plist_path: PathBuf,
+
+    /// Number of tiles in the apps section, spacers included.
+    pub apps: usize,
+
+    /// Number of tiles in the others section, spacers included.
+    pub others: usize,
+
+    /// The user settings in the plist, as JSON values.
+    pub settings: serde_json::Map<String, serde_json::Value>,
+
+    /// The id of the most recent snapshot, if any.
+    pub last_backup: Option<String>,
+
+    /// The profile most recently applied, if it is still on disk.
+    pub active_profile: Option<PathBuf>,
+
+    /// Whether the live Dock differs from the active profile; unset without one.
+    pub drifted: Option<bool>,
+
+    /// Whether the Dock was changed outside docktor since it last wrote it; unset
+    /// if docktor hasn't written it yet.
+    pub modified_externally: Option<bool>,
+
+    /// Bundle identifiers with tiles for more than one copy of the app.
+    pub duplicate_copies: Vec<String>,
+
+    /// The Dock process id, if it is running.
+    pub dock_pid: Option<u32>,
+}
+
+impl Status {
+    /// Gathers the status of the live Dock.
+    #[tracing::instrument]
+    pub fn collect() -> Result<Self> {
+        let plist_path = Dock::default_path()?;
+        let dock = Dock::load_from(&plist_path)?;
+
+        let active_profile = active_profile()?.filter(|path| path.exists());
+        // A profile that no longer resolves shouldn't stop the rest of the report.
+        let drifted = active_profile.as_ref().and_then(|path| {
+            let desired = Profile::load(path).and_then(|profile| profile.apply_to(&dock));
+            match desired {
+                Ok(desired) => Some(!DockDiff::between(&dock, &desired).is_empty()),
+                Err(err) => {
+                    tracing::warn!("Could not check drift: {err:#}");
+                    None
+                }
+            }
+        });
+
+        let modified_externally = match state::external_changes(&dock)? {
+            ExternalChanges::Unknown => None,
+            ExternalChanges::Unchanged => Some(false),
+            ExternalChanges::Modified(_) => Some(true),
+        };
+
+        Ok(Status {
+            apps: dock.section(Section::Apps).len(),
+            others: dock.section(Section::Others).len(),
+            settings: dock
+                .settings()
+                .map(|(key, value)| (key.clone(), serde_json::to_value(value).unwrap_or_default()))
+                .collect(),
+            last_backup: Snapshot::latest()?.map(|snapshot| snapshot.id),
+            active_profile,
+            drifted,
+            modified_externally,
+            duplicate_copies: dock
+                .duplicate_copies()
+                .into_iter()
+                .map(|copies| copies.bundle_id)
+                .collect(),
+            dock_pid: dock_pid(),
+            plist_path,
+        })
+    }
+}
+
+/// Where the path of the most recently applied profile is recorded.
+fn active_profile_file() -> Result<PathBuf> {
+    Ok(config::state_dir()?.join("active-profile"))
+}
+
+/// The profile most recently applied with [`Profile::apply`], if any.
+pub fn active_profile() -> Result<Option<PathBuf>> {
+    let file = active_profile_file()?;
+    if !file.exists() {
+        return Ok(None);
+    }
+    let path = std::fs::read_to_string(&file)
+        .with_context(|| format!("Failed to read {}", file.display()))?;
+    Ok(Some(PathBuf::from(path.trim())))
+}
+
+/// Records `path` as the active profile.
+pub(crate) fn set_active_profile(path: &Path) -> Result<()> {
+    let file = active_profile_file()?;
+    if let Some(dir) = file.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    std::fs::write(&file, path.to_string_lossy().as_bytes())
+        .with_context(|| format!("Failed to write {}", file.display()))
+}
+
+/// The process id of the Dock, via `pgrep`.
+fn dock_pid() -> Option<u32> {
+    let output = Command::new("pgrep").args(["-x", "Dock"]).output().ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()?
+        .trim()
+        .parse()
+        .ok()
+}
@@ -0,0 +1,206 @@
+use crate::config;
+use crate::dock::Dock;
+use crate::profile::Profile;
+use anyhow::{Context, Result, bail};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Where the commit of the last push or pull is recorded, relative to the repository.
+const LAST_SYNC_FILE: &str = ".git/docktor-last-sync";
+
+/// A git checkout that Dock profiles are synced through.
+#[derive(Debug, Clone)]
+pub struct SyncRepo {
+    /// The root of the git working tree.
+    pub path: PathBuf,
+
+    /// The profile file within the repository, e.g. `dock.yaml`.
+    pub file: PathBuf,
+}
+
+/// The result of pushing the live Dock.
+#[derive(Debug, PartialEq, Eq)]
+pub enum PushOutcome {
+    /// The profile already matched the live Dock.
+    UpToDate,
+
+    /// A new commit was created and pushed.
+    Pushed,
+}
+
+impl SyncRepo {
+    /// Returns the default location of the sync checkout.
+    pub fn default_path() -> Result<PathBuf> {
+        Ok(config::state_dir()?.join("sync"))
+    }
+
+    /// Clones `url` into `path` and returns the new checkout.
+    pub fn clone_from(url: &str, path: &Path, file: &Path) -> Result<Self> {
+        let status = Command::new("git")
+            .arg("clone")
+            .arg(url)
+            .arg(path)
+            .status()
+            .context("Failed to run git clone")?;
+        if !status.success() {
+            bail!("git clone {url} failed");
+        }
+        Self::open(path, file)
+    }
+
+    /// Opens an existing git checkout.
+    pub fn open(path: &Path, file: &Path) -> Result<Self> {
+        if !path.join(".git").exists() {
+            bail!(
+                "{} is not a git repository; run `docktor sync init <url>` first",
+                path.display()
+            );
+        }
+        Ok(SyncRepo {
+            path: path.to_path_buf(),
+            file: file.to_path_buf(),
+        })
+    }
+
+    /// Commits the live Dock as the profile and pushes it.
+    ///
+    /// Fails if the remote profile changed since the last sync, unless `force` is set.
+    #[tracing::instrument(skip_all, fields(repo = %self.path.display(), force = force))]
+    pub fn push(&self, live: &Dock, force: bool) -> Result<PushOutcome> {
+        let has_upstream = self.has_upstream();
+        if has_upstream {
+            self.git(&["fetch", "--quiet"])?;
+            if !force && self.upstream_changed()? {
+                bail!(
+                    "The synced profile changed on another machine; run `docktor sync pull` first or pass --force"
+                );
+            }
+            if force {
+                // Our Dock wins; base the new commit on whatever the remote has.
+                self.git(&["reset", "--quiet", "--soft", "@{u}"])?;
+            } else {
+                self.git(&["merge", "--quiet", "--ff-only", "@{u}"])?;
+            }
+        }
+
+        let profile = Profile::from_dock(live);
+        let target = self.path.join(&self.file);
+        std::fs::write(&target, profile.to_yaml()?)
+            .with_context(|| format!("Failed to write {}", target.display()))?;
+
+        let file = self.file.to_string_lossy();
+        if self
+            .git(&["status", "--porcelain", "--", &file])?
+            .is_empty()
+        {
+            self.mark_synced()?;
+            return Ok(PushOutcome::UpToDate);
+        }
+
+        let host = crate::macos::hostname();
+        self.git(&["add", "--", &file])?;
+        self.git(&[
+            "commit",
+            "--quiet",
+            "-m",
+            &format!("Update Dock from {host}"),
+            "--",
+            &file,
+        ])?;
+        if has_upstream {
+            self.git(&["push", "--quiet"])?;
+        } else {
+            self.git(&["push", "--quiet", "--set-upstream", "origin", "HEAD"])?;
+        }
+        self.mark_synced()?;
+        Ok(PushOutcome::Pushed)
+    }
+
+    /// Pulls the latest profile.
+    ///
+    /// Fails if the live Dock changed since the last sync and the remote profile
+    /// changed too, unless `force` is set. Call [`SyncRepo::mark_synced`] once the
+    /// profile has been applied.
+    #[tracing::instrument(skip_all, fields(repo = %self.path.display(), force = force))]
+    pub fn pull(&self, live: &Dock, force: bool) -> Result<Profile> {
+        if self.has_upstream() {
+            self.git(&["fetch", "--quiet"])?;
+            if !force && self.upstream_changed()? && self.live_changed(live)? {
+                bail!(
+                    "Both the live Dock and the synced profile changed since the last sync; \
+                     run `docktor sync push --force` to keep this Dock or `docktor sync pull --force` to take the profile"
+                );
+            }
+            self.git(&["merge", "--quiet", "--ff-only", "@{u}"])?;
+        }
+
+        Profile::load(self.path.join(&self.file))
+    }
+
+    /// Whether the current branch tracks a remote branch yet.
+    fn has_upstream(&self) -> bool {
+        self.git(&["rev-parse", "--abbrev-ref", "@{u}"]).is_ok()
+    }
+
+    /// Whether the remote has profile commits we haven't synced.
+    fn upstream_changed(&self) -> Result<bool> {
+        let file = self.file.to_string_lossy();
+        let base = self.last_sync()?.unwrap_or_else(|| "HEAD".to_string());
+        let log = self.git(&[
+            "log",
+            "--format=%H",
+            &format!("{base}..@{{u}}"),
+            "--",
+            &file,
+        ])?;
+        Ok(!log.is_empty())
+    }
+
+    /// Whether the live Dock differs from the profile as of the last sync.
+    fn live_changed(&self, live: &Dock) -> Result<bool> {
+        let Some(base) = self.last_sync()? else {
+            return Ok(false);
+        };
+        let file = self.file.to_string_lossy();
+        let yaml = self.git(&["show", &format!("{base}:{file}")])?;
+        let synced: Profile =
+            serde_yaml::from_str(&yaml).context("Failed to parse the last synced profile")?;
+        Ok(Profile::from_dock(live) != synced)
+    }
+
+    fn last_sync(&self) -> Result<Option<String>> {
+        let path = self.path.join(LAST_SYNC_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let commit = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Ok(Some(commit.trim().to_string()))
+    }
+
+    /// Records the current commit as the last one synced with the live Dock.
+    pub fn mark_synced(&self) -> Result<()> {
+        let head = self.git(&["rev-parse", "HEAD"])?;
+        let path = self.path.join(LAST_SYNC_FILE);
+        std::fs::write(&path, head).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Runs git in the repository, returning trimmed stdout.
+    fn git(&self, args: &[&str]) -> Result<String> {
+        tracing::debug!(repo = %self.path.display(), ?args, "running git");
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&self.path)
+            .args(args)
+            .output()
+            .context("Failed to run git")?;
+        if !output.status.success() {
+            bail!(
+                "git {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+}
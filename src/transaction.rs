@@ -0,0 +1,345 @@
+use crate::audit;
+use crate::cancel::CancellationToken;
+use crate::config::{Backend, Config, ConflictPolicy, PlistFormat};
+use crate::diff::DockDiff;
+use crate::dock::{AddPolicy, Dock, DockItem, ItemRef, PlistStamp, Position, Section};
+use crate::hooks::{self, Event, Hooks};
+use crate::mac_app::MacApp;
+use crate::profile::ApplyOptions;
+use crate::progress::{Progress, Step};
+use crate::snapshot::Snapshot;
+use anyhow::{Context, Result, bail};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// A batch of changes to the Dock, staged in memory by [`Dock::transaction`].
+#[derive(Debug)]
+pub struct Transaction {
+    dock: Dock,
+    changed: bool,
+}
+
+impl Transaction {
+    /// The Dock as it stands with the changes staged so far.
+    pub fn dock(&self) -> &Dock {
+        &self.dock
+    }
+
+    /// Appends an item to the given section, following `policy` if it is already there.
+    pub fn add(&mut self, section: Section, item: DockItem, policy: AddPolicy) {
+        self.changed |= self.dock.add(section, item, policy);
+    }
+
+    /// Appends the `.app` bundle at `path` to the apps section.
+    pub fn add_app<P: AsRef<Path>>(&mut self, path: P, policy: AddPolicy) -> Result<()> {
+        let app = MacApp::from_path(path)?;
+        self.changed |= self.dock.add_app(&app, policy);
+        Ok(())
+    }
+
+    /// Removes every item whose label or bundle identifier matches `query`.
+    ///
+    /// Fails if nothing matches, which aborts the transaction.
+    pub fn remove(&mut self, query: &str) -> Result<usize> {
+        let removed = self.dock.remove(query);
+        if removed == 0 {
+            bail!("No Dock item matches {query:?}");
+        }
+        self.changed = true;
+        Ok(removed)
+    }
+
+    /// Replaces the item matching `query` with `item`, keeping its position.
+    ///
+    /// Fails if nothing matches, which aborts the transaction.
+    pub fn replace(&mut self, query: &str, item: DockItem) -> Result<()> {
+        if !self.dock.replace(query, item) {
+            bail!("No Dock item matches {query:?}");
+        }
+        self.changed = true;
+        Ok(())
+    }
+
+    /// Changes the label of the item matching `query`.
+    ///
+    /// Fails if nothing matches, which aborts the transaction.
+    pub fn rename(&mut self, query: &str, label: &str) -> Result<()> {
+        if !self.dock.rename(query, label) {
+            bail!("No Dock item matches {query:?}");
+        }
+        self.changed = true;
+        Ok(())
+    }
+
+    /// Swaps two items in the same section.
+    pub fn swap(&mut self, a: &ItemRef, b: &ItemRef) -> Result<()> {
+        self.dock.swap(a, b)?;
+        self.changed = true;
+        Ok(())
+    }
+
+    /// Moves an item to `position`, optionally into the other section.
+    ///
+    /// Fails if the move isn't possible, which aborts the transaction.
+    pub fn move_to(
+        &mut self,
+        item: &ItemRef,
+        position: &Position,
+        section: Option<Section>,
+    ) -> Result<(Section, usize)> {
+        let moved = self.dock.move_to(item, position, section)?;
+        self.changed = true;
+        Ok(moved)
+    }
+
+    /// Sets a top-level setting such as `autohide` or `tilesize`.
+    pub fn set(&mut self, key: &str, value: impl Into<plist::Value>) {
+        self.dock.extra.insert(key.to_string(), value.into());
+        self.changed = true;
+    }
+}
+
+impl Dock {
+    /// Applies a batch of changes to the live Dock all at once.
+    ///
+    /// `f` stages changes on a copy of the live Dock. If it returns an error nothing is
+    /// written; otherwise a snapshot is taken, the Dock is saved, and it is restarted
+    /// once, as far as the [`Config`] allows. Returns the resulting Dock.
+    #[tracing::instrument(skip_all)]
+    pub fn transaction<F>(f: F) -> Result<Dock>
+    where
+        F: FnOnce(&mut Transaction) -> Result<()>,
+    {
+        let live = Dock::load()?;
+        let mut tx = Transaction {
+            dock: live.clone(),
+            changed: false,
+        };
+        f(&mut tx)?;
+
+        if tx.changed {
+            let config = Config::current();
+            if let Some(rebased) = rebase(&live, &DockDiff::between(&live, &tx.dock))? {
+                tx.dock = rebased;
+            }
+            if config.backup.enabled {
+                Snapshot::take()?;
+            }
+            tx.dock.save()?;
+            log_write(&DockDiff::between(&live, &tx.dock), None);
+            prune_backups(config);
+            if config.restarts(false) {
+                Dock::restart()?;
+            }
+        } else {
+            tracing::info!("transaction made no changes");
+        }
+        Ok(tx.dock)
+    }
+}
+
+/// Replaces the live Dock with `desired`, returning what changed relative to `live`.
+///
+/// A snapshot of the current Dock is taken before anything is written unless the
+/// [`Config`] turns backups off, and nothing is written when the two already match
+/// unless [`ApplyOptions::force`] is set.
+/// If the plist no longer holds `live` by the time it is written, the write fails or
+/// the changes are replayed on it, following `on_conflict`.
+pub fn write_changes(live: &Dock, desired: &Dock, options: ApplyOptions) -> Result<DockDiff> {
+    write_changes_with(
+        live,
+        desired,
+        options,
+        &Hooks::default(),
+        None,
+        &(),
+        &CancellationToken::new(),
+    )
+}
+
+/// Like [`write_changes`], also running a profile's `pre_apply` and `post_apply` hooks
+/// and reporting the write and restart to `progress`.
+///
+/// `cancel` is checked before the hooks run and again before the plist is written.
+///
+/// The config file's hooks run first. A failing `pre_apply` command aborts before
+/// anything is written; a failing `post_apply` command is only logged.
+pub fn write_changes_with(
+    live: &Dock,
+    desired: &Dock,
+    options: ApplyOptions,
+    hooks: &Hooks,
+    profile: Option<&str>,
+    progress: &dyn Progress,
+    cancel: &CancellationToken,
+) -> Result<DockDiff> {
+    let diff = DockDiff::between(live, desired);
+    tracing::info!(changes = diff.changes.len(), "computed changes");
+
+    if options.dry_run || (diff.is_empty() && !options.force) {
+        return Ok(diff);
+    }
+
+    cancel.check()?;
+    let config = Config::current();
+    hooks::run_commands(
+        config.hooks.pre_apply.iter().chain(&hooks.pre_apply),
+        Event::PreApply,
+        profile,
+        &diff,
+    )
+    .context("A pre_apply hook failed; nothing was changed")?;
+    cancel.check()?;
+    let rebased = rebase(live, &diff)?;
+    let desired = rebased.as_ref().unwrap_or(desired);
+    if config.backup.enabled {
+        Snapshot::take().context("Failed to back up the Dock before applying")?;
+    }
+    progress.step(Step::Writing);
+    desired.save()?;
+    log_write(&diff, profile);
+    prune_backups(config);
+    if config.restarts(options.no_restart) {
+        progress.step(Step::Restarting);
+        Dock::restart()?;
+    }
+    if let Err(err) = hooks::run_commands(
+        config.hooks.post_apply.iter().chain(&hooks.post_apply),
+        Event::PostApply,
+        profile,
+        &diff,
+    ) {
+        tracing::warn!("{err:#}");
+    }
+    Ok(diff)
+}
+
+/// What writing a diff does besides changing the Dock, for dry-run reports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SideEffects {
+    /// Whether anything would be written at all.
+    pub writes: bool,
+
+    /// The plist that would be written.
+    pub plist: PathBuf,
+
+    /// How the plist would be written.
+    pub backend: Backend,
+
+    /// How the plist would be encoded, when it is written as a file.
+    pub format: PlistFormat,
+
+    /// The folder a snapshot would be taken into first, or `None` when backups are off.
+    pub backup: Option<PathBuf>,
+
+    /// Whether the Dock would be restarted afterwards.
+    pub restart: bool,
+
+    /// How many `pre_apply` and `post_apply` commands would run, from the config file
+    /// and `hooks`.
+    pub hooks: usize,
+}
+
+impl SideEffects {
+    /// Works out what [`write_changes_with`] would do with `diff`, were `options` not a
+    /// dry run.
+    pub fn of(diff: &DockDiff, options: ApplyOptions, hooks: &Hooks) -> Result<Self> {
+        let config = Config::current();
+        Ok(SideEffects {
+            writes: !diff.is_empty() || options.force,
+            plist: Dock::default_path()?,
+            backend: config.backend,
+            format: config.plist_format,
+            backup: config.backup.enabled.then(Snapshot::dir).transpose()?,
+            restart: config.restarts(options.no_restart),
+            hooks: config.hooks.pre_apply.len()
+                + config.hooks.post_apply.len()
+                + hooks.pre_apply.len()
+                + hooks.post_apply.len(),
+        })
+    }
+}
+
+impl fmt::Display for SideEffects {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.writes {
+            return writeln!(f, "Nothing would be written.");
+        }
+        writeln!(f, "Writing these changes would:")?;
+        match &self.backup {
+            Some(dir) => writeln!(f, "  snapshot the Dock into {}", dir.display())?,
+            None => writeln!(f, "  skip the snapshot, since backups are off")?,
+        }
+        let format = match self.format {
+            PlistFormat::Binary => "binary",
+            PlistFormat::Xml => "XML",
+        };
+        match self.backend {
+            Backend::File => writeln!(f, "  write {} as a {format} plist", self.plist.display())?,
+            Backend::Defaults => writeln!(
+                f,
+                "  write {} through /usr/bin/defaults",
+                crate::defaults::domain()
+            )?,
+        }
+        if self.restart {
+            writeln!(f, "  restart the Dock")?;
+        } else {
+            writeln!(
+                f,
+                "  leave the Dock running; changes show up when it next starts"
+            )?;
+        }
+        match self.hooks {
+            0 => Ok(()),
+            1 => writeln!(f, "  run 1 hook command"),
+            count => writeln!(f, "  run {count} hook commands"),
+        }
+    }
+}
+
+/// Adds a write to the audit log. Failing to log it is only logged, since the write
+/// itself succeeded.
+fn log_write(diff: &DockDiff, profile: Option<&str>) {
+    if let Err(err) = audit::record(diff, profile) {
+        tracing::warn!("{err:#}");
+    }
+}
+
+/// Enforces the config's backup retention after a write. Failing to prune is only
+/// logged, since the write itself succeeded.
+fn prune_backups(config: &Config) {
+    if config.backup.has_retention()
+        && let Err(err) = Snapshot::prune(&config.backup)
+    {
+        tracing::warn!("{err:#}");
+    }
+}
+
+/// Checks that the plist is still byte for byte the one `live` was loaded from before
+/// docktor's changes replace it, so nothing the Dock wrote in between, such as its
+/// recent apps, is lost.
+///
+/// Returns `None` when it is, or when `live` wasn't loaded from the plist being
+/// written. If something else wrote the plist in the meantime, fails, or with
+/// [`ConflictPolicy::Reapply`] returns `diff` replayed on the newer plist.
+fn rebase(live: &Dock, diff: &DockDiff) -> Result<Option<Dock>> {
+    let path = Dock::default_path()?;
+    let Some(loaded) = live.loaded.as_ref().filter(|loaded| loaded.path() == path) else {
+        return Ok(None);
+    };
+    if !path.exists() || PlistStamp::of(&path)? == *loaded {
+        return Ok(None);
+    }
+    let current = Dock::load_from(&path)?;
+    match Config::current().on_conflict {
+        ConflictPolicy::Abort => bail!(
+            "The Dock changed while docktor was working on it; nothing was written. \
+             Run the command again, or set on_conflict = \"reapply\" in the config file"
+        ),
+        ConflictPolicy::Reapply => {
+            tracing::warn!("the Dock changed while docktor was working on it; reapplying");
+            Ok(Some(diff.replay(&current)))
+        }
+    }
+}
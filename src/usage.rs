@@ -0,0 +1,109 @@
+use crate::dock::{Dock, DockItem, Section};
+use anyhow::{Context, Result, bail};
+use std::path::Path;
+use std::process::Command;
+use time::macros::format_description;
+use time::{Duration, OffsetDateTime};
+
+/// When the item at `path` was last opened, according to Spotlight's `kMDItemLastUsedDate`.
+///
+/// Returns `None` if it has never been opened, isn't indexed, or `mdls` is unavailable,
+/// as it is outside macOS.
+pub fn last_used(path: &Path) -> Option<OffsetDateTime> {
+    if !cfg!(target_os = "macos") {
+        return None;
+    }
+    let output = Command::new("mdls")
+        .args(["-raw", "-name", "kMDItemLastUsedDate"])
+        .arg(path)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    // e.g. `2024-01-02 15:04:05 +0000`, or `(null)` when unset.
+    let raw = String::from_utf8_lossy(&output.stdout);
+    OffsetDateTime::parse(
+        raw.trim(),
+        format_description!(
+            "[year]-[month]-[day] [hour]:[minute]:[second] [offset_hour sign:mandatory][offset_minute]"
+        ),
+    )
+    .ok()
+}
+
+/// A short, human description of how long ago `then` was, such as `3 months ago`.
+pub fn describe_age(then: OffsetDateTime, now: OffsetDateTime) -> String {
+    let age = now - then;
+    let (count, unit) = if age < Duration::days(1) {
+        return "today".to_string();
+    } else if age < Duration::days(31) {
+        (age.whole_days(), "day")
+    } else if age < Duration::days(365) {
+        (age.whole_days() / 30, "month")
+    } else {
+        (age.whole_days() / 365, "year")
+    };
+    format!("{count} {unit}{} ago", if count == 1 { "" } else { "s" })
+}
+
+impl DockItem {
+    /// When the app or file this tile points at was last opened, per Spotlight.
+    pub fn last_used(&self) -> Option<OffsetDateTime> {
+        last_used(&self.path()?)
+    }
+}
+
+/// Parses a period such as `90d`, `12w`, `6m`, or `1y` (months are 30 days, years 365).
+///
+/// The count must be positive, and the period no longer than a [`Duration`] can hold.
+pub fn parse_period(s: &str) -> Result<Duration> {
+    let split = s.len() - s.chars().last().map_or(0, char::len_utf8);
+    let (count, unit) = s.split_at(split);
+    let count: i64 = count
+        .parse()
+        .with_context(|| format!("Invalid period {s:?}; use a number and unit, e.g. 90d"))?;
+    let days = match unit {
+        "d" => 1,
+        "w" => 7,
+        "m" => 30,
+        "y" => 365,
+        _ => bail!("Invalid period {s:?}; the unit must be d, w, m, or y"),
+    };
+    if count <= 0 {
+        bail!("Invalid period {s:?}; it must be longer than zero");
+    }
+    count
+        .checked_mul(days)
+        .and_then(|days| days.checked_mul(Duration::DAY.whole_seconds()))
+        .map(Duration::seconds)
+        .with_context(|| format!("Invalid period {s:?}; it is too long"))
+}
+
+impl Dock {
+    /// Lists pinned apps not opened within `period`, with their index and last-used date.
+    ///
+    /// Apps Spotlight has no date for are left out, since that usually means they
+    /// aren't indexed rather than never opened.
+    ///
+    /// Fails if `period` reaches back further than a date can.
+    pub fn unused_apps(&self, period: Duration) -> Result<Vec<(usize, &DockItem, OffsetDateTime)>> {
+        let cutoff = OffsetDateTime::now_utc()
+            .checked_sub(period)
+            .with_context(|| {
+                format!(
+                    "A period of {} days reaches back too far",
+                    period.whole_days()
+                )
+            })?;
+        Ok(self
+            .section(Section::Apps)
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| !item.is_spacer())
+            .filter_map(|(index, item)| Some((index, item, item.last_used()?)))
+            .filter(|(_, _, used)| *used < cutoff)
+            .collect())
+    }
+}
@@ -0,0 +1,224 @@
+//! The local user accounts on this Mac, for applying a profile to all of them at once.
+//!
+//! Accounts come from Directory Services through `dscl`. Writing another user's Dock
+//! needs root; the plist is handed back to its owner afterwards.
+
+use crate::config::Config;
+use crate::diff::DockDiff;
+use crate::dock::Dock;
+use crate::macos;
+use crate::profile::{ApplyOptions, Profile};
+use anyhow::{Context, Result, bail};
+use std::collections::HashMap;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Accounts below this UID belong to the system rather than to people.
+const FIRST_USER_UID: u32 = 500;
+
+/// A local account someone logs in to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalUser {
+    /// The short name, e.g. `alice`.
+    pub name: String,
+
+    /// The numeric user ID.
+    pub uid: u32,
+
+    /// The numeric ID of the user's primary group.
+    pub gid: u32,
+
+    /// The user's home folder.
+    pub home: PathBuf,
+}
+
+impl LocalUser {
+    /// Where this user's Dock preferences are stored.
+    pub fn dock_path(&self) -> PathBuf {
+        self.home.join("Library/Preferences/com.apple.dock.plist")
+    }
+
+    /// Writes `dock` as the user's Dock plist, owned by the user.
+    ///
+    /// This runs as root inside a folder the user controls, so nothing there is trusted:
+    /// the folder must really be the user's `~/Library/Preferences`, the temporary file
+    /// gets a fresh name and is never opened through an existing path or symlink, and
+    /// it's handed to the user through its open handle before being renamed into place.
+    fn save_dock(&self, dock: &Dock) -> Result<()> {
+        let path = self.dock_path();
+        let dir = path
+            .parent()
+            .context("The Dock plist has no parent folder")?;
+        let expected = std::fs::canonicalize(&self.home)
+            .with_context(|| format!("Failed to resolve {}", self.home.display()))?
+            .join("Library/Preferences");
+        if std::fs::canonicalize(dir).ok().as_deref() != Some(expected.as_path()) {
+            bail!(
+                "{} isn't {}'s own Preferences folder; refusing to write there",
+                dir.display(),
+                self.name
+            );
+        }
+
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .subsec_nanos();
+        let tmp_path = expected.join(format!(
+            "com.apple.dock.plist.docktor-{}-{nanos:09}",
+            std::process::id()
+        ));
+        let file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&tmp_path)
+            .with_context(|| format!("Failed to create {}", tmp_path.display()))?;
+        let written = std::os::unix::fs::fchown(&file, Some(self.uid), Some(self.gid))
+            .with_context(|| format!("Failed to give {} to {}", tmp_path.display(), self.name))
+            .and_then(|()| {
+                dock.write_to(
+                    std::io::BufWriter::new(file),
+                    Config::current().plist_format,
+                )
+            })
+            .and_then(|file| Ok(file.sync_all()?))
+            .with_context(|| format!("Failed to write Dock plist to {}", tmp_path.display()))
+            .and_then(|()| {
+                std::fs::rename(&tmp_path, expected.join("com.apple.dock.plist"))
+                    .with_context(|| format!("Failed to replace Dock plist at {}", path.display()))
+            });
+        if written.is_err() {
+            let _ = std::fs::remove_file(&tmp_path);
+        }
+        written
+    }
+
+    /// Whether the user is logged in, judged by whether their Dock is running.
+    pub fn is_logged_in(&self) -> bool {
+        Command::new("pgrep")
+            .args(["-x", "-u", &self.uid.to_string(), "Dock"])
+            .output()
+            .is_ok_and(|output| output.status.success())
+    }
+
+    /// Restarts this user's Dock so it picks up a changed plist.
+    pub fn restart_dock(&self) -> Result<()> {
+        macos::ensure("Restarting another user's Dock")?;
+        let status = Command::new("killall")
+            .args(["-u", &self.name, "Dock"])
+            .status()
+            .with_context(|| format!("Failed to restart the Dock for {}", self.name))?;
+        if !status.success() {
+            bail!("Failed to restart the Dock for {}", self.name);
+        }
+        Ok(())
+    }
+}
+
+/// Lists the local accounts people log in to, sorted by name.
+///
+/// System accounts (a UID below 500 or a name starting with `_`) and accounts whose
+/// home folder doesn't exist are left out.
+pub fn local_users() -> Result<Vec<LocalUser>> {
+    macos::ensure("Listing local users")?;
+    let uids = list_attribute("UniqueID")?;
+    let gids = list_attribute("PrimaryGroupID")?;
+    let homes = list_attribute("NFSHomeDirectory")?;
+
+    let mut users: Vec<LocalUser> = uids
+        .into_iter()
+        .filter(|(name, _)| !name.starts_with('_'))
+        .filter_map(|(name, uid)| {
+            let uid: u32 = uid.parse().ok().filter(|uid| *uid >= FIRST_USER_UID)?;
+            let gid = gids.get(&name)?.parse().ok()?;
+            let home = PathBuf::from(homes.get(&name)?);
+            home.is_dir().then_some(LocalUser {
+                name,
+                uid,
+                gid,
+                home,
+            })
+        })
+        .collect();
+    users.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(users)
+}
+
+/// The user logged in at the screen, if anyone is.
+///
+/// At the login window the console belongs to root, which counts as nobody.
+pub fn console_user() -> Result<Option<LocalUser>> {
+    macos::ensure("Finding the logged-in user")?;
+    let output = Command::new("stat")
+        .args(["-f", "%Su", "/dev/console"])
+        .output()
+        .context("Failed to run stat")?;
+    let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || name.is_empty() || name == "root" {
+        return Ok(None);
+    }
+    Ok(local_users()?.into_iter().find(|user| user.name == name))
+}
+
+/// One attribute of every local account, keyed by short name.
+fn list_attribute(attribute: &str) -> Result<HashMap<String, String>> {
+    let output = Command::new("dscl")
+        .args([".", "-list", "/Users", attribute])
+        .output()
+        .context("Failed to run dscl")?;
+    if !output.status.success() {
+        bail!(
+            "Listing local users failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (name, value) = line.split_once(char::is_whitespace)?;
+            Some((name.to_string(), value.trim().to_string()))
+        })
+        .collect())
+}
+
+/// Whether docktor is running as root, which writing other users' Docks requires.
+pub fn is_root() -> bool {
+    Command::new("id")
+        .arg("-u")
+        .output()
+        .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).trim() == "0")
+}
+
+impl Profile {
+    /// Makes `user`'s Dock match this profile, returning what changed.
+    ///
+    /// `~/` in the profile means `user`'s home folder. The plist is written as root and
+    /// given to `user` before it's moved into place, and their Dock is restarted only if
+    /// they are logged in. Neither hooks nor snapshots run for other users, and a user
+    /// who hasn't logged in yet, and so has no Dock plist, is an error.
+    pub fn apply_for(&self, user: &LocalUser, options: ApplyOptions) -> Result<DockDiff> {
+        let path = user.dock_path();
+        if !path.exists() {
+            bail!(
+                "{} has no Dock preferences yet; the Dock creates them at first login",
+                user.name
+            );
+        }
+        let live = Dock::load_from(&path)?;
+        let desired = self
+            .with_home(&user.home)
+            .apply_to_with(&live, options.unmounted)?;
+        let diff = DockDiff::between(&live, &desired);
+        if options.dry_run || (diff.is_empty() && !options.force) {
+            return Ok(diff);
+        }
+
+        user.save_dock(&desired)?;
+        if Config::current().restarts(options.no_restart) && user.is_logged_in() {
+            user.restart_dock()?;
+        }
+        Ok(diff)
+    }
+}
@@ -0,0 +1,297 @@
+use crate::dock;
+use crate::mac_app::MacApp;
+use crate::paths::VolumeState;
+use crate::profile::{self, Profile};
+use crate::settings::Setting;
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+
+/// Something wrong with a profile, with where it is in the file when known.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    /// What is wrong, phrased for the person editing the profile.
+    pub message: String,
+
+    /// The 1-based line the problem is on.
+    pub line: Option<usize>,
+
+    /// The 1-based column the problem starts at.
+    pub column: Option<usize>,
+
+    /// For an item that couldn't be found, whether its volume is mounted.
+    ///
+    /// Items on an unmounted volume aren't necessarily broken; they may be back
+    /// once it is mounted again.
+    pub volume: Option<VolumeState>,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => {
+                write!(f, "line {line}, column {column}: {}", self.message)
+            }
+            (Some(line), None) => write!(f, "line {line}: {}", self.message),
+            _ => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl Problem {
+    pub(crate) fn new(message: impl Into<String>) -> Self {
+        Problem {
+            message: message.into(),
+            line: None,
+            column: None,
+            volume: None,
+        }
+    }
+
+    /// A problem with an item that couldn't be found, noting if its volume is unmounted.
+    fn missing(message: impl Into<String>, candidates: &[String]) -> Self {
+        let volume = candidates
+            .iter()
+            .map(|candidate| VolumeState::of(&profile::expand_home(candidate)))
+            .find(VolumeState::is_unmounted);
+        match volume {
+            Some(VolumeState::Unmounted(mount_point)) => Problem {
+                volume: Some(VolumeState::Unmounted(mount_point.clone())),
+                ..Problem::new(format!(
+                    "{}; {} isn't mounted",
+                    message.into(),
+                    mount_point.display()
+                ))
+            },
+            _ => Problem::new(message),
+        }
+    }
+
+    /// Whether this is only a missing item on a volume that isn't mounted.
+    pub fn is_unmounted(&self) -> bool {
+        self.volume.as_ref().is_some_and(VolumeState::is_unmounted)
+    }
+}
+
+/// Reads and validates the profile at `path`, locating each problem in the file.
+///
+/// Only fails if the file can't be read; an unparseable profile is reported as a problem.
+pub fn validate_file<P: AsRef<Path>>(path: P) -> Result<Vec<Problem>> {
+    validate_file_with(path, false)
+}
+
+/// Like [`validate_file`]; with `strict`, an entry that falls back to a later
+/// alternative or is optional and missing is also a problem.
+pub fn validate_file_with<P: AsRef<Path>>(path: P, strict: bool) -> Result<Vec<Problem>> {
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read profile at {}", path.display()))?;
+
+    let mut profile: Profile = match serde_yaml::from_str(&source) {
+        Ok(profile) => profile,
+        Err(err) => {
+            let location = err.location();
+            // serde_yaml appends the location to the message; we report it separately.
+            let message = err.to_string();
+            let message = match message.rsplit_once(" at line ") {
+                Some((message, _)) if location.is_some() => message.to_string(),
+                _ => message,
+            };
+            return Ok(vec![Problem {
+                message,
+                line: location.as_ref().map(|l| l.line()),
+                column: location.as_ref().map(|l| l.column()),
+                volume: None,
+            }]);
+        }
+    };
+
+    profile.source = Some(path.to_path_buf());
+    let mut problems = match profile.resolve() {
+        Ok(profile) if strict => {
+            let mut problems = profile.validate();
+            problems.extend(profile.fallbacks());
+            problems
+        }
+        Ok(profile) => profile.validate(),
+        Err(err) => vec![Problem::new(format!("{err:#}"))],
+    };
+    for problem in &mut problems {
+        locate(problem, &source);
+    }
+    Ok(problems)
+}
+
+/// Points a problem at the first line mentioning the value it quotes, if any.
+pub(crate) fn locate(problem: &mut Problem, source: &str) {
+    let Some(quoted) = problem.message.split('"').nth(1) else {
+        return;
+    };
+    for (number, line) in source.lines().enumerate() {
+        if let Some(column) = line.find(quoted) {
+            problem.line = Some(number + 1);
+            problem.column = Some(column + 1);
+            return;
+        }
+    }
+}
+
+impl Profile {
+    /// Checks the profile against this machine without touching the Dock.
+    ///
+    /// Reports entries that don't resolve to an installed app or existing file,
+    /// duplicates, and rules that contradict each other.
+    pub fn validate(&self) -> Vec<Problem> {
+        let mut problems = Vec::new();
+        let mut seen = HashSet::new();
+
+        for entry in self.apps.iter().flatten() {
+            if entry.spacer_kind().is_some() {
+                continue;
+            }
+            for candidate in entry.candidates() {
+                if !seen.insert(candidate.as_str()) {
+                    problems.push(Problem::new(format!(
+                        "\"{candidate}\" is listed more than once"
+                    )));
+                }
+            }
+            if let Err(err) = entry.resolve_app() {
+                problems.push(Problem::missing(
+                    format!("App \"{entry}\" can't be pinned: {err:#}"),
+                    entry.candidates(),
+                ));
+            }
+        }
+
+        for entry in self.others.iter().flatten() {
+            if entry.spacer_kind().is_some() {
+                continue;
+            }
+            for candidate in entry.candidates() {
+                if !seen.insert(candidate.as_str()) {
+                    problems.push(Problem::new(format!(
+                        "\"{candidate}\" is listed more than once"
+                    )));
+                }
+            }
+            let present = entry.candidates().iter().any(|candidate| {
+                dock::is_network_share(candidate) || profile::expand_home(candidate).exists()
+            });
+            if !present && !entry.is_optional() {
+                problems.push(Problem::missing(
+                    format!("\"{entry}\" does not exist"),
+                    entry.candidates(),
+                ));
+            }
+        }
+
+        for entry in self.login_items.iter().flatten() {
+            if let Err(err) = entry.resolve_app() {
+                problems.push(Problem::missing(
+                    format!("Login item \"{entry}\" can't be found: {err:#}"),
+                    entry.candidates(),
+                ));
+            }
+        }
+
+        for addition in &self.add {
+            if seen.contains(addition.path.as_str()) {
+                problems.push(Problem::new(format!(
+                    "\"{}\" is in both `add` and `apps`/`others`; the `add` position wins",
+                    addition.path
+                )));
+            }
+            if !profile::expand_home(&addition.path).exists() {
+                problems.push(Problem::missing(
+                    format!("\"{}\" does not exist", addition.path),
+                    std::slice::from_ref(&addition.path),
+                ));
+            }
+        }
+
+        if let Some(grouping) = &self.group_by_category
+            && profile::spacer_kind(&grouping.spacer).is_none()
+        {
+            problems.push(Problem::new(format!(
+                "Unknown spacer \"{}\"; use spacer, small-spacer, or flex-spacer",
+                grouping.spacer
+            )));
+        }
+
+        if let (Some(max_items), Some(apps)) = (&self.max_items, &self.apps) {
+            let count = apps
+                .iter()
+                .filter(|entry| entry.spacer_kind().is_none())
+                .count();
+            if count > max_items.limit {
+                problems.push(Problem::new(format!(
+                    "The profile lists {count} apps but max_items.limit is {}; {} will be dropped on every apply",
+                    max_items.limit,
+                    count - max_items.limit
+                )));
+            }
+        }
+
+        if !self.separator_after.is_empty() {
+            for err in [self.separator_groups().err(), self.separator_kind().err()]
+                .into_iter()
+                .flatten()
+            {
+                problems.push(Problem::new(format!("{err:#}")));
+            }
+        }
+
+        for (key, value) in &self.settings {
+            if let Some(setting) = Setting::for_key(key)
+                && let Err(err) = setting.normalize(value)
+            {
+                problems.push(Problem::new(format!("Setting \"{key}\": {err:#}")));
+            }
+        }
+
+        problems
+    }
+
+    /// Reports entries whose first choice isn't present but which still pass
+    /// [`Profile::validate`], by falling back or by being optional.
+    pub fn fallbacks(&self) -> Vec<Problem> {
+        let mut problems = Vec::new();
+        let sections = [
+            (self.apps.iter().flatten(), true),
+            (self.others.iter().flatten(), false),
+        ];
+        for (entries, apps) in sections {
+            for entry in entries {
+                let [first, rest @ ..] = entry.candidates() else {
+                    continue;
+                };
+                if entry.spacer_kind().is_some() {
+                    continue;
+                }
+                let present = |candidate: &String| {
+                    if apps {
+                        MacApp::resolve(candidate).is_ok()
+                    } else {
+                        dock::is_network_share(candidate)
+                            || profile::expand_home(candidate).exists()
+                    }
+                };
+                if present(first) {
+                    continue;
+                }
+                if let Some(used) = rest.iter().find(|candidate| present(candidate)) {
+                    problems.push(Problem::new(format!(
+                        "\"{first}\" is not present; falling back to \"{used}\""
+                    )));
+                } else if entry.is_optional() {
+                    problems.push(Problem::new(format!(
+                        "Optional \"{entry}\" is not present and will be skipped"
+                    )));
+                }
+            }
+        }
+        problems
+    }
+}
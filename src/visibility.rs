@@ -0,0 +1,39 @@
+//! Turning autohide on or off on the spot, without restarting the Dock, for
+//! `docktor autohide --live`.
+//!
+//! Goes through System Events' Dock preferences, which the Dock picks up immediately.
+//! This is the same persistent `autohide` preference `docktor autohide` writes; macOS
+//! has no public way to hide the Dock for the moment only. docktor doesn't write the
+//! plist, so there's no snapshot, audit entry, or restart. The
+//! [record of the last save](crate::state) is updated to match, so `docktor status`
+//! doesn't mistake the change for an outside edit. The first use asks for permission to
+//! control System Events.
+
+use anyhow::{Result, bail};
+
+/// Turns autohide on, hiding the Dock until the pointer reaches its edge, or off.
+#[tracing::instrument]
+pub fn set_hidden(hidden: bool) -> Result<()> {
+    run(&format!(
+        "tell application \"System Events\" to set autohide of dock preferences to {hidden}"
+    ))?;
+    if let Err(err) = crate::state::record_autohide(hidden) {
+        tracing::warn!("Could not record the Dock's autohide: {err:#}");
+    }
+    Ok(())
+}
+
+/// Whether autohide is on right now.
+pub fn is_hidden() -> Result<bool> {
+    let output = run("tell application \"System Events\" to get autohide of dock preferences")?;
+    match output.as_str() {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => bail!("System Events said the Dock's autohide is {output:?}"),
+    }
+}
+
+/// Runs an AppleScript against System Events, returning what it printed.
+fn run(script: &str) -> Result<String> {
+    crate::macos::osascript(script, "Changing autohide through System Events")
+}